@@ -0,0 +1,998 @@
+//! A compact binary format for reflected types, driven directly by reflection.
+//!
+//! The format is schema-less: nothing but the values themselves is written, so both sides have to
+//! agree on the types.
+//!
+//! - unsigned integers are LEB128 varints, signed integers are zigzag encoded first, while `u8`
+//!   and `i8` are single bytes
+//! - floats are little-endian, `bool` is a byte, `char` is a varint
+//! - strings, sequences and maps are their length as a varint followed by their elements
+//! - `Option` is a byte, `0` for `None` and `1` for `Some`, followed by the value
+//! - arrays and tuples are their elements
+//! - structs are their fields in `INDEX` order, where flattened fields are simply nested
+//! - enums are the variant's `INDEX` as a varint, followed by its fields
+//! - transparent structs are their inner field
+//! - types with `#[reflect(version = ..)]` start with the version as a varint. Fields which were
+//!   added later, `#[reflect(since = ..)]`, or removed, `#[reflect(removed = ..)]`, are read
+//!   according to that version, getting their default when they are missing
+//!
+//! Decoding borrows from the input where it can, so `&[u8]`, `&str` and `Cow` fields are decoded
+//! without copying. Nested types are encoded through [`Encode`] and [`Decode`], which are
+//! implemented for std types and for reflected types marked
+//! `#[reflect(impl(reflector_binary::Encode, reflector_binary::Decode))]`, while fields with an
+//! adapter of `reflector_serde::with` are encoded through [`EncodeAs`] and [`DecodeAs`].
+
+pub use error::Error;
+pub use io::{Reader, Writer};
+pub use reflect::{ReflectDecode, ReflectEncode};
+
+/// Encodes any type which can be introspected.
+pub fn to_vec<T: ReflectEncode + ?Sized>(value: &T) -> Vec<u8> {
+    let mut writer = Writer::default();
+    ReflectEncode::encode(value, &mut writer);
+    writer.into_inner()
+}
+
+/// Decodes any type which can be introspected, which must use all of `bytes`.
+pub fn from_slice<'de, T: ReflectDecode<'de>>(bytes: &'de [u8]) -> Result<T, Error> {
+    let mut reader = Reader::new(bytes);
+    let value = T::decode(&mut reader)?;
+    match reader.remaining() {
+        0 => Ok(value),
+        n => Err(Error::TrailingBytes(n)),
+    }
+}
+
+pub trait Encode {
+    fn encode(&self, writer: &mut Writer);
+}
+
+pub trait Decode<'de>: Sized {
+    fn decode(reader: &mut Reader<'de>) -> Result<Self, Error>;
+}
+
+/// Implements [`Encode`] for a reflected type through [`ReflectEncode`], with
+/// `#[reflect(impl(reflector_binary::Encode))]`. Type parameters have to implement `Encode`.
+#[macro_export]
+macro_rules! Encode {
+    (
+        lifetimes [$($lifetime:lifetime),*]
+        types [$($param:ident),*]
+        impl [$($params:tt)*] $ty:ty
+        where [$($where:tt)*]
+    ) => {
+        impl<$($params)*> $crate::Encode for $ty
+        where
+            $($where)*
+            $($param: $crate::Encode,)*
+        {
+            fn encode(&self, writer: &mut $crate::Writer) {
+                <Self as $crate::ReflectEncode>::encode(self, writer)
+            }
+        }
+    };
+}
+
+/// Implements [`Decode`] for a reflected type through [`ReflectDecode`], with
+/// `#[reflect(impl(reflector_binary::Decode))]`. Type parameters have to implement `Decode`, and
+/// the input outlives all lifetimes of the type.
+#[macro_export]
+macro_rules! Decode {
+    (
+        lifetimes [$($lifetime:lifetime),*]
+        types [$($param:ident),*]
+        impl [$($params:tt)*] $ty:ty
+        where [$($where:tt)*]
+    ) => {
+        impl<'__de, $($params)*> $crate::Decode<'__de> for $ty
+        where
+            $('__de: $lifetime,)*
+            $($where)*
+            $($param: $crate::Decode<'__de>,)*
+        {
+            fn decode(reader: &mut $crate::Reader<'__de>) -> ::core::result::Result<Self, $crate::Error> {
+                <Self as $crate::ReflectDecode<'__de>>::decode(reader)
+            }
+        }
+    };
+}
+
+/// Encodes `T` on behalf of its own [`Encode`] impl, for an adapter of `reflector_serde::with`.
+pub trait EncodeAs<T: ?Sized> {
+    fn encode_as(value: &T, writer: &mut Writer);
+}
+
+/// Decodes `T` on behalf of its own [`Decode`] impl, for an adapter of `reflector_serde::with`.
+pub trait DecodeAs<'de, T> {
+    fn decode_as(reader: &mut Reader<'de>) -> Result<T, Error>;
+}
+
+mod io {
+    use crate::Error;
+
+    /// The output of encoding.
+    #[derive(Default)]
+    pub struct Writer {
+        bytes: Vec<u8>,
+    }
+
+    impl Writer {
+        pub fn byte(&mut self, byte: u8) {
+            self.bytes.push(byte);
+        }
+
+        pub fn bytes(&mut self, bytes: &[u8]) {
+            self.bytes.extend_from_slice(bytes);
+        }
+
+        /// Writes a LEB128 varint, 7 bits per byte with the highest bit set on all but the last
+        pub fn varint(&mut self, mut value: u128) {
+            while value >= 0x80 {
+                self.bytes.push(value as u8 | 0x80);
+                value >>= 7;
+            }
+            self.bytes.push(value as u8);
+        }
+
+        /// Writes the length of a string, sequence or map
+        pub fn length(&mut self, len: usize) {
+            self.varint(len as u128);
+        }
+
+        pub fn into_inner(self) -> Vec<u8> {
+            self.bytes
+        }
+    }
+
+    /// The input of decoding, which decoded values may borrow from.
+    pub struct Reader<'de> {
+        bytes: &'de [u8],
+    }
+
+    impl<'de> Reader<'de> {
+        pub fn new(bytes: &'de [u8]) -> Self {
+            Self { bytes }
+        }
+
+        pub fn remaining(&self) -> usize {
+            self.bytes.len()
+        }
+
+        pub fn byte(&mut self) -> Result<u8, Error> {
+            let (&byte, rest) = self.bytes.split_first().ok_or(Error::UnexpectedEnd)?;
+            self.bytes = rest;
+            Ok(byte)
+        }
+
+        pub fn bytes(&mut self, len: usize) -> Result<&'de [u8], Error> {
+            if len > self.bytes.len() {
+                return Err(Error::UnexpectedEnd);
+            }
+            let (bytes, rest) = self.bytes.split_at(len);
+            self.bytes = rest;
+            Ok(bytes)
+        }
+
+        pub fn array<const N: usize>(&mut self) -> Result<[u8; N], Error> {
+            Ok(self.bytes(N)?.try_into().unwrap())
+        }
+
+        /// Reads a LEB128 varint which must fit into `bits` bits
+        pub fn varint(&mut self, bits: u32) -> Result<u128, Error> {
+            let (mut value, mut shift) = (0u128, 0);
+            loop {
+                let byte = self.byte()?;
+                let payload = (byte & 0x7f) as u128;
+                // the payload has to fit into the remaining bits
+                if shift >= bits || (bits - shift < 7 && payload >> (bits - shift) != 0) {
+                    return Err(Error::Overflow);
+                }
+                value |= payload << shift;
+                if byte & 0x80 == 0 {
+                    return Ok(value);
+                }
+                shift += 7;
+            }
+        }
+
+        /// Reads the length of a string, sequence or map
+        pub fn length(&mut self) -> Result<usize, Error> {
+            usize::try_from(self.varint(usize::BITS)?).map_err(|_| Error::Overflow)
+        }
+    }
+}
+
+mod error {
+    use std::fmt::{Display, Formatter};
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum Error {
+        /// The input ended in the middle of a value
+        UnexpectedEnd,
+        /// The input was longer than the value
+        TrailingBytes(usize),
+        /// A varint which doesn't fit into its type
+        Overflow,
+        /// An invalid byte for a `bool` or `Option`
+        InvalidTag(u8),
+        /// A variant index which doesn't belong to the enum
+        InvalidVariant {
+            ident: &'static str,
+            index: u128,
+        },
+        /// Data written by a later version of a type, `#[reflect(version = ..)]`
+        UnsupportedVersion {
+            ident: &'static str,
+            version: u32,
+        },
+        InvalidChar(u32),
+        InvalidUtf8,
+        /// An error of the type being decoded, e.g. by `FromStr`
+        Custom(String),
+    }
+
+    impl Display for Error {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            match self {
+                Error::UnexpectedEnd => f.write_str("unexpected end of input"),
+                Error::TrailingBytes(n) => write!(f, "{n} trailing bytes"),
+                Error::Overflow => f.write_str("integer overflow"),
+                Error::InvalidTag(tag) => write!(f, "invalid tag {tag}"),
+                Error::InvalidVariant { ident, index } => {
+                    write!(f, "invalid variant index {index} for enum {ident}")
+                }
+                Error::UnsupportedVersion { ident, version } => {
+                    write!(f, "unsupported version {version} of {ident}")
+                }
+                Error::InvalidChar(c) => write!(f, "invalid char {c:#x}"),
+                Error::InvalidUtf8 => f.write_str("invalid UTF-8"),
+                Error::Custom(message) => f.write_str(message),
+            }
+        }
+    }
+
+    impl std::error::Error for Error {}
+}
+
+mod reflect {
+    use reflector::*;
+
+    use crate::{Decode, DecodeAs, Encode, EncodeAs, Error, Reader, Writer};
+
+    /// Encodes reflected types, implemented for any type which can be introspected and whose
+    /// fields implement [`Encode`], or [`EncodeAs`] for their adapter.
+    pub trait ReflectEncode {
+        fn encode(&self, writer: &mut Writer);
+    }
+
+    impl<T> ReflectEncode for T
+    where
+        T: Introspect<Root = T>,
+        T: EncodeKind<T::Kind>,
+    {
+        fn encode(&self, writer: &mut Writer) {
+            EncodeKind::encode(self, writer)
+        }
+    }
+
+    /// Decodes reflected types, implemented for any type which can be introspected and whose
+    /// fields implement [`Decode`], or [`DecodeAs`] for their adapter.
+    pub trait ReflectDecode<'de>: Sized {
+        fn decode(reader: &mut Reader<'de>) -> Result<Self, Error>;
+    }
+
+    impl<'de, T> ReflectDecode<'de> for T
+    where
+        T: Introspect<Root = T>,
+        T: DecodeKind<'de, T::Kind>,
+    {
+        fn decode(reader: &mut Reader<'de>) -> Result<Self, Error> {
+            <T as DecodeKind<_>>::decode(reader)
+        }
+    }
+
+    pub trait EncodeKind<Kind> {
+        fn encode(&self, writer: &mut Writer);
+    }
+
+    pub trait DecodeKind<'de, Kind>: Sized {
+        fn decode(reader: &mut Reader<'de>) -> Result<Self, Error>;
+    }
+
+    impl<T> EncodeKind<StructKind> for T
+    where
+        T: Struct<Root = T>,
+        T: EncodeRepr<T::Repr>,
+    {
+        fn encode(&self, writer: &mut Writer) {
+            EncodeRepr::encode(self, writer)
+        }
+    }
+
+    impl<'de, T> DecodeKind<'de, StructKind> for T
+    where
+        T: Struct<Root = T>,
+        T: DecodeRepr<'de, T::Repr>,
+    {
+        fn decode(reader: &mut Reader<'de>) -> Result<Self, Error> {
+            <T as DecodeRepr<_>>::decode(reader)
+        }
+    }
+
+    // the variant's index, followed by its fields
+    impl<T> EncodeKind<EnumKind> for T
+    where
+        T: Enum<Root = T>,
+        T::Variants: EncodeVariants<T>,
+    {
+        fn encode(&self, writer: &mut Writer) {
+            write_version::<T>(writer);
+            T::Variants::encode(self, writer)
+        }
+    }
+
+    impl<'de, T> DecodeKind<'de, EnumKind> for T
+    where
+        T: Enum<Root = T>,
+        T::Variants: DecodeVariants<'de, T>,
+    {
+        fn decode(reader: &mut Reader<'de>) -> Result<Self, Error> {
+            let version = read_version::<T>(reader)?;
+            let index = reader.varint(u32::BITS)?;
+            match u32::try_from(index) {
+                Ok(index) => T::Variants::decode(index, version, reader),
+                Err(_) => Err(Error::InvalidVariant {
+                    ident: T::IDENT,
+                    index,
+                }),
+            }
+        }
+    }
+
+    pub trait EncodeRepr<Repr> {
+        fn encode(&self, writer: &mut Writer);
+    }
+
+    pub trait DecodeRepr<'de, Repr>: Sized {
+        fn decode(reader: &mut Reader<'de>) -> Result<Self, Error>;
+    }
+
+    impl<T> EncodeRepr<PlainRepr> for T
+    where
+        T: Struct<Root = T, Fields: EncodeFields<T>>,
+    {
+        fn encode(&self, writer: &mut Writer) {
+            write_version::<T>(writer);
+            T::Fields::encode(self, writer)
+        }
+    }
+
+    impl<'de, T> DecodeRepr<'de, PlainRepr> for T
+    where
+        T: SizedStruct<Root = T, Fields: DecodeFields<'de, Values = T::FieldTypes>>,
+    {
+        fn decode(reader: &mut Reader<'de>) -> Result<Self, Error> {
+            let version = read_version::<T>(reader)?;
+            T::Fields::decode(version, reader).map(T::from_values)
+        }
+    }
+
+    // versioned types, `#[reflect(version = ..)]`, start with the version they were written in
+    fn write_version<T: Introspect>(writer: &mut Writer) {
+        if T::VERSION > 0 {
+            writer.varint(T::VERSION.into());
+        }
+    }
+
+    fn read_version<T: Introspect>(reader: &mut Reader<'_>) -> Result<u32, Error> {
+        if T::VERSION == 0 {
+            return Ok(0);
+        }
+        match reader.varint(u32::BITS)? as u32 {
+            version if version <= T::VERSION => Ok(version),
+            version => Err(Error::UnsupportedVersion {
+                ident: T::IDENT,
+                version,
+            }),
+        }
+    }
+
+    // #[reflect(transparent)] struct T(A, PhantomData<..>);
+    impl<T> EncodeRepr<TransparentRepr> for T
+    where
+        T: TransparentStruct<Root = T>,
+        T::Inner: EncodeField<<T::Inner as Field>::Repr>,
+    {
+        fn encode(&self, writer: &mut Writer) {
+            T::Inner::encode(self, writer)
+        }
+    }
+
+    impl<'de, T> DecodeRepr<'de, TransparentRepr> for T
+    where
+        T: TransparentStruct<Root = T>,
+        T::Inner: DecodeField<'de, <T::Inner as Field>::Repr>,
+    {
+        fn decode(reader: &mut Reader<'de>) -> Result<Self, Error> {
+            T::Inner::decode(reader).map(T::from_inner)
+        }
+    }
+
+    // helper traits implemented recursively for a list of fields, e.g `(Field0, (Field1, ()))`
+    pub trait EncodeFields<Root> {
+        fn encode(root: &Root, writer: &mut Writer);
+    }
+    pub trait DecodeFields<'de> {
+        type Values;
+
+        /// Decodes the fields of the given version of their root
+        fn decode(version: u32, reader: &mut Reader<'de>) -> Result<Self::Values, Error>;
+    }
+    // end of the recursion
+    impl<Root> EncodeFields<Root> for () {
+        fn encode(_: &Root, _: &mut Writer) {}
+    }
+    impl DecodeFields<'_> for () {
+        type Values = ();
+
+        fn decode(_: u32, _: &mut Reader<'_>) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+    impl<Root, Head, Tail> EncodeFields<Root> for Cons<Head, Tail>
+    where
+        Head: Field<Root = Root> + EncodeField<<Head as Field>::Repr>,
+        Tail: EncodeFields<Root>,
+    {
+        fn encode(root: &Root, writer: &mut Writer) {
+            // fields marked `#[reflect(removed = ..)]` are no longer written
+            if Head::REMOVED.is_none() {
+                Head::encode(root, writer);
+            }
+            Tail::encode(root, writer)
+        }
+    }
+    impl<'de, Head, Tail> DecodeFields<'de> for Cons<Head, Tail>
+    where
+        Head: Field<Type: Sized> + DecodeField<'de, <Head as Field>::Repr>,
+        Tail: DecodeFields<'de>,
+    {
+        type Values = Cons<Head::Type, Tail::Values>;
+
+        fn decode(version: u32, reader: &mut Reader<'de>) -> Result<Self::Values, Error> {
+            let head = match Head::in_version(version) {
+                true => Head::decode(reader)?,
+                // the derive provides a default for fields with `since` or `removed`
+                false => Head::default_value().expect("field without a default"),
+            };
+            Ok(Cons(head, Tail::decode(version, reader)?))
+        }
+    }
+
+    // helper traits implemented for every field, depending on its representation
+    pub trait EncodeField<Repr>: Field {
+        fn encode(root: &Self::Root, writer: &mut Writer) {
+            // only fails for inactive variants, which are never encoded
+            let value = Self::try_get_ref(root).expect("field of an inactive variant");
+            Self::encode_value(value, writer)
+        }
+
+        fn encode_value(value: &Self::Type, writer: &mut Writer);
+    }
+    pub trait DecodeField<'de, Repr>: Field<Type: Sized> {
+        fn decode(reader: &mut Reader<'de>) -> Result<Self::Type, Error>;
+    }
+
+    impl<F> EncodeField<PlainRepr> for F
+    where
+        F: Field<Type: Encode>,
+    {
+        fn encode_value(value: &F::Type, writer: &mut Writer) {
+            value.encode(writer)
+        }
+    }
+    impl<'de, F> DecodeField<'de, PlainRepr> for F
+    where
+        F: Field<Type: Decode<'de>>,
+    {
+        fn decode(reader: &mut Reader<'de>) -> Result<F::Type, Error> {
+            F::Type::decode(reader)
+        }
+    }
+
+    impl<F, A> EncodeField<WithRepr<A>> for F
+    where
+        F: Field,
+        A: EncodeAs<F::Type>,
+    {
+        fn encode_value(value: &F::Type, writer: &mut Writer) {
+            A::encode_as(value, writer)
+        }
+    }
+    impl<'de, F, A> DecodeField<'de, WithRepr<A>> for F
+    where
+        F: Field<Type: Sized>,
+        A: DecodeAs<'de, F::Type>,
+    {
+        fn decode(reader: &mut Reader<'de>) -> Result<F::Type, Error> {
+            A::decode_as(reader)
+        }
+    }
+
+    // the flattened struct is nested, like any other struct
+    impl<F, Inner> EncodeField<FlattenRepr> for F
+    where
+        F: Field<Type = Inner>,
+        Inner: Struct<Root = Inner, Fields: EncodeFields<Inner>>,
+    {
+        fn encode_value(value: &Inner, writer: &mut Writer) {
+            <Inner as EncodeRepr<PlainRepr>>::encode(value, writer)
+        }
+    }
+    impl<'de, F, Inner> DecodeField<'de, FlattenRepr> for F
+    where
+        F: Field<Type = Inner>,
+        Inner: SizedStruct<Root = Inner, Fields: DecodeFields<'de, Values = Inner::FieldTypes>>,
+    {
+        fn decode(reader: &mut Reader<'de>) -> Result<Inner, Error> {
+            <Inner as DecodeRepr<PlainRepr>>::decode(reader)
+        }
+    }
+
+    // helper traits implemented recursively for a list of variants, e.g `(Variant0, (Variant1, ()))`
+    pub trait EncodeVariants<Root> {
+        fn encode(root: &Root, writer: &mut Writer);
+    }
+    pub trait DecodeVariants<'de, Root> {
+        fn decode(index: u32, version: u32, reader: &mut Reader<'de>) -> Result<Root, Error>;
+    }
+    // end of the recursion
+    impl<Root> EncodeVariants<Root> for () {
+        fn encode(_: &Root, _: &mut Writer) {
+            unreachable!()
+        }
+    }
+    impl<'de, Root: Introspect> DecodeVariants<'de, Root> for () {
+        fn decode(index: u32, _: u32, _: &mut Reader<'de>) -> Result<Root, Error> {
+            Err(Error::InvalidVariant {
+                ident: Root::IDENT,
+                index: index.into(),
+            })
+        }
+    }
+    impl<Root, Head, Tail> EncodeVariants<Root> for Cons<Head, Tail>
+    where
+        Head: Variant<Root = Root, Fields: EncodeFields<Root>>,
+        Tail: EncodeVariants<Root>,
+    {
+        fn encode(root: &Root, writer: &mut Writer) {
+            if !Head::is_active(root) {
+                return Tail::encode(root, writer);
+            }
+            writer.varint(Head::INDEX.into());
+            Head::Fields::encode(root, writer)
+        }
+    }
+    impl<'de, Root, Head, Tail> DecodeVariants<'de, Root> for Cons<Head, Tail>
+    where
+        Head: Variant<Root = Root>,
+        Head: SizedStruct<Fields: DecodeFields<'de, Values = Head::FieldTypes>>,
+        Tail: DecodeVariants<'de, Root>,
+    {
+        fn decode(index: u32, version: u32, reader: &mut Reader<'de>) -> Result<Root, Error> {
+            if index != <Head as Variant>::INDEX {
+                return Tail::decode(index, version, reader);
+            }
+            Head::Fields::decode(version, reader).map(Head::from_values)
+        }
+    }
+}
+
+// the adapters of `reflector_serde::with`
+mod adapters {
+    use std::{fmt::Display, str::FromStr};
+
+    use reflector_serde::with::{Borrow, DisplayFromStr, Reflect, Same};
+
+    use crate::{
+        Decode, DecodeAs, Encode, EncodeAs, Error, Reader, ReflectDecode, ReflectEncode, Writer,
+    };
+
+    impl<T: Encode + ?Sized> EncodeAs<T> for Same {
+        fn encode_as(value: &T, writer: &mut Writer) {
+            value.encode(writer)
+        }
+    }
+    impl<'de, T: Decode<'de>> DecodeAs<'de, T> for Same {
+        fn decode_as(reader: &mut Reader<'de>) -> Result<T, Error> {
+            T::decode(reader)
+        }
+    }
+
+    impl<T: ReflectEncode + ?Sized> EncodeAs<T> for Reflect {
+        fn encode_as(value: &T, writer: &mut Writer) {
+            ReflectEncode::encode(value, writer)
+        }
+    }
+    impl<'de, T: ReflectDecode<'de>> DecodeAs<'de, T> for Reflect {
+        fn decode_as(reader: &mut Reader<'de>) -> Result<T, Error> {
+            T::decode(reader)
+        }
+    }
+
+    impl<T: Display + ?Sized> EncodeAs<T> for DisplayFromStr {
+        fn encode_as(value: &T, writer: &mut Writer) {
+            value.to_string().encode(writer)
+        }
+    }
+    impl<'de, T: FromStr<Err: Display>> DecodeAs<'de, T> for DisplayFromStr {
+        fn decode_as(reader: &mut Reader<'de>) -> Result<T, Error> {
+            <&str>::decode(reader)?
+                .parse()
+                .map_err(|e: T::Err| Error::Custom(e.to_string()))
+        }
+    }
+
+    // every impl of `Decode` borrows what it can
+    impl<T: Encode + ?Sized> EncodeAs<T> for Borrow {
+        fn encode_as(value: &T, writer: &mut Writer) {
+            value.encode(writer)
+        }
+    }
+    impl<'de, T: Decode<'de>> DecodeAs<'de, T> for Borrow {
+        fn decode_as(reader: &mut Reader<'de>) -> Result<T, Error> {
+            T::decode(reader)
+        }
+    }
+}
+
+mod impls {
+    use std::{
+        borrow::Cow,
+        collections::{BTreeMap, BTreeSet, HashMap, HashSet, LinkedList, VecDeque},
+        hash::{BuildHasher, Hash},
+        marker::PhantomData,
+        rc::Rc,
+        sync::Arc,
+        time::Duration,
+    };
+
+    use crate::{Decode, Encode, Error, Reader, Writer};
+
+    macro_rules! bytes {
+        ($($t:ty),*) => {$(
+            impl Encode for $t {
+                fn encode(&self, writer: &mut Writer) {
+                    writer.bytes(&self.to_le_bytes())
+                }
+            }
+            impl Decode<'_> for $t {
+                fn decode(reader: &mut Reader<'_>) -> Result<Self, Error> {
+                    reader.array().map(<$t>::from_le_bytes)
+                }
+            }
+        )*};
+    }
+
+    bytes!(u8, i8, f32, f64);
+
+    macro_rules! unsigned {
+        ($($t:ty),*) => {$(
+            impl Encode for $t {
+                fn encode(&self, writer: &mut Writer) {
+                    writer.varint(*self as u128)
+                }
+            }
+            impl Decode<'_> for $t {
+                fn decode(reader: &mut Reader<'_>) -> Result<Self, Error> {
+                    Ok(reader.varint(<$t>::BITS)? as $t)
+                }
+            }
+        )*};
+    }
+
+    unsigned!(u16, u32, u64, u128, usize);
+
+    // zigzag encoding maps small negative numbers to small varints: 0, -1, 1, -2, ..
+    macro_rules! signed {
+        ($($t:ty => $u:ty),*) => {$(
+            impl Encode for $t {
+                fn encode(&self, writer: &mut Writer) {
+                    let zigzag = (*self << 1) ^ (*self >> (<$t>::BITS - 1));
+                    writer.varint(zigzag as $u as u128)
+                }
+            }
+            impl Decode<'_> for $t {
+                fn decode(reader: &mut Reader<'_>) -> Result<Self, Error> {
+                    let zigzag = reader.varint(<$t>::BITS)? as $u;
+                    Ok((zigzag >> 1) as $t ^ -((zigzag & 1) as $t))
+                }
+            }
+        )*};
+    }
+
+    signed!(i16 => u16, i32 => u32, i64 => u64, i128 => u128, isize => usize);
+
+    impl Encode for bool {
+        fn encode(&self, writer: &mut Writer) {
+            writer.byte(*self as u8)
+        }
+    }
+    impl Decode<'_> for bool {
+        fn decode(reader: &mut Reader<'_>) -> Result<Self, Error> {
+            match reader.byte()? {
+                0 => Ok(false),
+                1 => Ok(true),
+                tag => Err(Error::InvalidTag(tag)),
+            }
+        }
+    }
+
+    impl Encode for char {
+        fn encode(&self, writer: &mut Writer) {
+            (*self as u32).encode(writer)
+        }
+    }
+    impl Decode<'_> for char {
+        fn decode(reader: &mut Reader<'_>) -> Result<Self, Error> {
+            let c = u32::decode(reader)?;
+            char::from_u32(c).ok_or(Error::InvalidChar(c))
+        }
+    }
+
+    impl Encode for str {
+        fn encode(&self, writer: &mut Writer) {
+            writer.length(self.len());
+            writer.bytes(self.as_bytes())
+        }
+    }
+    impl<'de: 'a, 'a> Decode<'de> for &'a str {
+        fn decode(reader: &mut Reader<'de>) -> Result<Self, Error> {
+            let len = reader.length()?;
+            str::from_utf8(reader.bytes(len)?).map_err(|_| Error::InvalidUtf8)
+        }
+    }
+
+    impl Encode for String {
+        fn encode(&self, writer: &mut Writer) {
+            self.as_str().encode(writer)
+        }
+    }
+    impl Decode<'_> for String {
+        fn decode(reader: &mut Reader<'_>) -> Result<Self, Error> {
+            <&str>::decode(reader).map(str::to_owned)
+        }
+    }
+
+    // same as a sequence of `u8`
+    impl<'de: 'a, 'a> Decode<'de> for &'a [u8] {
+        fn decode(reader: &mut Reader<'de>) -> Result<Self, Error> {
+            let len = reader.length()?;
+            reader.bytes(len)
+        }
+    }
+
+    impl<'de: 'a, 'a> Decode<'de> for Cow<'a, str> {
+        fn decode(reader: &mut Reader<'de>) -> Result<Self, Error> {
+            <&str>::decode(reader).map(Cow::Borrowed)
+        }
+    }
+
+    impl<'de: 'a, 'a> Decode<'de> for Cow<'a, [u8]> {
+        fn decode(reader: &mut Reader<'de>) -> Result<Self, Error> {
+            <&[u8]>::decode(reader).map(Cow::Borrowed)
+        }
+    }
+
+    impl<T: Encode + ToOwned + ?Sized> Encode for Cow<'_, T> {
+        fn encode(&self, writer: &mut Writer) {
+            (**self).encode(writer)
+        }
+    }
+
+    impl Encode for () {
+        fn encode(&self, _: &mut Writer) {}
+    }
+    impl Decode<'_> for () {
+        fn decode(_: &mut Reader<'_>) -> Result<Self, Error> {
+            Ok(())
+        }
+    }
+
+    impl<T: ?Sized> Encode for PhantomData<T> {
+        fn encode(&self, _: &mut Writer) {}
+    }
+    impl<T: ?Sized> Decode<'_> for PhantomData<T> {
+        fn decode(_: &mut Reader<'_>) -> Result<Self, Error> {
+            Ok(PhantomData)
+        }
+    }
+
+    impl Encode for Duration {
+        fn encode(&self, writer: &mut Writer) {
+            self.as_secs().encode(writer);
+            self.subsec_nanos().encode(writer)
+        }
+    }
+    impl Decode<'_> for Duration {
+        fn decode(reader: &mut Reader<'_>) -> Result<Self, Error> {
+            let (secs, nanos) = (u64::decode(reader)?, u32::decode(reader)?);
+            match nanos {
+                0..1_000_000_000 => Ok(Duration::new(secs, nanos)),
+                _ => Err(Error::Overflow),
+            }
+        }
+    }
+
+    impl<T: Encode> Encode for Option<T> {
+        fn encode(&self, writer: &mut Writer) {
+            match self {
+                None => writer.byte(0),
+                Some(value) => {
+                    writer.byte(1);
+                    value.encode(writer)
+                }
+            }
+        }
+    }
+    impl<'de, T: Decode<'de>> Decode<'de> for Option<T> {
+        fn decode(reader: &mut Reader<'de>) -> Result<Self, Error> {
+            match reader.byte()? {
+                0 => Ok(None),
+                1 => T::decode(reader).map(Some),
+                tag => Err(Error::InvalidTag(tag)),
+            }
+        }
+    }
+
+    // containers which are encoded as their contents
+    macro_rules! forward {
+        ($($t:ty),*) => {$(
+            impl<T: Encode + ?Sized> Encode for $t {
+                fn encode(&self, writer: &mut Writer) {
+                    (**self).encode(writer)
+                }
+            }
+        )*};
+    }
+
+    forward!(&T, &mut T, Box<T>, Rc<T>, Arc<T>);
+
+    macro_rules! owned {
+        ($($t:ident),*) => {$(
+            impl<'de, T: Decode<'de>> Decode<'de> for $t<T> {
+                fn decode(reader: &mut Reader<'de>) -> Result<Self, Error> {
+                    T::decode(reader).map($t::new)
+                }
+            }
+        )*};
+    }
+
+    owned!(Box, Rc, Arc);
+
+    impl<T: Encode> Encode for [T] {
+        fn encode(&self, writer: &mut Writer) {
+            writer.length(self.len());
+            self.iter().for_each(|item| item.encode(writer))
+        }
+    }
+
+    impl<T: Encode, const N: usize> Encode for [T; N] {
+        fn encode(&self, writer: &mut Writer) {
+            self.iter().for_each(|item| item.encode(writer))
+        }
+    }
+    impl<'de, T: Decode<'de>, const N: usize> Decode<'de> for [T; N] {
+        fn decode(reader: &mut Reader<'de>) -> Result<Self, Error> {
+            let items = (0..N)
+                .map(|_| T::decode(reader))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(items.try_into().unwrap_or_else(|_| unreachable!()))
+        }
+    }
+
+    // a length followed by the items
+    fn decode_items<'de, T, C>(reader: &mut Reader<'de>) -> Result<C, Error>
+    where
+        T: Decode<'de>,
+        C: FromIterator<T>,
+    {
+        let len = reader.length()?;
+        (0..len).map(|_| T::decode(reader)).collect()
+    }
+
+    macro_rules! sequences {
+        ($($t:ident<T $(: $bound:ident $(+ $bounds:ident)*)?>),*) => {$(
+            impl<T: Encode> Encode for $t<T> {
+                fn encode(&self, writer: &mut Writer) {
+                    writer.length(self.len());
+                    self.iter().for_each(|item| item.encode(writer))
+                }
+            }
+            impl<'de, T: Decode<'de> $(+ $bound $(+ $bounds)*)?> Decode<'de> for $t<T> {
+                fn decode(reader: &mut Reader<'de>) -> Result<Self, Error> {
+                    decode_items(reader)
+                }
+            }
+        )*};
+    }
+
+    sequences!(Vec<T>, VecDeque<T>, LinkedList<T>, BTreeSet<T: Ord>);
+
+    impl<T: Encode, S> Encode for HashSet<T, S> {
+        fn encode(&self, writer: &mut Writer) {
+            writer.length(self.len());
+            self.iter().for_each(|item| item.encode(writer))
+        }
+    }
+    impl<'de, T, S> Decode<'de> for HashSet<T, S>
+    where
+        T: Decode<'de> + Eq + Hash,
+        S: BuildHasher + Default,
+    {
+        fn decode(reader: &mut Reader<'de>) -> Result<Self, Error> {
+            decode_items(reader)
+        }
+    }
+
+    impl<K: Encode, V: Encode> Encode for BTreeMap<K, V> {
+        fn encode(&self, writer: &mut Writer) {
+            writer.length(self.len());
+            self.iter().for_each(|entry| entry.encode(writer))
+        }
+    }
+    impl<'de, K: Decode<'de> + Ord, V: Decode<'de>> Decode<'de> for BTreeMap<K, V> {
+        fn decode(reader: &mut Reader<'de>) -> Result<Self, Error> {
+            decode_items(reader)
+        }
+    }
+
+    impl<K: Encode, V: Encode, S> Encode for HashMap<K, V, S> {
+        fn encode(&self, writer: &mut Writer) {
+            writer.length(self.len());
+            self.iter().for_each(|entry| entry.encode(writer))
+        }
+    }
+    impl<'de, K, V, S> Decode<'de> for HashMap<K, V, S>
+    where
+        K: Decode<'de> + Eq + Hash,
+        V: Decode<'de>,
+        S: BuildHasher + Default,
+    {
+        fn decode(reader: &mut Reader<'de>) -> Result<Self, Error> {
+            decode_items(reader)
+        }
+    }
+
+    macro_rules! tuples {
+        ($(($($t:ident),+)),*) => {$(
+            #[allow(non_snake_case)]
+            impl<$($t: Encode),+> Encode for ($($t,)+) {
+                fn encode(&self, writer: &mut Writer) {
+                    let ($($t,)+) = self;
+                    $($t.encode(writer);)+
+                }
+            }
+            impl<'de, $($t: Decode<'de>),+> Decode<'de> for ($($t,)+) {
+                fn decode(reader: &mut Reader<'de>) -> Result<Self, Error> {
+                    Ok(($($t::decode(reader)?,)+))
+                }
+            }
+        )*};
+    }
+
+    tuples!(
+        (A),
+        (A, B),
+        (A, B, C),
+        (A, B, C, D),
+        (A, B, C, D, E),
+        (A, B, C, D, E, F),
+        (A, B, C, D, E, F, G),
+        (A, B, C, D, E, F, G, H),
+        (A, B, C, D, E, F, G, H, I),
+        (A, B, C, D, E, F, G, H, I, J),
+        (A, B, C, D, E, F, G, H, I, J, K),
+        (A, B, C, D, E, F, G, H, I, J, K, L)
+    );
+}
@@ -0,0 +1,254 @@
+use std::{borrow::Cow, collections::BTreeMap};
+
+use reflector::Introspect;
+use reflector_binary::{Error, from_slice, to_vec};
+use reflector_serde::with;
+
+#[derive(Debug, PartialEq, Introspect)]
+#[reflect(impl(reflector_binary::Encode, reflector_binary::Decode))]
+pub struct Server {
+    host: String,
+    port: u16,
+}
+
+#[derive(Debug, PartialEq, Introspect)]
+#[reflect(impl(reflector_binary::Encode, reflector_binary::Decode))]
+pub enum Mode {
+    Fast,
+    Slow { delay: u32 },
+    Fixed(i8, i64),
+}
+
+#[derive(Debug, PartialEq, Introspect)]
+pub struct Settings {
+    server: Server,
+    modes: Vec<Mode>,
+    workers: Option<usize>,
+    limits: BTreeMap<String, f32>,
+    ratio: (bool, char),
+}
+
+#[test]
+fn encoding() {
+    let settings = Settings {
+        server: Server {
+            host: "ab".into(),
+            port: 300,
+        },
+        modes: vec![Mode::Fast, Mode::Slow { delay: 1 }, Mode::Fixed(-1, -2)],
+        workers: None,
+        limits: BTreeMap::from([("x".into(), 1.0)]),
+        ratio: (true, 'a'),
+    };
+    let bytes = to_vec(&settings);
+    #[rustfmt::skip]
+    assert_eq!(
+        bytes,
+        [
+            2, b'a', b'b', 0xac, 0x02,  // server
+            3, 0, 1, 1, 2, 0xff, 3,     // modes
+            0,                          // workers
+            1, 1, b'x', 0, 0, 0x80, 0x3f, // limits
+            1, b'a',                    // ratio
+        ]
+    );
+    assert_eq!(from_slice::<Settings>(&bytes), Ok(settings));
+}
+
+#[derive(Debug, PartialEq, Introspect)]
+#[reflect(impl(reflector_binary::Encode, reflector_binary::Decode))]
+pub struct Message<'a, T> {
+    #[reflect(borrow)]
+    name: &'a str,
+    #[reflect(borrow)]
+    payload: &'a [u8],
+    #[reflect(borrow)]
+    note: Cow<'a, str>,
+    value: T,
+}
+
+#[test]
+fn zero_copy() {
+    let message = Message {
+        name: "hello",
+        payload: &[1, 2, 3],
+        note: Cow::Borrowed("note"),
+        value: -5i32,
+    };
+    let bytes = to_vec(&message);
+    let decoded = from_slice::<Message<i32>>(&bytes).unwrap();
+    assert_eq!(decoded, message);
+    let range = bytes.as_ptr_range();
+    assert!(range.contains(&decoded.name.as_ptr()));
+    assert!(range.contains(&decoded.payload.as_ptr()));
+    assert!(matches!(decoded.note, Cow::Borrowed(note) if range.contains(&note.as_ptr())));
+}
+
+#[derive(Debug, PartialEq, Introspect)]
+pub struct Page {
+    title: String,
+    #[reflect(flatten)]
+    meta: Meta,
+}
+
+#[derive(Debug, PartialEq, Introspect)]
+pub struct Meta {
+    author: String,
+}
+
+#[derive(Debug, PartialEq, Introspect)]
+#[reflect(transparent)]
+pub struct Id(u32);
+
+#[derive(Debug, PartialEq, Introspect)]
+pub struct Record {
+    #[reflect(adapter = with::Reflect)]
+    id: Id,
+    #[reflect(adapter = with::DisplayFromStr)]
+    count: u64,
+    #[reflect(adapter = with::Reflect)]
+    page: Page,
+}
+
+#[test]
+fn flatten_transparent_and_adapters() {
+    let record = Record {
+        id: Id(7),
+        count: 42,
+        page: Page {
+            title: "t".into(),
+            meta: Meta { author: "a".into() },
+        },
+    };
+    let bytes = to_vec(&record);
+    assert_eq!(bytes, [7, 2, b'4', b'2', 1, b't', 1, b'a']);
+    assert_eq!(from_slice::<Record>(&bytes), Ok(record));
+}
+
+#[derive(Debug, PartialEq, Introspect)]
+pub struct Numbers {
+    small: u64,
+    large: u128,
+    negative: isize,
+    float: f64,
+}
+
+#[test]
+fn integers() {
+    for numbers in [
+        Numbers {
+            small: 0,
+            large: 0,
+            negative: 0,
+            float: 0.0,
+        },
+        Numbers {
+            small: u64::MAX,
+            large: u128::MAX,
+            negative: isize::MIN,
+            float: f64::MIN,
+        },
+        Numbers {
+            small: 127,
+            large: 128,
+            negative: -64,
+            float: f64::NAN,
+        },
+    ] {
+        let bytes = to_vec(&numbers);
+        let decoded = from_slice::<Numbers>(&bytes).unwrap();
+        assert_eq!(to_vec(&decoded), bytes);
+    }
+    assert_eq!(
+        to_vec(&Numbers {
+            small: 127,
+            large: 128,
+            negative: -64,
+            float: 0.0
+        })[..4],
+        [127, 0x80, 1, 127]
+    );
+}
+
+#[test]
+fn errors() {
+    assert_eq!(from_slice::<Server>(&[2, b'a']), Err(Error::UnexpectedEnd));
+    assert_eq!(
+        from_slice::<Server>(&[0, 1, 0]),
+        Err(Error::TrailingBytes(1))
+    );
+    assert_eq!(
+        from_slice::<Server>(&[0, 0xff, 0xff, 0x04]),
+        Err(Error::Overflow)
+    );
+    assert_eq!(from_slice::<Server>(&[1, 0xff, 0]), Err(Error::InvalidUtf8));
+    assert_eq!(
+        from_slice::<Mode>(&[3]),
+        Err(Error::InvalidVariant {
+            ident: "Mode",
+            index: 3
+        })
+    );
+    assert_eq!(
+        from_slice::<Settings>(&[0, 0, 0, 2]),
+        Err(Error::InvalidTag(2))
+    );
+    assert_eq!(
+        from_slice::<Record>(&[0, 1, b'x', 0, 0]),
+        Err(Error::Custom("invalid digit found in string".into()))
+    );
+}
+
+#[derive(Debug, PartialEq, Introspect)]
+#[reflect(impl(reflector_binary::Encode, reflector_binary::Decode), version = 3)]
+pub struct Versioned {
+    name: String,
+    #[reflect(since = 2)]
+    count: u8,
+    #[reflect(removed = 3)]
+    legacy: bool,
+    #[reflect(since = 3, default = default_ratio)]
+    ratio: f32,
+}
+
+fn default_ratio() -> f32 {
+    0.5
+}
+
+#[test]
+fn versioning() {
+    let versioned = Versioned {
+        name: "a".into(),
+        count: 7,
+        legacy: false,
+        ratio: 1.0,
+    };
+    let bytes = to_vec(&versioned);
+    assert_eq!(bytes, [3, 1, b'a', 7, 0, 0, 0x80, 0x3f]);
+    assert_eq!(from_slice::<Versioned>(&bytes), Ok(versioned));
+
+    // version 1 has `name` and `legacy`, version 2 adds `count`
+    let old = Versioned {
+        name: "b".into(),
+        count: 0,
+        legacy: true,
+        ratio: 0.5,
+    };
+    assert_eq!(from_slice::<Versioned>(&[1, 1, b'b', 1]), Ok(old));
+    assert_eq!(
+        from_slice::<Versioned>(&[2, 1, b'b', 9, 1]),
+        Ok(Versioned {
+            name: "b".into(),
+            count: 9,
+            legacy: true,
+            ratio: 0.5,
+        })
+    );
+    assert_eq!(
+        from_slice::<Versioned>(&[4, 1, b'b']),
+        Err(Error::UnsupportedVersion {
+            ident: "Versioned",
+            version: 4
+        })
+    );
+}
@@ -0,0 +1,35 @@
+//! Builders for reflected structs and enum variants, generated with `#[reflect(builder)]`.
+//!
+//! A struct `Config` with named fields gets a `ConfigBuilder`, returned by `Config::builder()`,
+//! with a setter for every field. An enum `Mode` gets a `ModeSlowBuilder` for every variant
+//! `Slow` with named fields, returned by `Mode::slow_builder()`. Fields marked
+//! `#[reflect(default)]`, `since` or `removed` get their default if they aren't set, while all
+//! others are required: `build()` fails with an [`Error`] listing every one which wasn't set.
+//!
+//! ```
+//! # use reflector::Introspect;
+//! #[derive(Debug, PartialEq, Introspect)]
+//! #[reflect(builder)]
+//! struct Server {
+//!     host: String,
+//!     #[reflect(default)]
+//!     port: u16,
+//! }
+//!
+//! let server = Server::builder().host("localhost").build().unwrap();
+//! assert_eq!(server, Server { host: "localhost".into(), port: 0 });
+//! assert_eq!(Server::builder().port(80u16).build().unwrap_err().missing, ["host"]);
+//! ```
+//!
+//! The setters are named after the fields, so no field can be named `build`:
+//!
+//! ```compile_fail
+//! # use reflector::Introspect;
+//! #[derive(Introspect)]
+//! #[reflect(builder)]
+//! struct Step {
+//!     build: bool,
+//! }
+//! ```
+
+pub use reflector::builder::Error;
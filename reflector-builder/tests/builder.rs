@@ -0,0 +1,138 @@
+use std::marker::PhantomData;
+
+use reflector::Introspect;
+use reflector_builder::Error;
+
+#[derive(Debug, PartialEq, Introspect)]
+#[reflect(builder)]
+pub struct Server {
+    /// The address to bind
+    host: String,
+    #[reflect(default = default_port)]
+    port: u16,
+    tags: Vec<String>,
+    #[reflect(default)]
+    verbose: bool,
+}
+
+fn default_port() -> u16 {
+    8080
+}
+
+#[test]
+fn structs() {
+    assert_eq!(
+        Server::builder()
+            .host("localhost")
+            .tags(vec!["a".to_owned()])
+            .build(),
+        Ok(Server {
+            host: "localhost".into(),
+            port: 8080,
+            tags: vec!["a".into()],
+            verbose: false,
+        })
+    );
+    assert_eq!(
+        Server::builder()
+            .port(80u16)
+            .verbose(true)
+            .host("a")
+            .host("b")
+            .tags([])
+            .build()
+            .map(|server| (server.host, server.port, server.verbose)),
+        Ok(("b".into(), 80, true))
+    );
+
+    let error = ServerBuilder::default().port(80u16).build().unwrap_err();
+    assert_eq!(
+        error,
+        Error {
+            ident: "Server",
+            missing: vec!["host", "tags"],
+        }
+    );
+    assert_eq!(error.to_string(), "missing fields of Server: host, tags");
+}
+
+#[derive(Debug, PartialEq, Introspect)]
+#[reflect(builder)]
+pub enum Shape {
+    Circle { radius: f64 },
+    Rect { width: f64, height: f64 },
+    Point(i32, i32),
+    Empty,
+}
+
+#[test]
+fn variants() {
+    assert_eq!(
+        Shape::circle_builder().radius(1.0).build(),
+        Ok(Shape::Circle { radius: 1.0 })
+    );
+    assert_eq!(
+        Shape::rect_builder().width(2.0).height(3.0).build(),
+        Ok(Shape::Rect {
+            width: 2.0,
+            height: 3.0
+        })
+    );
+    let builder: ShapeRectBuilder = Shape::rect_builder();
+    assert_eq!(
+        builder.height(1.0).build(),
+        Err(Error {
+            ident: "Rect",
+            missing: vec!["width"],
+        })
+    );
+}
+
+#[derive(Debug, PartialEq, Introspect)]
+#[reflect(builder)]
+pub enum Message {
+    HTTPRequest { url: String },
+    SaveAs { path: String },
+}
+
+#[test]
+fn acronyms() {
+    assert_eq!(
+        Message::http_request_builder().url("/").build(),
+        Ok(Message::HTTPRequest { url: "/".into() })
+    );
+    assert_eq!(
+        Message::save_as_builder().path("a").build(),
+        Ok(Message::SaveAs { path: "a".into() })
+    );
+}
+
+#[derive(Debug, PartialEq, Introspect)]
+#[reflect(builder)]
+pub struct Wrapper<'a, T> {
+    name: &'a str,
+    value: T,
+    #[reflect(default)]
+    marker: PhantomData<T>,
+}
+
+#[derive(Debug, PartialEq, Introspect)]
+#[reflect(builder)]
+pub struct Unit;
+
+#[test]
+fn generics() {
+    assert_eq!(
+        Wrapper::builder().name("x").value(3u8).build(),
+        Ok(Wrapper {
+            name: "x",
+            value: 3u8,
+            marker: PhantomData,
+        })
+    );
+    assert_eq!(
+        Wrapper::<u8>::builder().build().unwrap_err().missing,
+        ["name", "value"]
+    );
+    assert_eq!(Unit::builder().build(), Ok(Unit));
+}
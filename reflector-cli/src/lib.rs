@@ -0,0 +1,775 @@
+//! Command-line parsing for reflected structs.
+//!
+//! The fields of a named struct are `--long-flags`, named after the field with underscores
+//! replaced by dashes, and the first paragraph of their doc comment is their help. Flags take a
+//! value as `--name value` or `--name=value`, except for `bool` fields which are switches like
+//! `--verbose`. `Option` fields are optional, `Vec` fields can be given repeatedly, and fields
+//! marked `#[reflect(default)]` get their default when missing. Flattened fields contribute their
+//! own flags.
+//!
+//! Enums are subcommands, named after their variants in kebab case, whose fields are the flags of
+//! the subcommand. They are marked `#[reflect(impl(reflector_cli::Arg))]`, or used through the
+//! `with::Reflect` adapter.
+//! The doc comments of structs and variants are the description of their command.
+//!
+//! Values are parsed through [`Arg`], which is implemented for std types with `FromStr`, while
+//! fields with an adapter of `reflector_serde::with` are parsed through [`ArgAs`].
+
+use std::path::Path;
+
+pub use error::Error;
+pub use reflect::{ReflectArg, ReflectCommand};
+
+/// Parses the arguments of the process, printing the help or an error and exiting if needed.
+pub fn parse<T: ReflectCommand>() -> T {
+    try_parse_from(std::env::args()).unwrap_or_else(|error| error.exit())
+}
+
+/// Parses the given arguments, the first of which is the name of the program.
+pub fn try_parse_from<T, I>(args: I) -> Result<T, Error>
+where
+    T: ReflectCommand,
+    I: IntoIterator<Item: Into<String>>,
+{
+    let mut args = args.into_iter().map(Into::into);
+    let name = args.next().unwrap_or_default();
+    let name = match Path::new(&name).file_name() {
+        Some(file_name) => file_name.to_string_lossy().into_owned(),
+        None => name,
+    };
+    T::parse(name, args.collect())
+}
+
+/// The help of the command `T`, invoked as `name`.
+pub fn help<T: ReflectCommand>(name: &str) -> String {
+    T::help(name)
+}
+
+/// How an argument is given on the command line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgKind {
+    /// A flag with a value, `--name value` or `--name=value`
+    Value { required: bool, repeated: bool },
+    /// A flag without a value, `--name`
+    Switch,
+    /// A subcommand, followed by its own flags
+    Subcommand { required: bool },
+}
+
+/// A type which can be given on the command line, as the value of a flag or as a subcommand.
+pub trait Arg: Sized {
+    const KIND: ArgKind;
+
+    /// Builds the value from what was given for it: the values of every occurrence of the flag,
+    /// an empty string per occurrence of a switch, or the name of the subcommand followed by the
+    /// remaining arguments. `name` is the flag, or the command which the subcommand belongs to.
+    fn from_args(name: &str, args: Vec<String>) -> Result<Self, Error>;
+
+    /// Adds the names of the subcommands, with their doc comments.
+    fn subcommands(_: &mut Vec<(String, &'static str)>) {}
+}
+
+/// Implements [`Arg`] for a reflected type through [`ReflectArg`], with
+/// `#[reflect(impl(reflector_cli::Arg))]`. Type parameters have to implement `Arg`.
+#[macro_export]
+macro_rules! Arg {
+    (
+        lifetimes [$($lifetime:lifetime),*]
+        types [$($param:ident),*]
+        impl [$($params:tt)*] $ty:ty
+        where [$($where:tt)*]
+    ) => {
+        impl<$($params)*> $crate::Arg for $ty
+        where
+            $($where)*
+            $($param: $crate::Arg,)*
+        {
+            const KIND: $crate::ArgKind = <Self as $crate::ReflectArg>::KIND;
+
+            fn from_args(
+                name: &str,
+                args: ::std::vec::Vec<::std::string::String>,
+            ) -> ::core::result::Result<Self, $crate::Error> {
+                <Self as $crate::ReflectArg>::from_args(name, args)
+            }
+
+            fn subcommands(subcommands: &mut ::std::vec::Vec<(::std::string::String, &'static str)>) {
+                <Self as $crate::ReflectArg>::subcommands(subcommands)
+            }
+        }
+    };
+}
+
+/// Parses `T` as the argument of a field with an adapter of `reflector_serde::with`.
+pub trait ArgAs<T> {
+    const KIND: ArgKind;
+
+    fn from_args_as(name: &str, args: Vec<String>) -> Result<T, Error>;
+
+    fn subcommands(_: &mut Vec<(String, &'static str)>) {}
+}
+
+mod error {
+    use std::fmt::{Display, Formatter};
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum Error {
+        /// `--help` was given, with the help of the command
+        Help(String),
+        UnknownFlag(String),
+        /// A flag which requires a value was the last argument
+        MissingValue(String),
+        /// A switch was given a value, `--switch=value`
+        UnexpectedValue(String),
+        /// A required flag wasn't given
+        MissingFlag(String),
+        /// A flag which can only be given once was given multiple times
+        Duplicate(String),
+        InvalidValue {
+            flag: String,
+            value: String,
+            message: String,
+        },
+        /// The command requires a subcommand, but none was given
+        MissingSubcommand(String),
+        UnknownSubcommand(String),
+        /// An argument which isn't a flag, where no subcommand is expected
+        UnexpectedArgument(String),
+    }
+
+    impl Error {
+        /// Prints the help to stdout and exits successfully, or prints the error to stderr and
+        /// exits with code 2.
+        pub fn exit(&self) -> ! {
+            match self {
+                Error::Help(help) => {
+                    print!("{help}");
+                    std::process::exit(0)
+                }
+                error => {
+                    eprintln!("error: {error}\n\nFor more information, try '--help'.");
+                    std::process::exit(2)
+                }
+            }
+        }
+    }
+
+    impl Display for Error {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            match self {
+                Error::Help(help) => f.write_str(help),
+                Error::UnknownFlag(flag) => write!(f, "unexpected flag '{flag}'"),
+                Error::MissingValue(flag) => write!(f, "'{flag}' requires a value"),
+                Error::UnexpectedValue(flag) => write!(f, "'{flag}' doesn't take a value"),
+                Error::MissingFlag(flag) => write!(f, "'{flag}' is required"),
+                Error::Duplicate(flag) => write!(f, "'{flag}' can only be given once"),
+                Error::InvalidValue {
+                    flag,
+                    value,
+                    message,
+                } => write!(f, "invalid value '{value}' for '{flag}': {message}"),
+                Error::MissingSubcommand(command) => {
+                    write!(f, "'{command}' requires a subcommand")
+                }
+                Error::UnknownSubcommand(name) => write!(f, "unrecognized subcommand '{name}'"),
+                Error::UnexpectedArgument(arg) => write!(f, "unexpected argument '{arg}'"),
+            }
+        }
+    }
+
+    impl std::error::Error for Error {}
+}
+
+// the flags and subcommand of a command, and parsing its arguments
+mod command {
+    use std::collections::HashMap;
+    use std::iter;
+
+    use reflector::{Field, SizedStruct};
+
+    use crate::reflect::CommandFields;
+    use crate::{ArgKind, Error};
+
+    /// The flags and subcommand of a command.
+    #[derive(Default)]
+    pub struct Command {
+        flags: Vec<Flag>,
+        subcommand: Option<Subcommand>,
+    }
+
+    struct Flag {
+        name: String,
+        value: String,
+        kind: ArgKind,
+        doc: &'static str,
+    }
+
+    struct Subcommand {
+        required: bool,
+        names: Vec<(String, &'static str)>,
+    }
+
+    /// The arguments given to a command, taken by its fields.
+    pub struct Matches {
+        command: String,
+        flags: HashMap<String, Vec<String>>,
+        subcommand: Vec<String>,
+    }
+
+    impl Command {
+        pub fn of<S: SizedStruct<Fields: CommandFields>>() -> Self {
+            let mut command = Self::default();
+            S::Fields::describe(&mut command);
+            command
+        }
+
+        pub(crate) fn push<F: Field<Type: Sized>>(
+            &mut self,
+            kind: ArgKind,
+            subcommands: fn(&mut Vec<(String, &'static str)>),
+        ) {
+            const {
+                assert!(
+                    F::IDENT.is_some(),
+                    "the fields of a command need names, or `#[reflect(flatten)]`"
+                )
+            };
+            let ident = F::IDENT.unwrap();
+            match kind {
+                ArgKind::Subcommand { required } => {
+                    assert!(
+                        self.subcommand.is_none(),
+                        "a command can only have one subcommand"
+                    );
+                    let mut names = Vec::new();
+                    subcommands(&mut names);
+                    self.subcommand = Some(Subcommand { required, names });
+                }
+                kind => self.flags.push(Flag {
+                    name: flag(ident),
+                    value: ident.to_uppercase(),
+                    kind: match kind {
+                        // `#[reflect(default)]`
+                        ArgKind::Value { repeated, .. } if F::default_value().is_some() => {
+                            ArgKind::Value {
+                                required: false,
+                                repeated,
+                            }
+                        }
+                        kind => kind,
+                    },
+                    doc: F::DOC,
+                }),
+            }
+        }
+
+        pub fn help(&self, command: &str, doc: &str) -> String {
+            let mut help = String::new();
+            if !doc.is_empty() {
+                help += doc;
+                help += "\n\n";
+            }
+
+            help += &format!("Usage: {command} [OPTIONS]");
+            for flag in &self.flags {
+                if let ArgKind::Value { required: true, .. } = flag.kind {
+                    help += &format!(" {} <{}>", flag.name, flag.value);
+                }
+            }
+            match &self.subcommand {
+                Some(Subcommand { required: true, .. }) => help += " <COMMAND>",
+                Some(_) => help += " [COMMAND]",
+                None => {}
+            }
+
+            help += "\n\nOptions:\n";
+            let mut rows = self
+                .flags
+                .iter()
+                .map(|flag| {
+                    let usage = match flag.kind {
+                        ArgKind::Switch => flag.name.clone(),
+                        ArgKind::Value { repeated: true, .. } => {
+                            format!("{} <{}>...", flag.name, flag.value)
+                        }
+                        _ => format!("{} <{}>", flag.name, flag.value),
+                    };
+                    (usage, summary(flag.doc))
+                })
+                .collect::<Vec<_>>();
+            rows.push(("-h, --help".into(), "Print help".into()));
+            help += &table(&rows);
+
+            if let Some(subcommand) = &self.subcommand {
+                help += "\nCommands:\n";
+                let rows = subcommand
+                    .names
+                    .iter()
+                    .map(|(name, doc)| (name.clone(), summary(doc)))
+                    .collect::<Vec<_>>();
+                help += &table(&rows);
+            }
+            help
+        }
+    }
+
+    impl Matches {
+        pub(crate) fn take<F>(
+            &mut self,
+            kind: ArgKind,
+            from_args: fn(&str, Vec<String>) -> Result<F::Type, Error>,
+        ) -> Result<F::Type, Error>
+        where
+            F: Field<Type: Sized>,
+        {
+            let (name, args) = match kind {
+                ArgKind::Subcommand { .. } => {
+                    (self.command.clone(), std::mem::take(&mut self.subcommand))
+                }
+                _ => {
+                    let name = flag(F::IDENT.unwrap());
+                    let args = self.flags.remove(&name).unwrap_or_default();
+                    (name, args)
+                }
+            };
+            if args.is_empty()
+                && let Some(default) = F::default_value()
+            {
+                return Ok(default);
+            }
+            from_args(&name, args)
+        }
+    }
+
+    /// Parses the arguments of the command `S`, invoked as `name`
+    pub fn parse<S>(name: String, args: Vec<String>) -> Result<S::Root, Error>
+    where
+        S: SizedStruct<Fields: CommandFields<Values = S::FieldTypes>>,
+    {
+        let command = Command::of::<S>();
+        let mut matches = Matches {
+            command: name,
+            flags: HashMap::new(),
+            subcommand: Vec::new(),
+        };
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if arg == "-h" || arg == "--help" {
+                return Err(Error::Help(command.help(&matches.command, S::DOC)));
+            }
+            let Some(flag) = arg.strip_prefix("--").filter(|flag| !flag.is_empty()) else {
+                // everything after the subcommand belongs to it
+                if command.subcommand.is_none() {
+                    return Err(Error::UnexpectedArgument(arg));
+                }
+                matches.subcommand = iter::once(arg).chain(args).collect();
+                break;
+            };
+
+            let (name, value) = match flag.split_once('=') {
+                Some((name, value)) => (format!("--{name}"), Some(value.to_owned())),
+                None => (format!("--{flag}"), None),
+            };
+            let Some(spec) = command.flags.iter().find(|spec| spec.name == name) else {
+                return Err(Error::UnknownFlag(name));
+            };
+            let value = match (spec.kind, value) {
+                (ArgKind::Switch, None) => String::new(),
+                (ArgKind::Switch, Some(_)) => return Err(Error::UnexpectedValue(name)),
+                (_, Some(value)) => value,
+                (_, None) => match args.next() {
+                    Some(value) => value,
+                    None => return Err(Error::MissingValue(name)),
+                },
+            };
+            matches.flags.entry(name).or_default().push(value);
+        }
+
+        S::Fields::build(&mut matches).map(S::from_values)
+    }
+
+    // `max_count` as `--max-count`
+    fn flag(ident: &str) -> String {
+        format!("--{}", kebab_case(ident))
+    }
+
+    // `ListAll` as `list-all`, and `max_count` as `max-count`
+    pub fn kebab_case(ident: &str) -> String {
+        let chars = ident.chars().collect::<Vec<_>>();
+        let mut out = String::new();
+        for (i, &c) in chars.iter().enumerate() {
+            if c == '_' {
+                out.push('-');
+                continue;
+            }
+            if c.is_uppercase() && i > 0 {
+                let previous = chars[i - 1];
+                let next = chars.get(i + 1);
+                if previous.is_lowercase()
+                    || previous.is_ascii_digit()
+                    || previous.is_uppercase() && next.is_some_and(|c| c.is_lowercase())
+                {
+                    out.push('-');
+                }
+            }
+            out.extend(c.to_lowercase());
+        }
+        out
+    }
+
+    // the first paragraph of a doc comment, on a single line
+    fn summary(doc: &str) -> String {
+        let paragraph = doc.split("\n\n").next().unwrap_or_default();
+        paragraph
+            .lines()
+            .map(str::trim)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn table(rows: &[(String, String)]) -> String {
+        let width = rows.iter().map(|(left, _)| left.len()).max().unwrap_or(0);
+        rows.iter()
+            .map(|(left, right)| format!("  {left:width$}  {right}").trim_end().to_owned() + "\n")
+            .collect()
+    }
+}
+
+mod reflect {
+    use reflector::*;
+
+    use crate::command::{self, Command, Matches, kebab_case};
+    use crate::{Arg, ArgAs, ArgKind, Error};
+
+    /// Command-line parsing of reflected structs, implemented for any struct which can be
+    /// introspected and whose fields implement [`Arg`], or [`ArgAs`] for their adapter.
+    pub trait ReflectCommand: Sized {
+        /// Parses the arguments following `name`, the name of the program.
+        fn parse(name: String, args: Vec<String>) -> Result<Self, Error>;
+
+        fn help(name: &str) -> String;
+    }
+
+    impl<T> ReflectCommand for T
+    where
+        T: SizedStruct<Root = T, Fields: CommandFields<Values = T::FieldTypes>>,
+    {
+        fn parse(name: String, args: Vec<String>) -> Result<Self, Error> {
+            command::parse::<T>(name, args)
+        }
+
+        fn help(name: &str) -> String {
+            Command::of::<T>().help(name, T::DOC)
+        }
+    }
+
+    /// Enums as subcommands, implemented for any enum which can be introspected and whose
+    /// variants are commands.
+    pub trait ReflectArg: Sized {
+        const KIND: ArgKind;
+
+        fn from_args(name: &str, args: Vec<String>) -> Result<Self, Error>;
+
+        fn subcommands(subcommands: &mut Vec<(String, &'static str)>);
+    }
+
+    impl<T> ReflectArg for T
+    where
+        T: Enum<Root = T, Variants: CommandVariants<T>>,
+    {
+        const KIND: ArgKind = ArgKind::Subcommand { required: true };
+
+        fn from_args(command: &str, args: Vec<String>) -> Result<Self, Error> {
+            let mut args = args.into_iter();
+            let Some(name) = args.next() else {
+                return Err(Error::MissingSubcommand(command.into()));
+            };
+            match T::Variants::parse(&name, format!("{command} {name}"), args.collect()) {
+                Some(result) => result,
+                None => Err(Error::UnknownSubcommand(name)),
+            }
+        }
+
+        fn subcommands(subcommands: &mut Vec<(String, &'static str)>) {
+            T::Variants::subcommands(subcommands)
+        }
+    }
+
+    // helper trait implemented recursively for a list of fields, e.g `(Field0, (Field1, ()))`
+    pub trait CommandFields {
+        type Values;
+
+        fn describe(command: &mut Command);
+        fn build(matches: &mut Matches) -> Result<Self::Values, Error>;
+    }
+    // end of the recursion
+    impl CommandFields for () {
+        type Values = ();
+
+        fn describe(_: &mut Command) {}
+        fn build(_: &mut Matches) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+    impl<Head, Tail> CommandFields for Cons<Head, Tail>
+    where
+        Head: Field<Type: Sized> + CommandField<<Head as Field>::Repr>,
+        Tail: CommandFields,
+    {
+        type Values = Cons<Head::Type, Tail::Values>;
+
+        fn describe(command: &mut Command) {
+            Head::describe(command);
+            Tail::describe(command)
+        }
+
+        fn build(matches: &mut Matches) -> Result<Self::Values, Error> {
+            Ok(Cons(Head::build(matches)?, Tail::build(matches)?))
+        }
+    }
+
+    // helper trait implemented for every field, depending on its representation
+    pub trait CommandField<Repr>: Field<Type: Sized> {
+        fn describe(command: &mut Command);
+        fn build(matches: &mut Matches) -> Result<Self::Type, Error>;
+    }
+
+    impl<F> CommandField<PlainRepr> for F
+    where
+        F: Field<Type: Arg>,
+    {
+        fn describe(command: &mut Command) {
+            command.push::<F>(F::Type::KIND, F::Type::subcommands)
+        }
+
+        fn build(matches: &mut Matches) -> Result<F::Type, Error> {
+            matches.take::<F>(F::Type::KIND, F::Type::from_args)
+        }
+    }
+
+    impl<F, A> CommandField<WithRepr<A>> for F
+    where
+        F: Field<Type: Sized>,
+        A: ArgAs<F::Type>,
+    {
+        fn describe(command: &mut Command) {
+            command.push::<F>(A::KIND, A::subcommands)
+        }
+
+        fn build(matches: &mut Matches) -> Result<F::Type, Error> {
+            matches.take::<F>(A::KIND, A::from_args_as)
+        }
+    }
+
+    // the flags of the flattened struct are flags of the parent
+    impl<F, Inner> CommandField<FlattenRepr> for F
+    where
+        F: Field<Type = Inner>,
+        Inner: SizedStruct<Root = Inner, Fields: CommandFields<Values = Inner::FieldTypes>>,
+    {
+        fn describe(command: &mut Command) {
+            Inner::Fields::describe(command)
+        }
+
+        fn build(matches: &mut Matches) -> Result<Inner, Error> {
+            Inner::Fields::build(matches).map(Inner::from_values)
+        }
+    }
+
+    // helper trait implemented recursively for a list of variants, e.g `(Variant0, (Variant1, ()))`
+    pub trait CommandVariants<Root> {
+        fn subcommands(subcommands: &mut Vec<(String, &'static str)>);
+        /// Parses the variant named `name`, if there is one
+        fn parse(name: &str, command: String, args: Vec<String>) -> Option<Result<Root, Error>>;
+    }
+    // end of the recursion
+    impl<Root> CommandVariants<Root> for () {
+        fn subcommands(_: &mut Vec<(String, &'static str)>) {}
+        fn parse(_: &str, _: String, _: Vec<String>) -> Option<Result<Root, Error>> {
+            None
+        }
+    }
+    impl<Root, Head, Tail> CommandVariants<Root> for Cons<Head, Tail>
+    where
+        Head: Variant<Root = Root>,
+        Head: SizedStruct<Fields: CommandFields<Values = Head::FieldTypes>>,
+        Tail: CommandVariants<Root>,
+    {
+        fn subcommands(subcommands: &mut Vec<(String, &'static str)>) {
+            subcommands.push((kebab_case(Head::IDENT), Head::DOC));
+            Tail::subcommands(subcommands)
+        }
+
+        fn parse(name: &str, command: String, args: Vec<String>) -> Option<Result<Root, Error>> {
+            match kebab_case(Head::IDENT) == name {
+                true => Some(command::parse::<Head>(command, args)),
+                false => Tail::parse(name, command, args),
+            }
+        }
+    }
+}
+
+// the adapters of `reflector_serde::with`
+mod adapters {
+    use std::{fmt::Display, str::FromStr};
+
+    use reflector_serde::with::{DisplayFromStr, Reflect, Same};
+
+    use crate::impls::from_str;
+    use crate::{Arg, ArgAs, ArgKind, Error, ReflectArg};
+
+    impl<T: Arg> ArgAs<T> for Same {
+        const KIND: ArgKind = T::KIND;
+
+        fn from_args_as(name: &str, args: Vec<String>) -> Result<T, Error> {
+            T::from_args(name, args)
+        }
+
+        fn subcommands(subcommands: &mut Vec<(String, &'static str)>) {
+            T::subcommands(subcommands)
+        }
+    }
+
+    impl<T: ReflectArg> ArgAs<T> for Reflect {
+        const KIND: ArgKind = T::KIND;
+
+        fn from_args_as(name: &str, args: Vec<String>) -> Result<T, Error> {
+            T::from_args(name, args)
+        }
+
+        fn subcommands(subcommands: &mut Vec<(String, &'static str)>) {
+            T::subcommands(subcommands)
+        }
+    }
+
+    impl<T: FromStr<Err: Display>> ArgAs<T> for DisplayFromStr {
+        const KIND: ArgKind = ArgKind::Value {
+            required: true,
+            repeated: false,
+        };
+
+        fn from_args_as(name: &str, args: Vec<String>) -> Result<T, Error> {
+            from_str(name, args)
+        }
+    }
+}
+
+mod impls {
+    use std::{
+        ffi::OsString,
+        fmt::Display,
+        net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6},
+        path::PathBuf,
+        str::FromStr,
+    };
+
+    use crate::{Arg, ArgKind, Error};
+
+    // the value of a flag which can only be given once, parsed with `FromStr`
+    pub fn from_str<T: FromStr<Err: Display>>(name: &str, args: Vec<String>) -> Result<T, Error> {
+        let mut args = args.into_iter();
+        let value = match (args.next(), args.next()) {
+            (Some(value), None) => value,
+            (None, _) => return Err(Error::MissingFlag(name.into())),
+            (Some(_), Some(_)) => return Err(Error::Duplicate(name.into())),
+        };
+        match value.parse() {
+            Ok(value) => Ok(value),
+            Err(error) => Err(Error::InvalidValue {
+                flag: name.into(),
+                value,
+                message: error.to_string(),
+            }),
+        }
+    }
+
+    macro_rules! from_str {
+        ($($t:ty),*) => {$(
+            impl Arg for $t {
+                const KIND: ArgKind = ArgKind::Value { required: true, repeated: false };
+
+                fn from_args(name: &str, args: Vec<String>) -> Result<Self, Error> {
+                    from_str(name, args)
+                }
+            }
+        )*};
+    }
+
+    from_str!(
+        u8,
+        u16,
+        u32,
+        u64,
+        u128,
+        usize,
+        i8,
+        i16,
+        i32,
+        i64,
+        i128,
+        isize,
+        f32,
+        f64,
+        char,
+        String,
+        PathBuf,
+        OsString,
+        IpAddr,
+        Ipv4Addr,
+        Ipv6Addr,
+        SocketAddr,
+        SocketAddrV4,
+        SocketAddrV6
+    );
+
+    // `--switch`, true when given
+    impl Arg for bool {
+        const KIND: ArgKind = ArgKind::Switch;
+
+        fn from_args(_: &str, args: Vec<String>) -> Result<Self, Error> {
+            Ok(!args.is_empty())
+        }
+    }
+
+    impl<T: Arg> Arg for Option<T> {
+        const KIND: ArgKind = match T::KIND {
+            ArgKind::Value { repeated, .. } => ArgKind::Value {
+                required: false,
+                repeated,
+            },
+            ArgKind::Switch => ArgKind::Switch,
+            ArgKind::Subcommand { .. } => ArgKind::Subcommand { required: false },
+        };
+
+        fn from_args(name: &str, args: Vec<String>) -> Result<Self, Error> {
+            match args.is_empty() {
+                true => Ok(None),
+                false => T::from_args(name, args).map(Some),
+            }
+        }
+
+        fn subcommands(subcommands: &mut Vec<(String, &'static str)>) {
+            T::subcommands(subcommands)
+        }
+    }
+
+    // every occurrence of the flag is an element
+    impl<T: Arg> Arg for Vec<T> {
+        const KIND: ArgKind = match T::KIND {
+            ArgKind::Value {
+                repeated: false, ..
+            } => ArgKind::Value {
+                required: false,
+                repeated: true,
+            },
+            _ => panic!("only flags with a single value can be repeated"),
+        };
+
+        fn from_args(name: &str, args: Vec<String>) -> Result<Self, Error> {
+            args.into_iter()
+                .map(|arg| T::from_args(name, vec![arg]))
+                .collect()
+        }
+    }
+}
@@ -0,0 +1,254 @@
+use std::path::PathBuf;
+
+use reflector::Introspect;
+use reflector_cli::{Error, help, try_parse_from};
+use reflector_serde::with;
+
+/// Serves files over HTTP.
+#[derive(Debug, PartialEq, Introspect)]
+pub struct App {
+    /// The address to bind
+    host: String,
+    /// The port to listen on,
+    /// 8080 by default
+    #[reflect(default = default_port)]
+    port: u16,
+    /// Prints every request
+    verbose: bool,
+    max_connections: Option<u32>,
+    /// Files to serve, in addition to the root
+    include: Vec<PathBuf>,
+    #[reflect(flatten)]
+    log: Log,
+    command: Option<Command>,
+}
+
+fn default_port() -> u16 {
+    8080
+}
+
+#[derive(Debug, PartialEq, Introspect)]
+pub struct Log {
+    /// Where to write the log
+    log_file: Option<PathBuf>,
+}
+
+#[derive(Debug, PartialEq, Introspect)]
+#[reflect(impl(reflector_cli::Arg))]
+pub enum Command {
+    /// Starts serving
+    ///
+    /// Runs until interrupted.
+    Start {
+        /// The directory to serve
+        root: PathBuf,
+    },
+    /// Reloads the configuration
+    ReloadConfig {
+        #[reflect(flatten)]
+        log: Log,
+    },
+    Stop,
+}
+
+fn parse<T: reflector_cli::ReflectCommand>(args: &str) -> Result<T, Error> {
+    try_parse_from(args.split_whitespace())
+}
+
+#[test]
+fn flags() {
+    assert_eq!(
+        parse::<App>("/usr/bin/app --host localhost"),
+        Ok(App {
+            host: "localhost".into(),
+            port: 8080,
+            verbose: false,
+            max_connections: None,
+            include: vec![],
+            log: Log { log_file: None },
+            command: None,
+        })
+    );
+    assert_eq!(
+        parse::<App>(
+            "app --verbose --include a --port=80 --host=::1 --include b --max-connections 3 \
+             --log-file app.log"
+        ),
+        Ok(App {
+            host: "::1".into(),
+            port: 80,
+            verbose: true,
+            max_connections: Some(3),
+            include: vec!["a".into(), "b".into()],
+            log: Log {
+                log_file: Some("app.log".into())
+            },
+            command: None,
+        })
+    );
+}
+
+#[test]
+fn subcommands() {
+    let app = parse::<App>("app --host h start --root /srv").unwrap();
+    assert_eq!(
+        app.command,
+        Some(Command::Start {
+            root: "/srv".into()
+        })
+    );
+    let app = parse::<App>("app --host h reload-config --log-file x").unwrap();
+    assert_eq!(
+        app.command,
+        Some(Command::ReloadConfig {
+            log: Log {
+                log_file: Some("x".into())
+            }
+        })
+    );
+    let app = parse::<App>("app --host h stop").unwrap();
+    assert_eq!(app.command, Some(Command::Stop));
+
+    // flags after the subcommand belong to it
+    assert_eq!(
+        parse::<App>("app --host h stop --verbose"),
+        Err(Error::UnknownFlag("--verbose".into()))
+    );
+}
+
+#[test]
+fn help_text() {
+    assert_eq!(
+        help::<App>("app"),
+        "Serves files over HTTP.
+
+Usage: app [OPTIONS] --host <HOST> [COMMAND]
+
+Options:
+  --host <HOST>                        The address to bind
+  --port <PORT>                        The port to listen on, 8080 by default
+  --verbose                            Prints every request
+  --max-connections <MAX_CONNECTIONS>
+  --include <INCLUDE>...               Files to serve, in addition to the root
+  --log-file <LOG_FILE>                Where to write the log
+  -h, --help                           Print help
+
+Commands:
+  start          Starts serving
+  reload-config  Reloads the configuration
+  stop
+"
+    );
+    assert_eq!(
+        parse::<App>("app --host h start --help"),
+        Err(Error::Help(
+            "Starts serving
+
+Runs until interrupted.
+
+Usage: app start [OPTIONS] --root <ROOT>
+
+Options:
+  --root <ROOT>  The directory to serve
+  -h, --help     Print help
+"
+            .into()
+        ))
+    );
+}
+
+#[test]
+fn errors() {
+    let error = |args| parse::<App>(args).unwrap_err();
+    assert_eq!(error("app"), Error::MissingFlag("--host".into()));
+    assert_eq!(error("app --host"), Error::MissingValue("--host".into()));
+    assert_eq!(
+        error("app --host a --host b"),
+        Error::Duplicate("--host".into())
+    );
+    assert_eq!(error("app --hots a"), Error::UnknownFlag("--hots".into()));
+    assert_eq!(
+        error("app --host a --verbose=yes"),
+        Error::UnexpectedValue("--verbose".into())
+    );
+    assert_eq!(
+        error("app --host a --port high"),
+        Error::InvalidValue {
+            flag: "--port".into(),
+            value: "high".into(),
+            message: "invalid digit found in string".into(),
+        }
+    );
+    assert_eq!(
+        error("app --host a restart"),
+        Error::UnknownSubcommand("restart".into())
+    );
+    assert_eq!(
+        error("app --host a start"),
+        Error::MissingFlag("--root".into())
+    );
+    assert_eq!(
+        error("app --host a start --root"),
+        Error::MissingValue("--root".into())
+    );
+    assert_eq!(
+        error("app --host a start --root /srv stop"),
+        Error::UnexpectedArgument("stop".into())
+    );
+    assert_eq!(
+        error("app --host a start --root /srv stop").to_string(),
+        "unexpected argument 'stop'"
+    );
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Level(u8);
+
+impl std::str::FromStr for Level {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "low" => Ok(Level(0)),
+            "high" => Ok(Level(1)),
+            _ => Err(format!("expected low or high, got {s}")),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Introspect)]
+pub enum Action {
+    Run,
+    Check { strict: bool },
+}
+
+#[derive(Debug, PartialEq, Introspect)]
+pub struct Tool {
+    #[reflect(adapter = with::DisplayFromStr)]
+    level: Level,
+    #[reflect(adapter = with::Reflect)]
+    action: Action,
+}
+
+#[test]
+fn adapters() {
+    assert_eq!(
+        parse::<Tool>("tool --level high check --strict"),
+        Ok(Tool {
+            level: Level(1),
+            action: Action::Check { strict: true },
+        })
+    );
+    assert_eq!(
+        parse::<Tool>("tool --level low"),
+        Err(Error::MissingSubcommand("tool".into()))
+    );
+    assert_eq!(
+        parse::<Tool>("tool --level mid run"),
+        Err(Error::InvalidValue {
+            flag: "--level".into(),
+            value: "mid".into(),
+            message: "expected low or high, got mid".into(),
+        })
+    );
+}
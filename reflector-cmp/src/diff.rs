@@ -0,0 +1,573 @@
+//! Structural diffs between two values, see [`diff`].
+
+use std::fmt::{self, Debug, Display, Formatter, Write};
+
+use reflector::*;
+
+use crate::Comparator;
+
+/// Lists what changed from `a` to `b`, recursing into nested reflected types marked
+/// `#[reflect(impl(reflector_cmp::Diff))]` and std collections.
+///
+/// Fields marked `#[reflect(skip_compare)]` are ignored, those marked `#[reflect(compare_with =
+/// ..)]` are compared as a whole with the comparator, and the values of fields marked
+/// `#[reflect(sensitive)]` are replaced with `<redacted>`.
+pub fn diff<T: ReflectDiff>(a: &T, b: &T) -> Changes {
+    let mut differ = Differ::default();
+    a.diff_with(b, &mut differ);
+    differ.finish()
+}
+
+/// Compares two values and reports their differences to a [`Differ`].
+///
+/// Implemented for std types, and for reflected types marked
+/// `#[reflect(impl(reflector_cmp::Diff))]`.
+pub trait Diff: Debug {
+    fn diff_with(&self, other: &Self, differ: &mut Differ);
+}
+
+/// Implements [`Diff`] for a reflected type through [`ReflectDiff`], with
+/// `#[reflect(impl(reflector_cmp::Diff))]`. Type parameters have to implement `Diff`.
+#[macro_export]
+macro_rules! Diff {
+    (
+        lifetimes [$($lifetime:lifetime),*]
+        types [$($param:ident),*]
+        impl [$($params:tt)*] $ty:ty
+        where [$($where:tt)*]
+    ) => {
+        impl<$($params)*> $crate::Diff for $ty
+        where
+            $($where)*
+            $($param: $crate::Diff,)*
+        {
+            fn diff_with(&self, other: &Self, differ: &mut $crate::Differ) {
+                $crate::ReflectDiff::diff_with(self, other, differ)
+            }
+        }
+    };
+}
+
+/// A step on the way from a value to one of its parts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Segment {
+    /// A named field, `.name`
+    Field(&'static str),
+    /// A field of a tuple struct or variant, or of a tuple, `.0`
+    Position(u32),
+    /// An element of a sequence, `[0]`
+    Index(usize),
+    /// An entry of a map, by the `Debug` output of its key, `["key"]`
+    Key(String),
+}
+
+/// The location of a change, e.g. `server.port` or `routes[2].methods`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Path(pub Vec<Segment>);
+
+impl Path {
+    /// Whether the path is `prefix` or inside of it, e.g. `server.port` is inside `server` but
+    /// `servers` is not.
+    pub fn starts_with(&self, prefix: &str) -> bool {
+        let path = self.to_string();
+        path.strip_prefix(prefix).is_some_and(|rest| {
+            prefix.is_empty() || rest.is_empty() || rest.starts_with(['.', '['])
+        })
+    }
+}
+
+impl Display for Path {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (i, segment) in self.0.iter().enumerate() {
+            if i > 0 && matches!(segment, Segment::Field(_) | Segment::Position(_)) {
+                f.write_char('.')?;
+            }
+            match segment {
+                Segment::Field(name) => f.write_str(name)?,
+                Segment::Position(position) => write!(f, "{position}")?,
+                Segment::Index(index) => write!(f, "[{index}]")?,
+                Segment::Key(key) => write!(f, "[{key}]")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What happened at a [`Path`], with values rendered using their `Debug` impls.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ChangeKind {
+    Modified {
+        from: String,
+        to: String,
+    },
+    /// A different variant of an enum is active
+    Variant {
+        from: &'static str,
+        to: &'static str,
+    },
+    /// An element of a sequence or set, or an entry of a map, was added
+    Added(String),
+    Removed(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Change {
+    pub path: Path,
+    pub kind: ChangeKind,
+}
+
+impl Display for Change {
+    // `server.port: 80 -> 8080`
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if !self.path.0.is_empty() {
+            write!(f, "{}: ", self.path)?;
+        }
+        match &self.kind {
+            ChangeKind::Modified { from, to } => write!(f, "{from} -> {to}"),
+            ChangeKind::Variant { from, to } => write!(f, "variant {from} -> {to}"),
+            ChangeKind::Added(value) => write!(f, "added {value}"),
+            ChangeKind::Removed(value) => write!(f, "removed {value}"),
+        }
+    }
+}
+
+/// The result of [`diff`], in the order of the fields and elements. Displayed as one change per
+/// line.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Changes(pub Vec<Change>);
+
+impl Changes {
+    /// Whether anything at or inside `prefix` changed, see [`Path::starts_with`].
+    pub fn affects(&self, prefix: &str) -> bool {
+        self.0.iter().any(|change| change.path.starts_with(prefix))
+    }
+}
+
+impl std::ops::Deref for Changes {
+    type Target = [Change];
+
+    fn deref(&self) -> &[Change] {
+        &self.0
+    }
+}
+
+impl IntoIterator for Changes {
+    type Item = Change;
+    type IntoIter = std::vec::IntoIter<Change>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl Display for Changes {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for change in &self.0 {
+            writeln!(f, "{change}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Collects the changes found by [`Diff`] impls, keeping track of the current path.
+#[derive(Default)]
+pub struct Differ {
+    path: Vec<Segment>,
+    changes: Vec<Change>,
+    redact: bool,
+}
+
+impl Differ {
+    /// Diffs a part of the values, e.g. a field or an element
+    pub fn nested(&mut self, segment: Segment, f: impl FnOnce(&mut Self)) {
+        self.path.push(segment);
+        f(self);
+        self.path.pop();
+    }
+
+    pub fn modified(&mut self, from: &dyn Debug, to: &dyn Debug) {
+        let kind = ChangeKind::Modified {
+            from: self.render(from),
+            to: self.render(to),
+        };
+        self.push(kind);
+    }
+
+    pub fn variant(&mut self, from: &'static str, to: &'static str) {
+        self.push(ChangeKind::Variant { from, to });
+    }
+
+    pub fn added(&mut self, value: &dyn Debug) {
+        let kind = ChangeKind::Added(self.render(value));
+        self.push(kind);
+    }
+
+    pub fn removed(&mut self, value: &dyn Debug) {
+        let kind = ChangeKind::Removed(self.render(value));
+        self.push(kind);
+    }
+
+    pub fn finish(self) -> Changes {
+        Changes(self.changes)
+    }
+
+    // values inside a sensitive field are never rendered
+    fn sensitive(&mut self, f: impl FnOnce(&mut Self)) {
+        let redact = std::mem::replace(&mut self.redact, true);
+        f(self);
+        self.redact = redact;
+    }
+
+    fn render(&self, value: &dyn Debug) -> String {
+        if self.redact {
+            String::from("<redacted>")
+        } else {
+            format!("{value:?}")
+        }
+    }
+
+    fn push(&mut self, kind: ChangeKind) {
+        let path = Path(self.path.clone());
+        self.changes.push(Change { path, kind });
+    }
+}
+
+/// Diffs reflected types through their fields, implemented for any type which can be introspected
+/// and whose compared fields implement [`Diff`].
+pub trait ReflectDiff {
+    fn diff_with(&self, other: &Self, differ: &mut Differ);
+}
+
+impl<T> ReflectDiff for T
+where
+    T: Introspect,
+    T: DiffKind<T::Kind>,
+{
+    fn diff_with(&self, other: &Self, differ: &mut Differ) {
+        T::diff(self, other, differ)
+    }
+}
+
+pub trait DiffKind<Kind> {
+    fn diff(a: &Self, b: &Self, differ: &mut Differ);
+}
+
+impl<T> DiffKind<StructKind> for T
+where
+    T: Struct<Root = T>,
+    T::Fields: DiffFields<T>,
+{
+    fn diff(a: &T, b: &T, differ: &mut Differ) {
+        T::Fields::diff(a, b, differ)
+    }
+}
+
+impl<T> DiffKind<EnumKind> for T
+where
+    T: Enum,
+    T::Variants: DiffVariants<T>,
+{
+    fn diff(a: &T, b: &T, differ: &mut Differ) {
+        T::Variants::diff(a, b, differ)
+    }
+}
+
+// helper trait implemented recursively for a list of fields, e.g `(Field0, (Field1, ()))`
+pub trait DiffFields<P> {
+    fn diff(a: &P, b: &P, differ: &mut Differ);
+}
+// end of the recursion
+impl<P> DiffFields<P> for () {
+    fn diff(_: &P, _: &P, _: &mut Differ) {}
+}
+impl<P, Head, Tail> DiffFields<P> for Cons<Head, Tail>
+where
+    Head: Field<Root = P> + DiffField<<Head as Field>::CompareRepr>,
+    Tail: DiffFields<P>,
+{
+    fn diff(a: &P, b: &P, differ: &mut Differ) {
+        let segment = match Head::IDENT {
+            Some(ident) => Segment::Field(ident),
+            None => Segment::Position(Head::INDEX),
+        };
+        // the fields of a struct, or of the active variant of both values, are always present
+        let (a_value, b_value) = (Head::try_get_ref(a).unwrap(), Head::try_get_ref(b).unwrap());
+        differ.nested(segment, |differ| {
+            if Head::SENSITIVE {
+                differ.sensitive(|differ| Head::diff(a_value, b_value, differ))
+            } else {
+                Head::diff(a_value, b_value, differ)
+            }
+        });
+        Tail::diff(a, b, differ)
+    }
+}
+
+// a single field, dispatched on `Field::CompareRepr`
+pub trait DiffField<Repr>: Field {
+    fn diff(a: &Self::Type, b: &Self::Type, differ: &mut Differ);
+}
+impl<F: Field<Type: Diff>> DiffField<PlainRepr> for F {
+    fn diff(a: &F::Type, b: &F::Type, differ: &mut Differ) {
+        a.diff_with(b, differ)
+    }
+}
+impl<F: Field> DiffField<SkipRepr> for F {
+    fn diff(_: &F::Type, _: &F::Type, _: &mut Differ) {}
+}
+impl<F: Field<Type: Debug>, C: Comparator<F::Type>> DiffField<WithRepr<C>> for F {
+    fn diff(a: &F::Type, b: &F::Type, differ: &mut Differ) {
+        if !C::eq(a, b) {
+            differ.modified(&a, &b)
+        }
+    }
+}
+
+// helper trait implemented recursively for a list of variants, e.g `(Variant0, (Variant1, ()))`
+pub trait DiffVariants<P> {
+    fn diff(a: &P, b: &P, differ: &mut Differ);
+    fn active(p: &P) -> &'static str;
+}
+// end of the recursion, one of the variants is always active
+impl<P> DiffVariants<P> for () {
+    fn diff(_: &P, _: &P, _: &mut Differ) {
+        unreachable!()
+    }
+
+    fn active(_: &P) -> &'static str {
+        unreachable!()
+    }
+}
+impl<P, Head, Tail> DiffVariants<P> for Cons<Head, Tail>
+where
+    Head: Variant<Root = P>,
+    Head::Fields: DiffFields<P>,
+    Tail: DiffVariants<P>,
+{
+    fn diff(a: &P, b: &P, differ: &mut Differ) {
+        match (Head::is_active(a), Head::is_active(b)) {
+            (true, true) => Head::Fields::diff(a, b, differ),
+            (true, false) => differ.variant(Head::IDENT, Tail::active(b)),
+            (false, true) => differ.variant(Tail::active(a), Head::IDENT),
+            (false, false) => Tail::diff(a, b, differ),
+        }
+    }
+
+    fn active(p: &P) -> &'static str {
+        if Head::is_active(p) {
+            Head::IDENT
+        } else {
+            Tail::active(p)
+        }
+    }
+}
+
+mod impls {
+    use std::{
+        borrow::Cow,
+        collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+        fmt::Debug,
+        hash::{BuildHasher, Hash},
+        net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+        path::{Path, PathBuf},
+        rc::Rc,
+        sync::Arc,
+        time::Duration,
+    };
+
+    use super::{Diff, Differ, Segment};
+
+    // values which are compared as a whole
+    macro_rules! leaves {
+        ($($t:ty),*) => {$(
+            impl Diff for $t {
+                fn diff_with(&self, other: &Self, differ: &mut Differ) {
+                    if self != other {
+                        differ.modified(&self, &other)
+                    }
+                }
+            }
+        )*};
+    }
+
+    leaves!(
+        u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize
+    );
+    leaves!(
+        f32,
+        f64,
+        bool,
+        char,
+        (),
+        str,
+        String,
+        Path,
+        PathBuf,
+        Duration
+    );
+    leaves!(IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr);
+
+    // containers which only forward to their contents
+    macro_rules! forward {
+        ($($t:ty),*) => {$(
+            impl<T: Diff + ?Sized> Diff for $t {
+                fn diff_with(&self, other: &Self, differ: &mut Differ) {
+                    (**self).diff_with(other, differ)
+                }
+            }
+        )*};
+    }
+
+    forward!(&T, &mut T, Box<T>, Rc<T>, Arc<T>);
+
+    impl<T: Diff + ToOwned + ?Sized> Diff for Cow<'_, T>
+    where
+        T::Owned: Debug,
+    {
+        fn diff_with(&self, other: &Self, differ: &mut Differ) {
+            (**self).diff_with(other, differ)
+        }
+    }
+
+    impl<T: Diff> Diff for Option<T> {
+        fn diff_with(&self, other: &Self, differ: &mut Differ) {
+            match (self, other) {
+                (Some(a), Some(b)) => a.diff_with(b, differ),
+                (None, None) => {}
+                _ => differ.modified(self, other),
+            }
+        }
+    }
+
+    macro_rules! tuples {
+        ($(($($i:tt $t:ident),+)),*) => {$(
+            impl<$($t: Diff),+> Diff for ($($t,)+) {
+                fn diff_with(&self, other: &Self, differ: &mut Differ) {
+                    $(differ.nested(Segment::Position($i), |differ| {
+                        self.$i.diff_with(&other.$i, differ)
+                    });)+
+                }
+            }
+        )*};
+    }
+
+    tuples!(
+        (0 A),
+        (0 A, 1 B),
+        (0 A, 1 B, 2 C),
+        (0 A, 1 B, 2 C, 3 D),
+        (0 A, 1 B, 2 C, 3 D, 4 E),
+        (0 A, 1 B, 2 C, 3 D, 4 E, 5 F),
+        (0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G),
+        (0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G, 7 H)
+    );
+
+    // elements at the same index are diffed, the rest was added or removed at the end
+    fn sequence<'a, T: Diff + 'a>(
+        a: impl IntoIterator<Item = &'a T>,
+        b: impl IntoIterator<Item = &'a T>,
+        differ: &mut Differ,
+    ) {
+        let (mut a, mut b) = (a.into_iter(), b.into_iter());
+        for index in 0.. {
+            match (a.next(), b.next()) {
+                (Some(a), Some(b)) => {
+                    differ.nested(Segment::Index(index), |differ| a.diff_with(b, differ))
+                }
+                (Some(a), None) => differ.nested(Segment::Index(index), |differ| differ.removed(a)),
+                (None, Some(b)) => differ.nested(Segment::Index(index), |differ| differ.added(b)),
+                (None, None) => break,
+            }
+        }
+    }
+
+    impl<T: Diff> Diff for [T] {
+        fn diff_with(&self, other: &Self, differ: &mut Differ) {
+            sequence(self, other, differ)
+        }
+    }
+
+    impl<T: Diff, const N: usize> Diff for [T; N] {
+        fn diff_with(&self, other: &Self, differ: &mut Differ) {
+            sequence(self, other, differ)
+        }
+    }
+
+    impl<T: Diff> Diff for Vec<T> {
+        fn diff_with(&self, other: &Self, differ: &mut Differ) {
+            sequence(self, other, differ)
+        }
+    }
+
+    impl<T: Diff> Diff for VecDeque<T> {
+        fn diff_with(&self, other: &Self, differ: &mut Differ) {
+            sequence(self, other, differ)
+        }
+    }
+
+    // entries are matched by their keys, given in the order the changes are reported
+    fn map<'a, K: Debug + 'a, V: Diff + 'a>(
+        keys: impl IntoIterator<Item = &'a K>,
+        a: impl Fn(&K) -> Option<&'a V>,
+        b: impl Fn(&K) -> Option<&'a V>,
+        differ: &mut Differ,
+    ) {
+        for key in keys {
+            differ.nested(Segment::Key(format!("{key:?}")), |differ| {
+                match (a(key), b(key)) {
+                    (Some(a), Some(b)) => a.diff_with(b, differ),
+                    (Some(a), None) => differ.removed(a),
+                    (None, Some(b)) => differ.added(b),
+                    (None, None) => unreachable!(),
+                }
+            });
+        }
+    }
+
+    impl<K: Ord + Debug, V: Diff> Diff for BTreeMap<K, V> {
+        fn diff_with(&self, other: &Self, differ: &mut Differ) {
+            let keys = self.keys().chain(other.keys()).collect::<BTreeSet<_>>();
+            map(keys, |k| self.get(k), |k| other.get(k), differ)
+        }
+    }
+
+    impl<K: Eq + Hash + Debug, V: Diff, S: BuildHasher> Diff for HashMap<K, V, S> {
+        fn diff_with(&self, other: &Self, differ: &mut Differ) {
+            let keys = self
+                .keys()
+                .chain(other.keys().filter(|k| !self.contains_key(k)));
+            map(sorted(keys), |k| self.get(k), |k| other.get(k), differ)
+        }
+    }
+
+    // elements are reported as removed or added at the path of the set
+    impl<T: Ord + Debug> Diff for BTreeSet<T> {
+        fn diff_with(&self, other: &Self, differ: &mut Differ) {
+            self.difference(other)
+                .for_each(|value| differ.removed(value));
+            other.difference(self).for_each(|value| differ.added(value));
+        }
+    }
+
+    impl<T: Eq + Hash + Debug, S: BuildHasher> Diff for HashSet<T, S> {
+        fn diff_with(&self, other: &Self, differ: &mut Differ) {
+            sorted(self.difference(other))
+                .into_iter()
+                .for_each(|value| differ.removed(value));
+            sorted(other.difference(self))
+                .into_iter()
+                .for_each(|value| differ.added(value));
+        }
+    }
+
+    // hash based collections are reported in the order of their keys' `Debug` output, so the
+    // changes are the same every time
+    fn sorted<'a, T: Debug + 'a>(items: impl IntoIterator<Item = &'a T>) -> Vec<&'a T> {
+        let mut items = items
+            .into_iter()
+            .map(|item| (format!("{item:?}"), item))
+            .collect::<Vec<_>>();
+        items.sort_by(|(a, _), (b, _)| a.cmp(b));
+        items.into_iter().map(|(_, item)| item).collect()
+    }
+}
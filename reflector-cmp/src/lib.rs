@@ -0,0 +1,532 @@
+//! `PartialEq`, `Eq`, `PartialOrd`, `Ord` and `Hash` for reflected types.
+//!
+//! Fields are compared in declaration order, and variants by their index before their fields,
+//! like the std derives do for enums without explicit discriminants. Unlike the derives:
+//!
+//! - fields marked `#[reflect(skip_compare)]` are ignored, e.g. timestamps or caches,
+//! - fields marked `#[reflect(compare_with = module)]` are compared with `module::eq`,
+//!   `module::cmp` and `module::hash`, see [`Comparator`].
+//!
+//! Every trait is implemented as long as all compared fields implement it, so e.g. a struct with
+//! an `f64` field only gets `PartialEq` and `PartialOrd`.
+//!
+//! [`diff`] lists the changes between two values, following the same rules.
+
+mod diff;
+
+pub use diff::{Change, ChangeKind, Changes, Diff, Differ, Path, ReflectDiff, Segment, diff};
+pub use reflect::{Reflect, ReflectOwned};
+pub use reflector::Comparator;
+
+mod reflect {
+    use std::{
+        cmp::Ordering,
+        hash::{Hash, Hasher},
+    };
+
+    use reflector::*;
+
+    use crate::field::*;
+
+    /// Compares and hashes the referenced value through its fields.
+    #[derive(Clone, Copy)]
+    pub struct Reflect<'a, T>(pub &'a T);
+
+    /// Like [`Reflect`], but owning the value, e.g. to be used as the key of a map.
+    #[derive(Clone, Copy, Default)]
+    pub struct ReflectOwned<T>(pub T);
+
+    impl<T> PartialEq for Reflect<'_, T>
+    where
+        T: Introspect,
+        T: EqKind<T::Kind>,
+    {
+        fn eq(&self, other: &Self) -> bool {
+            T::eq(self.0, other.0)
+        }
+    }
+
+    impl<T> Eq for Reflect<'_, T>
+    where
+        T: Introspect,
+        T: EqKind<T::Kind> + TotalEqKind<T::Kind>,
+    {
+    }
+
+    impl<T> PartialOrd for Reflect<'_, T>
+    where
+        T: Introspect,
+        T: EqKind<T::Kind> + PartialOrdKind<T::Kind>,
+    {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            T::partial_cmp(self.0, other.0)
+        }
+    }
+
+    impl<T> Ord for Reflect<'_, T>
+    where
+        T: Introspect,
+        T: EqKind<T::Kind> + TotalEqKind<T::Kind> + PartialOrdKind<T::Kind> + OrdKind<T::Kind>,
+    {
+        fn cmp(&self, other: &Self) -> Ordering {
+            T::cmp(self.0, other.0)
+        }
+    }
+
+    impl<T> Hash for Reflect<'_, T>
+    where
+        T: Introspect,
+        T: HashKind<T::Kind>,
+    {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            T::hash(self.0, state)
+        }
+    }
+
+    impl<T> PartialEq for ReflectOwned<T>
+    where
+        for<'a> Reflect<'a, T>: PartialEq,
+    {
+        fn eq(&self, other: &Self) -> bool {
+            Reflect(&self.0) == Reflect(&other.0)
+        }
+    }
+
+    impl<T> Eq for ReflectOwned<T> where for<'a> Reflect<'a, T>: Eq {}
+
+    impl<T> PartialOrd for ReflectOwned<T>
+    where
+        for<'a> Reflect<'a, T>: PartialOrd,
+    {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Reflect(&self.0).partial_cmp(&Reflect(&other.0))
+        }
+    }
+
+    impl<T> Ord for ReflectOwned<T>
+    where
+        for<'a> Reflect<'a, T>: Ord,
+    {
+        fn cmp(&self, other: &Self) -> Ordering {
+            Reflect(&self.0).cmp(&Reflect(&other.0))
+        }
+    }
+
+    impl<T> Hash for ReflectOwned<T>
+    where
+        for<'a> Reflect<'a, T>: Hash,
+    {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            Reflect(&self.0).hash(state)
+        }
+    }
+
+    // one helper trait per std trait, so that e.g. a struct with a float field still gets
+    // `PartialEq`, implemented for structs and enums
+    pub trait EqKind<Kind> {
+        fn eq(a: &Self, b: &Self) -> bool;
+    }
+    pub trait TotalEqKind<Kind> {}
+    pub trait PartialOrdKind<Kind> {
+        fn partial_cmp(a: &Self, b: &Self) -> Option<Ordering>;
+    }
+    pub trait OrdKind<Kind> {
+        fn cmp(a: &Self, b: &Self) -> Ordering;
+    }
+    pub trait HashKind<Kind> {
+        fn hash<H: Hasher>(value: &Self, state: &mut H);
+    }
+
+    impl<T> EqKind<StructKind> for T
+    where
+        T: Struct<Root = T>,
+        T::Fields: EqFields<T>,
+    {
+        fn eq(a: &T, b: &T) -> bool {
+            T::Fields::eq(a, b)
+        }
+    }
+
+    impl<T> TotalEqKind<StructKind> for T
+    where
+        T: Struct<Root = T>,
+        T::Fields: TotalEqFields,
+    {
+    }
+
+    impl<T> PartialOrdKind<StructKind> for T
+    where
+        T: Struct<Root = T>,
+        T::Fields: PartialOrdFields<T>,
+    {
+        fn partial_cmp(a: &T, b: &T) -> Option<Ordering> {
+            T::Fields::partial_cmp(a, b)
+        }
+    }
+
+    impl<T> OrdKind<StructKind> for T
+    where
+        T: Struct<Root = T>,
+        T::Fields: OrdFields<T>,
+    {
+        fn cmp(a: &T, b: &T) -> Ordering {
+            T::Fields::cmp(a, b)
+        }
+    }
+
+    impl<T> HashKind<StructKind> for T
+    where
+        T: Struct<Root = T>,
+        T::Fields: HashFields<T>,
+    {
+        fn hash<H: Hasher>(value: &T, state: &mut H) {
+            T::Fields::hash(value, state)
+        }
+    }
+
+    impl<T> EqKind<EnumKind> for T
+    where
+        T: Enum,
+        T::Variants: EqVariants<T>,
+    {
+        fn eq(a: &T, b: &T) -> bool {
+            T::Variants::eq(a, b)
+        }
+    }
+
+    impl<T> TotalEqKind<EnumKind> for T
+    where
+        T: Enum,
+        T::Variants: TotalEqVariants,
+    {
+    }
+
+    impl<T> PartialOrdKind<EnumKind> for T
+    where
+        T: Enum,
+        T::Variants: PartialOrdVariants<T>,
+    {
+        fn partial_cmp(a: &T, b: &T) -> Option<Ordering> {
+            T::Variants::partial_cmp(a, b)
+        }
+    }
+
+    impl<T> OrdKind<EnumKind> for T
+    where
+        T: Enum,
+        T::Variants: OrdVariants<T>,
+    {
+        fn cmp(a: &T, b: &T) -> Ordering {
+            T::Variants::cmp(a, b)
+        }
+    }
+
+    impl<T> HashKind<EnumKind> for T
+    where
+        T: Enum,
+        T::Variants: HashVariants<T>,
+    {
+        fn hash<H: Hasher>(value: &T, state: &mut H) {
+            T::Variants::hash(value, state)
+        }
+    }
+
+    // helper traits implemented recursively for a list of fields, e.g `(Field0, (Field1, ()))`,
+    // combining the fields from left to right
+    pub trait EqFields<P> {
+        fn eq(a: &P, b: &P) -> bool;
+    }
+    pub trait TotalEqFields {}
+    pub trait PartialOrdFields<P> {
+        fn partial_cmp(a: &P, b: &P) -> Option<Ordering>;
+    }
+    pub trait OrdFields<P> {
+        fn cmp(a: &P, b: &P) -> Ordering;
+    }
+    pub trait HashFields<P> {
+        fn hash<H: Hasher>(value: &P, state: &mut H);
+    }
+
+    // end of the recursion
+    impl<P> EqFields<P> for () {
+        fn eq(_: &P, _: &P) -> bool {
+            true
+        }
+    }
+    impl TotalEqFields for () {}
+    impl<P> PartialOrdFields<P> for () {
+        fn partial_cmp(_: &P, _: &P) -> Option<Ordering> {
+            Some(Ordering::Equal)
+        }
+    }
+    impl<P> OrdFields<P> for () {
+        fn cmp(_: &P, _: &P) -> Ordering {
+            Ordering::Equal
+        }
+    }
+    impl<P> HashFields<P> for () {
+        fn hash<H: Hasher>(_: &P, _: &mut H) {}
+    }
+
+    impl<P, Head, Tail> EqFields<P> for Cons<Head, Tail>
+    where
+        Head: Field<Root = P> + EqField<<Head as Field>::CompareRepr>,
+        Tail: EqFields<P>,
+    {
+        fn eq(a: &P, b: &P) -> bool {
+            let (a_value, b_value) = values::<Head>(a, b);
+            <Head as EqField<_>>::eq(a_value, b_value) && Tail::eq(a, b)
+        }
+    }
+
+    impl<Head, Tail> TotalEqFields for Cons<Head, Tail>
+    where
+        Head: Field + TotalEqField<<Head as Field>::CompareRepr>,
+        Tail: TotalEqFields,
+    {
+    }
+
+    impl<P, Head, Tail> PartialOrdFields<P> for Cons<Head, Tail>
+    where
+        Head: Field<Root = P> + PartialOrdField<<Head as Field>::CompareRepr>,
+        Tail: PartialOrdFields<P>,
+    {
+        fn partial_cmp(a: &P, b: &P) -> Option<Ordering> {
+            let (a_value, b_value) = values::<Head>(a, b);
+            match <Head as PartialOrdField<_>>::partial_cmp(a_value, b_value) {
+                Some(Ordering::Equal) => Tail::partial_cmp(a, b),
+                ordering => ordering,
+            }
+        }
+    }
+
+    impl<P, Head, Tail> OrdFields<P> for Cons<Head, Tail>
+    where
+        Head: Field<Root = P> + OrdField<<Head as Field>::CompareRepr>,
+        Tail: OrdFields<P>,
+    {
+        fn cmp(a: &P, b: &P) -> Ordering {
+            let (a_value, b_value) = values::<Head>(a, b);
+            <Head as OrdField<_>>::cmp(a_value, b_value).then_with(|| Tail::cmp(a, b))
+        }
+    }
+
+    impl<P, Head, Tail> HashFields<P> for Cons<Head, Tail>
+    where
+        Head: Field<Root = P> + HashField<<Head as Field>::CompareRepr>,
+        Tail: HashFields<P>,
+    {
+        fn hash<H: Hasher>(value: &P, state: &mut H) {
+            <Head as HashField<_>>::hash(Head::try_get_ref(value).unwrap(), state);
+            Tail::hash(value, state)
+        }
+    }
+
+    // the fields of a struct, or of the active variant of both values, so they are always present
+    fn values<'a, F: Field>(a: &'a F::Root, b: &'a F::Root) -> (&'a F::Type, &'a F::Type) {
+        (F::try_get_ref(a).unwrap(), F::try_get_ref(b).unwrap())
+    }
+
+    // helper traits implemented recursively for a list of variants, e.g `(Variant0, (Variant1, ()))`.
+    // The variants before `Head` were already checked not to be active in either value, so if only
+    // one of them has `Head` active, the other one has a later variant.
+    pub trait EqVariants<P> {
+        fn eq(a: &P, b: &P) -> bool;
+    }
+    pub trait TotalEqVariants {}
+    pub trait PartialOrdVariants<P> {
+        fn partial_cmp(a: &P, b: &P) -> Option<Ordering>;
+    }
+    pub trait OrdVariants<P> {
+        fn cmp(a: &P, b: &P) -> Ordering;
+    }
+    pub trait HashVariants<P> {
+        fn hash<H: Hasher>(value: &P, state: &mut H);
+    }
+
+    // end of the recursion, one of the variants is always active
+    impl<P> EqVariants<P> for () {
+        fn eq(_: &P, _: &P) -> bool {
+            unreachable!()
+        }
+    }
+    impl TotalEqVariants for () {}
+    impl<P> PartialOrdVariants<P> for () {
+        fn partial_cmp(_: &P, _: &P) -> Option<Ordering> {
+            unreachable!()
+        }
+    }
+    impl<P> OrdVariants<P> for () {
+        fn cmp(_: &P, _: &P) -> Ordering {
+            unreachable!()
+        }
+    }
+    impl<P> HashVariants<P> for () {
+        fn hash<H: Hasher>(_: &P, _: &mut H) {
+            unreachable!()
+        }
+    }
+
+    impl<P, Head, Tail> EqVariants<P> for Cons<Head, Tail>
+    where
+        Head: Variant<Root = P>,
+        Head::Fields: EqFields<P>,
+        Tail: EqVariants<P>,
+    {
+        fn eq(a: &P, b: &P) -> bool {
+            match (Head::is_active(a), Head::is_active(b)) {
+                (true, true) => Head::Fields::eq(a, b),
+                (false, false) => Tail::eq(a, b),
+                _ => false,
+            }
+        }
+    }
+
+    impl<Head, Tail> TotalEqVariants for Cons<Head, Tail>
+    where
+        Head: Variant<Fields: TotalEqFields>,
+        Tail: TotalEqVariants,
+    {
+    }
+
+    impl<P, Head, Tail> PartialOrdVariants<P> for Cons<Head, Tail>
+    where
+        Head: Variant<Root = P>,
+        Head::Fields: PartialOrdFields<P>,
+        Tail: PartialOrdVariants<P>,
+    {
+        fn partial_cmp(a: &P, b: &P) -> Option<Ordering> {
+            match (Head::is_active(a), Head::is_active(b)) {
+                (true, true) => Head::Fields::partial_cmp(a, b),
+                (true, false) => Some(Ordering::Less),
+                (false, true) => Some(Ordering::Greater),
+                (false, false) => Tail::partial_cmp(a, b),
+            }
+        }
+    }
+
+    impl<P, Head, Tail> OrdVariants<P> for Cons<Head, Tail>
+    where
+        Head: Variant<Root = P>,
+        Head::Fields: OrdFields<P>,
+        Tail: OrdVariants<P>,
+    {
+        fn cmp(a: &P, b: &P) -> Ordering {
+            match (Head::is_active(a), Head::is_active(b)) {
+                (true, true) => Head::Fields::cmp(a, b),
+                (true, false) => Ordering::Less,
+                (false, true) => Ordering::Greater,
+                (false, false) => Tail::cmp(a, b),
+            }
+        }
+    }
+
+    impl<P, Head, Tail> HashVariants<P> for Cons<Head, Tail>
+    where
+        Head: Variant<Root = P>,
+        Head::Fields: HashFields<P>,
+        Tail: HashVariants<P>,
+    {
+        fn hash<H: Hasher>(value: &P, state: &mut H) {
+            if Head::is_active(value) {
+                Head::INDEX.hash(state);
+                Head::Fields::hash(value, state)
+            } else {
+                Tail::hash(value, state)
+            }
+        }
+    }
+}
+
+// single fields, dispatched on `Field::CompareRepr`
+mod field {
+    use std::{
+        cmp::Ordering,
+        hash::{Hash, Hasher},
+    };
+
+    use reflector::{Field, PlainRepr, SkipRepr, WithRepr};
+
+    use crate::Comparator;
+
+    pub trait EqField<Repr>: Field {
+        fn eq(a: &Self::Type, b: &Self::Type) -> bool;
+    }
+    pub trait TotalEqField<Repr>: Field {}
+    pub trait PartialOrdField<Repr>: Field {
+        fn partial_cmp(a: &Self::Type, b: &Self::Type) -> Option<Ordering>;
+    }
+    pub trait OrdField<Repr>: Field {
+        fn cmp(a: &Self::Type, b: &Self::Type) -> Ordering;
+    }
+    pub trait HashField<Repr>: Field {
+        fn hash<H: Hasher>(value: &Self::Type, state: &mut H);
+    }
+
+    // the field type's own impls
+    impl<F: Field<Type: PartialEq>> EqField<PlainRepr> for F {
+        fn eq(a: &F::Type, b: &F::Type) -> bool {
+            a == b
+        }
+    }
+    impl<F: Field<Type: Eq>> TotalEqField<PlainRepr> for F {}
+    impl<F: Field<Type: PartialOrd>> PartialOrdField<PlainRepr> for F {
+        fn partial_cmp(a: &F::Type, b: &F::Type) -> Option<Ordering> {
+            a.partial_cmp(b)
+        }
+    }
+    impl<F: Field<Type: Ord>> OrdField<PlainRepr> for F {
+        fn cmp(a: &F::Type, b: &F::Type) -> Ordering {
+            a.cmp(b)
+        }
+    }
+    impl<F: Field<Type: Hash>> HashField<PlainRepr> for F {
+        fn hash<H: Hasher>(value: &F::Type, state: &mut H) {
+            value.hash(state)
+        }
+    }
+
+    // `#[reflect(skip_compare)]`, all values are equal
+    impl<F: Field> EqField<SkipRepr> for F {
+        fn eq(_: &F::Type, _: &F::Type) -> bool {
+            true
+        }
+    }
+    impl<F: Field> TotalEqField<SkipRepr> for F {}
+    impl<F: Field> PartialOrdField<SkipRepr> for F {
+        fn partial_cmp(_: &F::Type, _: &F::Type) -> Option<Ordering> {
+            Some(Ordering::Equal)
+        }
+    }
+    impl<F: Field> OrdField<SkipRepr> for F {
+        fn cmp(_: &F::Type, _: &F::Type) -> Ordering {
+            Ordering::Equal
+        }
+    }
+    impl<F: Field> HashField<SkipRepr> for F {
+        fn hash<H: Hasher>(_: &F::Type, _: &mut H) {}
+    }
+
+    // `#[reflect(compare_with = module)]`, a comparator is always a total order
+    impl<F: Field, C: Comparator<F::Type>> EqField<WithRepr<C>> for F {
+        fn eq(a: &F::Type, b: &F::Type) -> bool {
+            C::eq(a, b)
+        }
+    }
+    impl<F: Field, C: Comparator<F::Type>> TotalEqField<WithRepr<C>> for F {}
+    impl<F: Field, C: Comparator<F::Type>> PartialOrdField<WithRepr<C>> for F {
+        fn partial_cmp(a: &F::Type, b: &F::Type) -> Option<Ordering> {
+            Some(C::cmp(a, b))
+        }
+    }
+    impl<F: Field, C: Comparator<F::Type>> OrdField<WithRepr<C>> for F {
+        fn cmp(a: &F::Type, b: &F::Type) -> Ordering {
+            C::cmp(a, b)
+        }
+    }
+    impl<F: Field, C: Comparator<F::Type>> HashField<WithRepr<C>> for F {
+        fn hash<H: Hasher>(value: &F::Type, state: &mut H) {
+            C::hash(value, state)
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use std::{
+    cmp::Ordering,
+    collections::{BTreeSet, HashSet},
+    hash::{BuildHasher, Hash, RandomState},
+};
+
+use reflector::Introspect;
+use reflector_cmp::{Reflect, ReflectOwned};
+
+fn hash<T: Hash>(value: &T, state: &RandomState) -> u64 {
+    state.hash_one(value)
+}
+
+// compares every pair of values with the derived impls and through reflection
+fn assert_same<T: Introspect + Ord + Hash>(values: &[T])
+where
+    for<'a> Reflect<'a, T>: Ord + Hash,
+{
+    let state = RandomState::new();
+    for a in values {
+        for b in values {
+            assert_eq!(Reflect(a) == Reflect(b), a == b);
+            assert_eq!(Reflect(a).partial_cmp(&Reflect(b)), a.partial_cmp(b));
+            assert_eq!(Reflect(a).cmp(&Reflect(b)), a.cmp(b));
+            if a == b {
+                assert_eq!(hash(&Reflect(a), &state), hash(&Reflect(b), &state));
+            }
+        }
+    }
+}
+
+#[test]
+fn structs() {
+    #[derive(Introspect, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct Unit;
+    #[derive(Introspect, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct Tuple(u8, &'static str);
+    #[derive(Introspect, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct Named<T> {
+        id: u64,
+        name: String,
+        inner: T,
+    }
+
+    assert_same(&[Unit]);
+    assert_same(&[Tuple(1, "a"), Tuple(1, "b"), Tuple(0, "c"), Tuple(1, "a")]);
+    assert_same(&[
+        Named {
+            id: 1,
+            name: String::from("b"),
+            inner: Some(3),
+        },
+        Named {
+            id: 1,
+            name: String::from("a"),
+            inner: None,
+        },
+        Named {
+            id: 1,
+            name: String::from("a"),
+            inner: Some(2),
+        },
+        Named {
+            id: 0,
+            name: String::from("z"),
+            inner: Some(9),
+        },
+    ]);
+}
+
+#[test]
+fn enums() {
+    #[derive(Introspect, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub enum Enum {
+        Unit,
+        Tuple(u8, char),
+        Named { a: i32, b: Vec<u8> },
+    }
+
+    assert_same(&[
+        Enum::Named { a: 1, b: vec![2] },
+        Enum::Tuple(2, 'a'),
+        Enum::Unit,
+        Enum::Named { a: 1, b: vec![] },
+        Enum::Tuple(1, 'b'),
+        Enum::Tuple(2, 'a'),
+        Enum::Named { a: -1, b: vec![3] },
+    ]);
+}
+
+#[test]
+fn partial() {
+    #[derive(Introspect)]
+    pub struct Point {
+        x: f64,
+        y: f64,
+    }
+
+    let a = Point { x: 1.0, y: 2.0 };
+    let b = Point {
+        x: 1.0,
+        y: f64::NAN,
+    };
+    assert!(Reflect(&a) == Reflect(&a));
+    assert!(Reflect(&b) != Reflect(&b));
+    assert_eq!(Reflect(&a).partial_cmp(&Reflect(&b)), None);
+    assert_eq!(
+        Reflect(&a).partial_cmp(&Reflect(&Point {
+            x: 0.5,
+            y: f64::NAN
+        })),
+        Some(Ordering::Greater)
+    );
+}
+
+#[test]
+fn skip_compare() {
+    #[derive(Introspect)]
+    pub struct Event {
+        name: &'static str,
+        #[reflect(skip_compare)]
+        timestamp: u64,
+    }
+
+    let events = [
+        Event {
+            name: "start",
+            timestamp: 1,
+        },
+        Event {
+            name: "stop",
+            timestamp: 2,
+        },
+        Event {
+            name: "start",
+            timestamp: 3,
+        },
+    ];
+    let unique: HashSet<_> = events.iter().map(Reflect).collect();
+    assert_eq!(unique.len(), 2);
+    assert!(unique.contains(&Reflect(&Event {
+        name: "stop",
+        timestamp: 0,
+    })));
+    assert_eq!(
+        Reflect(&events[0]).cmp(&Reflect(&events[2])),
+        Ordering::Equal
+    );
+}
+
+mod case_insensitive {
+    use std::{cmp::Ordering, hash::Hasher};
+
+    pub fn eq(a: &str, b: &str) -> bool {
+        a.eq_ignore_ascii_case(b)
+    }
+
+    pub fn cmp(a: &str, b: &str) -> Ordering {
+        a.bytes()
+            .map(|b| b.to_ascii_lowercase())
+            .cmp(b.bytes().map(|b| b.to_ascii_lowercase()))
+    }
+
+    pub fn hash<H: Hasher>(value: &str, state: &mut H) {
+        for b in value.bytes() {
+            state.write_u8(b.to_ascii_lowercase());
+        }
+        state.write_u8(0xff);
+    }
+}
+
+#[test]
+fn compare_with() {
+    #[derive(Introspect)]
+    pub enum Key {
+        Id(u32),
+        Name {
+            #[reflect(compare_with = case_insensitive)]
+            name: String,
+            #[reflect(skip_compare)]
+            hits: usize,
+        },
+    }
+
+    let name = |name: &str, hits| Key::Name {
+        name: String::from(name),
+        hits,
+    };
+    let keys: BTreeSet<_> = [
+        name("b", 0),
+        Key::Id(2),
+        name("A", 1),
+        name("B", 2),
+        Key::Id(1),
+    ]
+    .into_iter()
+    .map(ReflectOwned)
+    .collect();
+    let names: Vec<_> = keys
+        .iter()
+        .map(|ReflectOwned(key)| match key {
+            Key::Id(id) => id.to_string(),
+            Key::Name { name, .. } => name.clone(),
+        })
+        .collect();
+    assert_eq!(names[..3], ["1", "2", "A"]);
+    assert!(names[3].eq_ignore_ascii_case("b"));
+
+    let state = RandomState::new();
+    assert_eq!(
+        hash(&ReflectOwned(name("Hello", 1)), &state),
+        hash(&ReflectOwned(name("hELLO", 2)), &state)
+    );
+}
@@ -0,0 +1,172 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use reflector::Introspect;
+use reflector_cmp::{ChangeKind, Segment, diff};
+
+#[derive(Debug, Introspect)]
+#[reflect(impl(reflector_cmp::Diff))]
+pub struct Server {
+    host: String,
+    port: u16,
+    #[reflect(sensitive)]
+    password: String,
+}
+
+#[derive(Debug, Introspect)]
+#[reflect(impl(reflector_cmp::Diff))]
+pub enum Mode {
+    Fast,
+    Slow { delay: u32 },
+    Custom(u8, char),
+}
+
+#[derive(Debug, Introspect)]
+pub struct Config {
+    server: Server,
+    mode: Mode,
+    workers: Option<usize>,
+    routes: Vec<&'static str>,
+    limits: BTreeMap<&'static str, u32>,
+    tags: HashSet<&'static str>,
+    #[reflect(skip_compare)]
+    loaded_at: u64,
+}
+
+fn config() -> Config {
+    Config {
+        server: Server {
+            host: String::from("localhost"),
+            port: 80,
+            password: String::from("hunter2"),
+        },
+        mode: Mode::Fast,
+        workers: None,
+        routes: vec!["/", "/api"],
+        limits: BTreeMap::from([("a", 1), ("b", 2)]),
+        tags: HashSet::from(["x"]),
+        loaded_at: 1,
+    }
+}
+
+#[test]
+fn unchanged() {
+    let mut b = config();
+    b.loaded_at = 2;
+    let changes = diff(&config(), &b);
+    assert!(changes.is_empty());
+    assert_eq!(changes.to_string(), "");
+}
+
+#[test]
+fn text() {
+    let a = config();
+    let mut b = config();
+    b.server.port = 8080;
+    b.server.password = String::from("hunter3");
+    b.mode = Mode::Slow { delay: 5 };
+    b.workers = Some(4);
+    b.routes = vec!["/", "/v2", "/health"];
+    b.limits.remove("a");
+    b.limits.insert("b", 3);
+    b.limits.insert("c", 4);
+    b.tags = HashSet::from(["y", "z"]);
+
+    assert_eq!(
+        diff(&a, &b).to_string(),
+        r#"server.port: 80 -> 8080
+server.password: <redacted> -> <redacted>
+mode: variant Fast -> Slow
+workers: None -> Some(4)
+routes[1]: "/api" -> "/v2"
+routes[2]: added "/health"
+limits["a"]: removed 1
+limits["b"]: 2 -> 3
+limits["c"]: added 4
+tags: removed "x"
+tags: added "y"
+tags: added "z"
+"#
+    );
+}
+
+#[test]
+fn structured() {
+    let a = config();
+    let mut b = config();
+    b.mode = Mode::Custom(1, 'a');
+    let mut c = config();
+    c.mode = Mode::Custom(1, 'b');
+
+    let changes = diff(&b, &c);
+    assert_eq!(changes.len(), 1);
+    assert_eq!(
+        changes[0].path.0,
+        [Segment::Field("mode"), Segment::Position(1)]
+    );
+    assert_eq!(
+        changes[0].kind,
+        ChangeKind::Modified {
+            from: String::from("'a'"),
+            to: String::from("'b'"),
+        }
+    );
+    assert_eq!(changes[0].to_string(), "mode.1: 'a' -> 'b'");
+
+    let changes = diff(&a, &b);
+    assert_eq!(
+        changes[0].kind,
+        ChangeKind::Variant {
+            from: "Fast",
+            to: "Custom",
+        }
+    );
+}
+
+#[test]
+fn affects() {
+    let a = config();
+    let mut b = config();
+    b.server.host = String::from("example.com");
+    b.limits.insert("d", 0);
+
+    let changes = diff(&a, &b);
+    assert!(changes.affects("server"));
+    assert!(changes.affects("server.host"));
+    assert!(!changes.affects("server.port"));
+    assert!(!changes.affects("serv"));
+    assert!(changes.affects("limits"));
+    assert!(changes.affects(r#"limits["d"]"#));
+    assert!(!changes.affects("routes"));
+}
+
+#[test]
+fn nested() {
+    #[derive(Debug, Introspect)]
+    #[reflect(impl(reflector_cmp::Diff))]
+    pub struct Pair(Server, (u8, Vec<Mode>));
+
+    #[derive(Debug, Introspect)]
+    pub struct Cluster {
+        servers: HashMap<&'static str, Pair>,
+    }
+
+    let server = |port| Server {
+        host: String::from("localhost"),
+        port,
+        password: String::new(),
+    };
+    let a = Cluster {
+        servers: HashMap::from([("a", Pair(server(1), (0, vec![Mode::Fast])))]),
+    };
+    let b = Cluster {
+        servers: HashMap::from([("a", Pair(server(2), (1, vec![Mode::Slow { delay: 1 }])))]),
+    };
+
+    assert_eq!(
+        diff(&a, &b).to_string(),
+        r#"servers["a"].0.port: 1 -> 2
+servers["a"].1.0: 0 -> 1
+servers["a"].1.1[0]: variant Fast -> Slow
+"#
+    );
+}
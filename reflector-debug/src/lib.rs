@@ -0,0 +1,566 @@
+//! `Debug` for reflected types, with the same output as `#[derive(Debug)]`.
+//!
+//! In addition, fields marked `#[reflect(sensitive)]` can be redacted and long collections
+//! truncated, see [`Options`].
+//!
+//! Fields are formatted through [`DebugValue`], which std types implement, and types marked
+//! `#[reflect(impl(reflector_debug::DebugValue))]` through reflection, so they respect the options
+//! when nested inside other reflected types. Fields with an adapter, e.g. `#[reflect(with = module)]`,
+//! are formatted with the `Debug` impl of their type instead, which is how fields of foreign types
+//! are formatted.
+
+use std::fmt::{self, Formatter};
+
+pub use options::Options;
+pub use reflect::{Reflect, ReflectDebug, ReflectWith};
+
+/// A value which can be formatted with [`Options`].
+pub trait DebugValue {
+    fn fmt_value(&self, f: &mut Formatter<'_>, options: Options) -> fmt::Result;
+}
+
+/// Implements [`DebugValue`] for a reflected type through [`ReflectDebug`], and `Debug` with the
+/// default [`Options`], with `#[reflect(impl(reflector_debug::DebugValue))]`. Type parameters have
+/// to implement `DebugValue`.
+#[macro_export]
+macro_rules! DebugValue {
+    (
+        lifetimes [$($lifetime:lifetime),*]
+        types [$($param:ident),*]
+        impl [$($params:tt)*] $ty:ty
+        where [$($where:tt)*]
+    ) => {
+        impl<$($params)*> ::core::fmt::Debug for $ty
+        where
+            $($where)*
+            $($param: $crate::DebugValue,)*
+        {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                ::core::fmt::Debug::fmt(&$crate::Reflect(self), f)
+            }
+        }
+
+        impl<$($params)*> $crate::DebugValue for $ty
+        where
+            $($where)*
+            $($param: $crate::DebugValue,)*
+        {
+            fn fmt_value(
+                &self,
+                f: &mut ::core::fmt::Formatter<'_>,
+                options: $crate::Options,
+            ) -> ::core::fmt::Result {
+                <Self as $crate::ReflectDebug>::fmt_reflect(self, f, options)
+            }
+        }
+    };
+}
+
+mod options {
+    use std::cell::Cell;
+
+    /// How [`ReflectWith`](crate::ReflectWith) formats values.
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub struct Options {
+        /// Print `<redacted>` instead of the values of fields marked `#[reflect(sensitive)]`
+        pub redact: bool,
+        /// Print at most this many items of collections, followed by `..`
+        pub max_items: Option<usize>,
+    }
+
+    impl Options {
+        pub fn redact(self) -> Self {
+            Self {
+                redact: true,
+                ..self
+            }
+        }
+
+        pub fn truncate(self, max_items: usize) -> Self {
+            Self {
+                max_items: Some(max_items),
+                ..self
+            }
+        }
+    }
+
+    thread_local! {
+        // options of the innermost `ReflectWith` being formatted
+        static CURRENT: Cell<Options> = const {
+            Cell::new(Options {
+                redact: false,
+                max_items: None,
+            })
+        };
+    }
+
+    pub(crate) fn current() -> Options {
+        CURRENT.get()
+    }
+
+    // `Debug` impls of nested types can't be passed the options, so they are looked up instead
+    pub(crate) fn scoped<R>(options: Options, f: impl FnOnce() -> R) -> R {
+        struct Restore(Options);
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                CURRENT.set(self.0);
+            }
+        }
+
+        let _restore = Restore(CURRENT.replace(options));
+        f()
+    }
+}
+
+mod reflect {
+    use std::fmt::{self, Debug, DebugStruct, DebugTuple, Formatter};
+
+    use reflector::*;
+
+    use crate::{
+        DebugValue, Options,
+        options::{current, scoped},
+    };
+
+    /// Formats the value like `#[derive(Debug)]`, using the options of an enclosing
+    /// [`ReflectWith`], if any.
+    pub struct Reflect<'a, T>(pub &'a T);
+
+    /// Formats the value with the given options.
+    pub struct ReflectWith<'a, T>(pub &'a T, pub Options);
+
+    impl<'a, T> Reflect<'a, T> {
+        pub fn with(self, options: Options) -> ReflectWith<'a, T> {
+            ReflectWith(self.0, options)
+        }
+    }
+
+    impl<T: ReflectDebug> Debug for Reflect<'_, T> {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            T::fmt_reflect(self.0, f, current())
+        }
+    }
+
+    impl<T: ReflectDebug> Debug for ReflectWith<'_, T> {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            scoped(self.1, || T::fmt_reflect(self.0, f, self.1))
+        }
+    }
+
+    /// Formatting of reflected types, implemented for any struct or enum which can be
+    /// introspected and whose fields implement [`DebugValue`], or `Debug` for fields with an adapter.
+    pub trait ReflectDebug {
+        fn fmt_reflect(&self, f: &mut Formatter<'_>, options: Options) -> fmt::Result;
+    }
+
+    impl<T> ReflectDebug for T
+    where
+        T: Introspect,
+        T: DebugKind<T::Kind>,
+    {
+        fn fmt_reflect(&self, f: &mut Formatter<'_>, options: Options) -> fmt::Result {
+            <T as DebugKind<_>>::fmt(self, f, options)
+        }
+    }
+
+    pub trait DebugKind<Kind> {
+        fn fmt(&self, f: &mut Formatter<'_>, options: Options) -> fmt::Result;
+    }
+
+    impl<T> DebugKind<StructKind> for T
+    where
+        T: Struct<Root = T>,
+        T: DebugShape<T, T::Shape>,
+    {
+        fn fmt(&self, f: &mut Formatter<'_>, options: Options) -> fmt::Result {
+            <T as DebugShape<_, _>>::fmt(self, f, options)
+        }
+    }
+
+    impl<T> DebugKind<EnumKind> for T
+    where
+        T: Enum,
+        T::Variants: DebugVariants<T>,
+    {
+        fn fmt(&self, f: &mut Formatter<'_>, options: Options) -> fmt::Result {
+            T::Variants::fmt(self, f, options)
+        }
+    }
+
+    pub trait DebugShape<Root, Shape> {
+        fn fmt(root: &Root, f: &mut Formatter<'_>, options: Options) -> fmt::Result;
+    }
+
+    // struct S; and enum E { S, .. }
+    impl<S: Struct> DebugShape<S::Root, UnitShape> for S {
+        fn fmt(_: &S::Root, f: &mut Formatter<'_>, _: Options) -> fmt::Result {
+            f.write_str(S::IDENT)
+        }
+    }
+
+    // struct S(..); and enum E { S(..), .. }
+    impl<S: Struct> DebugShape<S::Root, TupleShape> for S
+    where
+        S::Fields: DebugFields<S::Root>,
+    {
+        fn fmt(root: &S::Root, f: &mut Formatter<'_>, options: Options) -> fmt::Result {
+            let mut tuple = f.debug_tuple(S::IDENT);
+            S::Fields::tuple(root, &mut tuple, options);
+            tuple.finish()
+        }
+    }
+
+    // struct S { .. } and enum E { S { .. }, .. }
+    impl<S: Struct> DebugShape<S::Root, NamedShape> for S
+    where
+        S::Fields: DebugFields<S::Root>,
+    {
+        fn fmt(root: &S::Root, f: &mut Formatter<'_>, options: Options) -> fmt::Result {
+            let mut named = f.debug_struct(S::IDENT);
+            S::Fields::named(root, &mut named, options);
+            named.finish()
+        }
+    }
+
+    // helper trait implemented recursively for a list of fields, e.g `(Field0, (Field1, ()))`
+    pub trait DebugFields<P> {
+        fn named(parent: &P, s: &mut DebugStruct<'_, '_>, options: Options);
+        fn tuple(parent: &P, t: &mut DebugTuple<'_, '_>, options: Options);
+    }
+    // end of the recursion
+    impl<P> DebugFields<P> for () {
+        fn named(_: &P, _: &mut DebugStruct<'_, '_>, _: Options) {}
+        fn tuple(_: &P, _: &mut DebugTuple<'_, '_>, _: Options) {}
+    }
+    impl<P, Head, Tail> DebugFields<P> for Cons<Head, Tail>
+    where
+        Head: DebugField<<Head as Field>::Repr, Root = P>,
+        Tail: DebugFields<P>,
+    {
+        fn named(parent: &P, s: &mut DebugStruct<'_, '_>, options: Options) {
+            let value = FieldValue::<Head>(Head::try_get_ref(parent).unwrap(), options);
+            s.field(Head::IDENT.unwrap(), &value);
+            Tail::named(parent, s, options);
+        }
+
+        fn tuple(parent: &P, t: &mut DebugTuple<'_, '_>, options: Options) {
+            let value = FieldValue::<Head>(Head::try_get_ref(parent).unwrap(), options);
+            t.field(&value);
+            Tail::tuple(parent, t, options);
+        }
+    }
+
+    struct FieldValue<'a, F: Field>(&'a F::Type, Options);
+
+    impl<F: Field + DebugField<<F as Field>::Repr>> Debug for FieldValue<'_, F> {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            if F::SENSITIVE && self.1.redact {
+                f.write_str("<redacted>")
+            } else {
+                F::fmt_field(self.0, f, self.1)
+            }
+        }
+    }
+
+    // helper trait implemented for every field, depending on its representation
+    pub trait DebugField<Repr>: Field {
+        fn fmt_field(value: &Self::Type, f: &mut Formatter<'_>, options: Options) -> fmt::Result;
+    }
+
+    // flattened fields are formatted as nested structs, like `#[derive(Debug)]` does
+    impl<F> DebugField<PlainRepr> for F
+    where
+        F: Field<Type: DebugValue>,
+    {
+        fn fmt_field(value: &F::Type, f: &mut Formatter<'_>, options: Options) -> fmt::Result {
+            value.fmt_value(f, options)
+        }
+    }
+
+    impl<F> DebugField<FlattenRepr> for F
+    where
+        F: Field<Type: DebugValue>,
+    {
+        fn fmt_field(value: &F::Type, f: &mut Formatter<'_>, options: Options) -> fmt::Result {
+            value.fmt_value(f, options)
+        }
+    }
+
+    // adapters only change how the value is serialized, it is formatted with the `Debug` impl of
+    // its type, which knows nothing of the options
+    impl<F, A> DebugField<WithRepr<A>> for F
+    where
+        F: Field<Type: Debug>,
+    {
+        fn fmt_field(value: &F::Type, f: &mut Formatter<'_>, _: Options) -> fmt::Result {
+            value.fmt(f)
+        }
+    }
+
+    // helper trait implemented recursively for a list of variants, e.g `(Variant0, (Variant1, ()))`
+    pub trait DebugVariants<P> {
+        fn fmt(parent: &P, f: &mut Formatter<'_>, options: Options) -> fmt::Result;
+    }
+    // end of the recursion, one of the variants is always active
+    impl<P> DebugVariants<P> for () {
+        fn fmt(_: &P, _: &mut Formatter<'_>, _: Options) -> fmt::Result {
+            unreachable!()
+        }
+    }
+    impl<P, Head, Tail> DebugVariants<P> for Cons<Head, Tail>
+    where
+        Head: Variant<Root = P>,
+        Head: DebugShape<P, Head::Shape>,
+        Tail: DebugVariants<P>,
+    {
+        fn fmt(parent: &P, f: &mut Formatter<'_>, options: Options) -> fmt::Result {
+            if Head::is_active(parent) {
+                <Head as DebugShape<_, _>>::fmt(parent, f, options)
+            } else {
+                Tail::fmt(parent, f, options)
+            }
+        }
+    }
+}
+
+// std types, with collections formatted by hand to apply `Options::max_items`
+mod impls {
+    use std::{
+        borrow::{Cow, ToOwned},
+        cell::{Cell, RefCell},
+        collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+        ffi::{OsStr, OsString},
+        fmt::{self, Debug, Formatter},
+        marker::PhantomData,
+        net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6},
+        path::{Path, PathBuf},
+        rc::Rc,
+        sync::{Arc, Mutex, RwLock},
+        time::Duration,
+    };
+
+    use crate::{DebugValue, Options};
+
+    struct Value<'a, T: ?Sized>(&'a T, Options);
+
+    impl<T: DebugValue + ?Sized> Debug for Value<'_, T> {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            self.0.fmt_value(f, self.1)
+        }
+    }
+
+    // types without any nested values, formatted with their `Debug` impl
+    macro_rules! scalars {
+        ($($t:ty),*) => {$(
+            impl DebugValue for $t {
+                fn fmt_value(&self, f: &mut Formatter<'_>, _: Options) -> fmt::Result {
+                    self.fmt(f)
+                }
+            }
+        )*};
+    }
+
+    scalars!(
+        u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize
+    );
+    scalars!(f32, f64, bool, char, str, String, ());
+    scalars!(Duration, Path, PathBuf, OsStr, OsString);
+    scalars!(
+        IpAddr,
+        Ipv4Addr,
+        Ipv6Addr,
+        SocketAddr,
+        SocketAddrV4,
+        SocketAddrV6
+    );
+
+    impl<T: ?Sized> DebugValue for PhantomData<T> {
+        fn fmt_value(&self, f: &mut Formatter<'_>, _: Options) -> fmt::Result {
+            self.fmt(f)
+        }
+    }
+
+    // locks and cells use their `Debug` impls, which only lock or copy what they can
+    impl<T: Debug + Copy> DebugValue for Cell<T> {
+        fn fmt_value(&self, f: &mut Formatter<'_>, _: Options) -> fmt::Result {
+            self.fmt(f)
+        }
+    }
+
+    macro_rules! locks {
+        ($($t:ident),*) => {$(
+            impl<T: Debug + ?Sized> DebugValue for $t<T> {
+                fn fmt_value(&self, f: &mut Formatter<'_>, _: Options) -> fmt::Result {
+                    self.fmt(f)
+                }
+            }
+        )*};
+    }
+
+    locks!(RefCell, Mutex, RwLock);
+
+    // containers which only forward to their contents
+    macro_rules! forward {
+        ($($t:ty),*) => {$(
+            impl<T: DebugValue + ?Sized> DebugValue for $t {
+                fn fmt_value(&self, f: &mut Formatter<'_>, options: Options) -> fmt::Result {
+                    (**self).fmt_value(f, options)
+                }
+            }
+        )*};
+    }
+
+    forward!(&T, &mut T, Box<T>, Rc<T>, Arc<T>);
+
+    impl<B: DebugValue + ToOwned + ?Sized> DebugValue for Cow<'_, B> {
+        fn fmt_value(&self, f: &mut Formatter<'_>, options: Options) -> fmt::Result {
+            (**self).fmt_value(f, options)
+        }
+    }
+
+    impl<T: DebugValue> DebugValue for Option<T> {
+        fn fmt_value(&self, f: &mut Formatter<'_>, options: Options) -> fmt::Result {
+            match self {
+                None => f.write_str("None"),
+                Some(value) => f.debug_tuple("Some").field(&Value(value, options)).finish(),
+            }
+        }
+    }
+
+    impl<T: DebugValue, E: DebugValue> DebugValue for Result<T, E> {
+        fn fmt_value(&self, f: &mut Formatter<'_>, options: Options) -> fmt::Result {
+            match self {
+                Ok(value) => f.debug_tuple("Ok").field(&Value(value, options)).finish(),
+                Err(error) => f.debug_tuple("Err").field(&Value(error, options)).finish(),
+            }
+        }
+    }
+
+    macro_rules! tuples {
+        ($(($($t:ident),+)),*) => {$(
+            #[allow(non_snake_case)]
+            impl<$($t: DebugValue),+> DebugValue for ($($t,)+) {
+                fn fmt_value(&self, f: &mut Formatter<'_>, options: Options) -> fmt::Result {
+                    let ($($t,)+) = self;
+                    f.debug_tuple("")
+                        $(.field(&Value($t, options)))+
+                        .finish()
+                }
+            }
+        )*};
+    }
+
+    tuples!(
+        (A),
+        (A, B),
+        (A, B, C),
+        (A, B, C, D),
+        (A, B, C, D, E),
+        (A, B, C, D, E, F),
+        (A, B, C, D, E, F, G),
+        (A, B, C, D, E, F, G, H),
+        (A, B, C, D, E, F, G, H, I),
+        (A, B, C, D, E, F, G, H, I, J),
+        (A, B, C, D, E, F, G, H, I, J, K),
+        (A, B, C, D, E, F, G, H, I, J, K, L)
+    );
+
+    fn list<'a, T: DebugValue + 'a>(
+        f: &mut Formatter<'_>,
+        items: impl IntoIterator<Item = &'a T>,
+        options: Options,
+    ) -> fmt::Result {
+        let mut items = items.into_iter();
+        let mut list = f.debug_list();
+        for item in items.by_ref().take(options.max_items.unwrap_or(usize::MAX)) {
+            list.entry(&Value(item, options));
+        }
+        match items.next() {
+            Some(_) => list.finish_non_exhaustive(),
+            None => list.finish(),
+        }
+    }
+
+    fn set<'a, T: DebugValue + 'a>(
+        f: &mut Formatter<'_>,
+        items: impl IntoIterator<Item = &'a T>,
+        options: Options,
+    ) -> fmt::Result {
+        let mut items = items.into_iter();
+        let mut set = f.debug_set();
+        for item in items.by_ref().take(options.max_items.unwrap_or(usize::MAX)) {
+            set.entry(&Value(item, options));
+        }
+        match items.next() {
+            Some(_) => set.finish_non_exhaustive(),
+            None => set.finish(),
+        }
+    }
+
+    fn map<'a, K: DebugValue + 'a, V: DebugValue + 'a>(
+        f: &mut Formatter<'_>,
+        entries: impl IntoIterator<Item = (&'a K, &'a V)>,
+        options: Options,
+    ) -> fmt::Result {
+        let mut entries = entries.into_iter();
+        let mut map = f.debug_map();
+        for (key, value) in entries
+            .by_ref()
+            .take(options.max_items.unwrap_or(usize::MAX))
+        {
+            map.entry(&Value(key, options), &Value(value, options));
+        }
+        match entries.next() {
+            Some(_) => map.finish_non_exhaustive(),
+            None => map.finish(),
+        }
+    }
+
+    impl<T: DebugValue> DebugValue for [T] {
+        fn fmt_value(&self, f: &mut Formatter<'_>, options: Options) -> fmt::Result {
+            list(f, self, options)
+        }
+    }
+
+    impl<T: DebugValue, const N: usize> DebugValue for [T; N] {
+        fn fmt_value(&self, f: &mut Formatter<'_>, options: Options) -> fmt::Result {
+            list(f, self, options)
+        }
+    }
+
+    impl<T: DebugValue> DebugValue for Vec<T> {
+        fn fmt_value(&self, f: &mut Formatter<'_>, options: Options) -> fmt::Result {
+            list(f, self, options)
+        }
+    }
+
+    impl<T: DebugValue> DebugValue for VecDeque<T> {
+        fn fmt_value(&self, f: &mut Formatter<'_>, options: Options) -> fmt::Result {
+            list(f, self, options)
+        }
+    }
+
+    impl<T: DebugValue, S> DebugValue for HashSet<T, S> {
+        fn fmt_value(&self, f: &mut Formatter<'_>, options: Options) -> fmt::Result {
+            set(f, self, options)
+        }
+    }
+
+    impl<T: DebugValue> DebugValue for BTreeSet<T> {
+        fn fmt_value(&self, f: &mut Formatter<'_>, options: Options) -> fmt::Result {
+            set(f, self, options)
+        }
+    }
+
+    impl<K: DebugValue, V: DebugValue, S> DebugValue for HashMap<K, V, S> {
+        fn fmt_value(&self, f: &mut Formatter<'_>, options: Options) -> fmt::Result {
+            map(f, self, options)
+        }
+    }
+
+    impl<K: DebugValue, V: DebugValue> DebugValue for BTreeMap<K, V> {
+        fn fmt_value(&self, f: &mut Formatter<'_>, options: Options) -> fmt::Result {
+            map(f, self, options)
+        }
+    }
+}
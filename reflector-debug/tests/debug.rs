@@ -0,0 +1,199 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+
+use reflector::Introspect;
+use reflector_debug::{Options, Reflect};
+
+// formats the value with the derived `Debug` and through reflection, in both modes
+fn assert_same<T: Introspect + std::fmt::Debug>(value: &T)
+where
+    for<'a> Reflect<'a, T>: std::fmt::Debug,
+{
+    assert_eq!(format!("{:?}", Reflect(value)), format!("{value:?}"));
+    assert_eq!(format!("{:#?}", Reflect(value)), format!("{value:#?}"));
+}
+
+// a foreign type, which only implements `Debug`
+#[derive(Debug)]
+pub struct Color(u8, u8, u8);
+
+mod rgb {
+    use reflector::with::{Deserialize, Deserializer, Serializer};
+
+    use super::Color;
+
+    pub fn serialize<S: Serializer>(value: &Color, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_u32(u32::from_be_bytes([0, value.0, value.1, value.2]))
+    }
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Color, D::Error> {
+        let [_, r, g, b] = u32::deserialize(d)?.to_be_bytes();
+        Ok(Color(r, g, b))
+    }
+}
+
+#[test]
+fn structs() {
+    #[derive(Debug, Introspect)]
+    pub struct Unit;
+    #[derive(Debug, Introspect)]
+    pub struct EmptyTuple();
+    #[derive(Debug, Introspect)]
+    pub struct Empty {}
+    #[derive(Debug, Introspect)]
+    pub struct Tuple(u8, &'static str, Option<char>);
+    #[derive(Debug, Introspect)]
+    pub struct Named<T> {
+        id: u64,
+        name: String,
+        tags: Vec<&'static str>,
+        inner: T,
+        // formatted with its own `Debug` impl
+        #[reflect(with = rgb)]
+        color: Color,
+        nested: BTreeMap<u8, Vec<(i32, f64)>>,
+    }
+
+    assert_same(&Unit);
+    assert_same(&EmptyTuple());
+    assert_same(&Empty {});
+    assert_same(&Tuple(1, "two", Some('3')));
+    assert_same(&Named {
+        id: 7,
+        name: String::from("seven"),
+        tags: vec!["a", "b"],
+        inner: Some('x'),
+        color: Color(255, 0, 128),
+        nested: BTreeMap::from([(1, vec![(2, 3.5)]), (4, vec![])]),
+    });
+}
+
+#[test]
+fn enums() {
+    #[derive(Debug, Introspect)]
+    pub enum Shape {
+        Empty,
+        Circle(f32),
+        Rect { w: u32, h: u32 },
+        Polygon(Vec<(i8, i8)>, #[reflect(with = rgb)] Color),
+    }
+
+    assert_same(&Shape::Empty);
+    assert_same(&Shape::Circle(1.5));
+    assert_same(&Shape::Rect { w: 2, h: 3 });
+    assert_same(&Shape::Polygon(vec![(0, 0), (1, 1)], Color(0, 0, 0)));
+}
+
+#[test]
+fn redact() {
+    #[derive(Introspect)]
+    #[reflect(impl(reflector_debug::DebugValue))]
+    pub struct Credentials {
+        user: String,
+        #[reflect(sensitive)]
+        password: String,
+    }
+
+    #[derive(Introspect)]
+    #[reflect(impl(reflector_debug::DebugValue))]
+    pub enum Auth {
+        Anonymous,
+        Token(#[reflect(sensitive)] String),
+        Login(Credentials),
+    }
+
+    #[derive(Introspect)]
+    pub struct Request {
+        path: &'static str,
+        auth: Vec<Auth>,
+    }
+
+    let request = Request {
+        path: "/",
+        auth: vec![
+            Auth::Anonymous,
+            Auth::Token(String::from("secret")),
+            Auth::Login(Credentials {
+                user: String::from("root"),
+                password: String::from("hunter2"),
+            }),
+        ],
+    };
+
+    let plain = format!("{:?}", Reflect(&request));
+    assert!(plain.contains(r#"Token("secret")"#));
+    assert!(plain.contains(r#"password: "hunter2""#));
+
+    let redacted = format!("{:?}", Reflect(&request).with(Options::default().redact()));
+    assert_eq!(
+        redacted,
+        r#"Request { path: "/", auth: [Anonymous, Token(<redacted>), Login(Credentials { user: "root", password: <redacted> })] }"#
+    );
+
+    // options only apply while formatting
+    assert!(format!("{:?}", request.auth[1]).contains("secret"));
+}
+
+#[test]
+fn truncate() {
+    #[derive(Debug, Introspect)]
+    pub struct Batch {
+        ids: Vec<u32>,
+        short: [u8; 2],
+        queue: VecDeque<char>,
+        seen: BTreeSet<u8>,
+        counts: HashMap<&'static str, Option<Vec<u8>>>,
+    }
+
+    let batch = Batch {
+        ids: (0..100).collect(),
+        short: [1, 2],
+        queue: VecDeque::from(['a', 'b', 'c']),
+        seen: (0..10).collect(),
+        counts: HashMap::from([("x", Some((0..5).collect()))]),
+    };
+    assert_same(&batch);
+
+    let options = Options::default().truncate(2);
+    assert_eq!(
+        format!("{:?}", Reflect(&batch).with(options)),
+        "Batch { ids: [0, 1, ..], short: [1, 2], queue: ['a', 'b', ..], seen: {0, 1, ..}, \
+         counts: {\"x\": Some([0, 1, ..])} }"
+    );
+
+    let pretty = format!("{:#?}", Reflect(&batch).with(options.truncate(1)));
+    assert!(pretty.starts_with("Batch {\n    ids: [\n        0,\n        ..\n    ],\n"));
+
+    // nested reflected types are truncated as well, unlike fields formatted with `Debug`
+    #[derive(Introspect)]
+    #[reflect(impl(reflector_debug::DebugValue))]
+    pub struct Page {
+        ids: Vec<u32>,
+    }
+
+    mod bytes {
+        use reflector::with::{Deserialize, Deserializer, Serializer};
+
+        pub fn serialize<S: Serializer>(value: &[u8; 3], s: S) -> Result<S::Ok, S::Error> {
+            s.serialize_bytes(value)
+        }
+        pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<[u8; 3], D::Error> {
+            <[u8; 3]>::deserialize(d)
+        }
+    }
+
+    #[derive(Debug, Introspect)]
+    pub struct Jobs {
+        page: Page,
+        #[reflect(with = bytes)]
+        pending: [u8; 3],
+    }
+
+    let jobs = Jobs {
+        page: Page { ids: batch.ids },
+        pending: [1, 2, 3],
+    };
+    assert_same(&jobs);
+    assert_eq!(
+        format!("{:?}", Reflect(&jobs).with(options)),
+        "Jobs { page: Page { ids: [0, 1, ..] }, pending: [1, 2, 3] }"
+    );
+}
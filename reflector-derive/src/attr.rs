@@ -0,0 +1,284 @@
+use syn::meta::ParseNestedMeta;
+use syn::spanned::Spanned;
+use syn::{
+    Attribute, Error, Expr, ExprLit, Field, Ident, Item, Lit, LitInt, LitStr, Meta, MetaNameValue,
+    Path, Result, Token, Type, parse_quote,
+};
+
+/// `#[reflect(..)]` attributes on a struct or enum
+#[derive(Default)]
+pub struct ContainerAttrs {
+    pub transparent: bool,
+    /// `#[reflect(version = 3)]`, the current version of the layout
+    pub version: u32,
+    /// The doc comment, `///`
+    pub doc: String,
+    /// `#[reflect(builder)]`, generates a builder for the struct, or for every variant with named
+    /// fields
+    pub builder: bool,
+    /// `#[reflect(type_hash)]`, implements `reflector::TypeHash`
+    pub type_hash: bool,
+    /// `#[reflect(impl(path::Trait, ..))]`, implements every trait with the macro of the same name
+    /// next to it, e.g. `reflector_binary::Encode`
+    pub impls: Vec<Path>,
+}
+
+impl ContainerAttrs {
+    pub fn parse(item: &Item) -> Result<Self> {
+        let attrs = match item {
+            Item::Struct(s) => &s.attrs,
+            Item::Enum(e) => &e.attrs,
+            _ => return Ok(Self::default()),
+        };
+
+        let mut out = Self {
+            doc: doc(attrs),
+            ..Self::default()
+        };
+        for attr in reflect_attrs(attrs) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("transparent") {
+                    out.transparent = true;
+                } else if meta.path.is_ident("version") {
+                    out.version = parse_version(&meta)?;
+                } else if meta.path.is_ident("builder") {
+                    out.builder = true;
+                } else if meta.path.is_ident("type_hash") {
+                    out.type_hash = true;
+                } else if meta.path.is_ident("impl") {
+                    meta.parse_nested_meta(|meta| {
+                        out.impls.push(meta.path);
+                        Ok(())
+                    })?;
+                } else {
+                    return Err(meta.error("unknown container attribute"));
+                }
+                Ok(())
+            })?;
+        }
+
+        if out.transparent && !matches!(item, Item::Struct(_)) {
+            return Err(Error::new_spanned(
+                item,
+                "`transparent` is only supported on structs",
+            ));
+        }
+
+        Ok(out)
+    }
+}
+
+/// `#[reflect(..)]` attributes on a field
+#[derive(Default)]
+pub struct FieldAttrs {
+    pub flatten: bool,
+    pub sensitive: bool,
+    /// `#[reflect(opaque)]`, the type contributes its written name to the `TypeHash` of its
+    /// container instead of its own, which foreign types can't implement
+    pub opaque: bool,
+    /// `#[reflect(since = 3)]`
+    pub since: Option<u32>,
+    /// `#[reflect(removed = 4)]`
+    pub removed: Option<u32>,
+    /// `#[reflect(renamed_from = "old")]`, possibly multiple times
+    pub aliases: Vec<LitStr>,
+    /// `#[reflect(default)]` as `Some(None)`, and `#[reflect(default = function)]`
+    pub default: Option<Option<Path>>,
+    pub compare: Option<Compare>,
+    pub with: Option<With>,
+}
+
+/// How a field with `#[reflect(with = ..)]` and friends is (de)serialized
+pub enum With {
+    /// `#[reflect(adapter = Type)]`, or `#[reflect(borrow)]`
+    Adapter(Type),
+    /// `#[reflect(with = module)]`, `#[reflect(serialize_with = function)]` and
+    /// `#[reflect(deserialize_with = function)]`
+    Functions {
+        serialize: Option<Path>,
+        deserialize: Option<Path>,
+    },
+}
+
+/// How a field is compared and hashed by `reflector_cmp`
+pub enum Compare {
+    /// `#[reflect(skip_compare)]`
+    Skip,
+    /// `#[reflect(compare_with = module)]`
+    With(Path),
+}
+
+impl FieldAttrs {
+    pub fn parse(field: &Field) -> Result<Self> {
+        let mut out = Self::default();
+        for attr in reflect_attrs(&field.attrs) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("flatten") {
+                    out.flatten = true;
+                } else if meta.path.is_ident("sensitive") {
+                    out.sensitive = true;
+                } else if meta.path.is_ident("opaque") {
+                    out.opaque = true;
+                } else if meta.path.is_ident("since") {
+                    out.since = Some(parse_version(&meta)?);
+                } else if meta.path.is_ident("removed") {
+                    out.removed = Some(parse_version(&meta)?);
+                } else if meta.path.is_ident("renamed_from") {
+                    out.aliases.push(meta.value()?.parse()?);
+                } else if meta.path.is_ident("default") {
+                    out.default = match meta.input.peek(Token![=]) {
+                        true => Some(Some(parse_path(&meta)?)),
+                        false => Some(None),
+                    };
+                } else if meta.path.is_ident("skip_compare") {
+                    out.set_compare(&meta, Compare::Skip)?;
+                } else if meta.path.is_ident("compare_with") {
+                    out.set_compare(&meta, Compare::With(parse_path(&meta)?))?;
+                } else if meta.path.is_ident("borrow") {
+                    let adapter = parse_quote!(::reflector::with::Borrow);
+                    out.set_with(&meta, With::Adapter(adapter))?;
+                } else if meta.path.is_ident("adapter") {
+                    let adapter = meta.value()?.parse()?;
+                    out.set_with(&meta, With::Adapter(adapter))?;
+                } else if meta.path.is_ident("with") {
+                    let module = parse_path(&meta)?;
+                    let function = |name: &str| {
+                        let mut path = module.clone();
+                        path.segments
+                            .push(Ident::new(name, meta.path.span()).into());
+                        Some(path)
+                    };
+                    let with = With::Functions {
+                        serialize: function("serialize"),
+                        deserialize: function("deserialize"),
+                    };
+                    out.set_with(&meta, with)?;
+                } else if meta.path.is_ident("serialize_with") {
+                    let function = parse_path(&meta)?;
+                    match &mut out.with {
+                        Some(With::Functions {
+                            serialize: serialize @ None,
+                            ..
+                        }) => *serialize = Some(function),
+                        _ => out.set_with(
+                            &meta,
+                            With::Functions {
+                                serialize: Some(function),
+                                deserialize: None,
+                            },
+                        )?,
+                    }
+                } else if meta.path.is_ident("deserialize_with") {
+                    let function = parse_path(&meta)?;
+                    match &mut out.with {
+                        Some(With::Functions {
+                            deserialize: deserialize @ None,
+                            ..
+                        }) => *deserialize = Some(function),
+                        _ => out.set_with(
+                            &meta,
+                            With::Functions {
+                                serialize: None,
+                                deserialize: Some(function),
+                            },
+                        )?,
+                    }
+                } else {
+                    return Err(meta.error("unknown field attribute"));
+                }
+                Ok(())
+            })?;
+        }
+
+        if out.flatten && field.ident.is_none() {
+            return Err(Error::new_spanned(
+                field,
+                "`flatten` is only supported on named fields",
+            ));
+        }
+        if out.flatten && out.with.is_some() {
+            return Err(Error::new_spanned(
+                field,
+                "`flatten` can not be combined with `with`, `adapter`, `borrow` and friends",
+            ));
+        }
+
+        if let (Some(since), Some(removed)) = (out.since, out.removed)
+            && since >= removed
+        {
+            return Err(Error::new_spanned(
+                field,
+                "`removed` has to be a later version than `since`",
+            ));
+        }
+
+        Ok(out)
+    }
+
+    /// Checks the versions of the field against the version of its container
+    pub fn check_version(&self, field: &Field, version: u32) -> Result<()> {
+        match self.since.into_iter().chain(self.removed).max() {
+            Some(latest) if latest > version => Err(Error::new_spanned(
+                field,
+                format!(
+                    "`since` and `removed` can't be later than the current version {version}, set with `#[reflect(version = ..)]`"
+                ),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn set_compare(&mut self, meta: &ParseNestedMeta, compare: Compare) -> Result<()> {
+        if self.compare.is_some() {
+            return Err(meta.error("conflicting `skip_compare` and `compare_with`"));
+        }
+        self.compare = Some(compare);
+        Ok(())
+    }
+
+    fn set_with(&mut self, meta: &ParseNestedMeta, with: With) -> Result<()> {
+        if self.with.is_some() {
+            return Err(meta.error("conflicting `with`, `adapter`, `borrow` and friends"));
+        }
+        self.with = Some(with);
+        Ok(())
+    }
+}
+
+fn reflect_attrs(attrs: &[Attribute]) -> impl Iterator<Item = &Attribute> {
+    attrs.iter().filter(|attr| attr.path().is_ident("reflect"))
+}
+
+/// The lines of `///` comments, without their leading space
+pub fn doc(attrs: &[Attribute]) -> String {
+    let lines = attrs.iter().filter_map(|attr| match &attr.meta {
+        Meta::NameValue(MetaNameValue {
+            path,
+            value:
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(line),
+                    ..
+                }),
+            ..
+        }) if path.is_ident("doc") => Some(line.value()),
+        _ => None,
+    });
+    let lines = lines
+        .map(|line| line.strip_prefix(' ').map(str::to_owned).unwrap_or(line))
+        .collect::<Vec<_>>();
+    lines.join("\n").trim().to_owned()
+}
+
+fn parse_version(meta: &ParseNestedMeta) -> Result<u32> {
+    meta.value()?.parse::<LitInt>()?.base10_parse()
+}
+
+// accepts both `key = path` and `key = "path"`, the latter like serde_derive does
+fn parse_path(meta: &ParseNestedMeta) -> Result<Path> {
+    let value = meta.value()?;
+    if value.peek(LitStr) {
+        value.parse::<LitStr>()?.parse()
+    } else {
+        value.parse()
+    }
+}
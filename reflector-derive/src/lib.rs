@@ -1,12 +1,16 @@
-use proc_macro2::TokenStream;
+mod attr;
+
+use attr::{Compare, ContainerAttrs, FieldAttrs, With, doc};
+use proc_macro2::{Literal, TokenStream};
 use quote::{format_ident, quote};
 use syn::spanned::Spanned;
+use syn::visit_mut::{self, VisitMut};
 use syn::{
-    Error, Field, Fields, Generics, Ident, Index, Item, ItemEnum, ItemStruct, Member, Result,
-    Variant, Visibility,
+    Error, Field, Fields, GenericParam, Generics, Ident, Index, Item, ItemEnum, ItemStruct, Member,
+    Path, Result, Type, Variant, Visibility, parse_quote,
 };
 
-#[proc_macro_derive(Introspect)]
+#[proc_macro_derive(Introspect, attributes(reflect))]
 pub fn my_macro(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     entry(input)
         .unwrap_or_else(|err| err.to_compile_error())
@@ -15,27 +19,304 @@ pub fn my_macro(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
 
 fn entry(input: proc_macro::TokenStream) -> Result<TokenStream> {
     let input = syn::parse::<Item>(input)?;
-    let output = match &input {
-        Item::Struct(s) => for_struct(s),
-        Item::Enum(e) => for_enum(e),
+    let attrs = ContainerAttrs::parse(&input)?;
+    let (mut output, ident, generics) = match &input {
+        Item::Struct(s) => (for_struct(s, &attrs)?, &s.ident, &s.generics),
+        Item::Enum(e) => (for_enum(e, &attrs)?, &e.ident, &e.generics),
         x => return Err(Error::new(x.span(), "unsupported item")),
     };
+    if attrs.type_hash {
+        output.extend(type_hash_impl(&input)?);
+    }
+    for path in &attrs.impls {
+        output.extend(forward(path, ident, generics));
+    }
+    // the builders are declared outside of the constant to be nameable
+    let mut builders = TokenStream::new();
+    if attrs.builder {
+        let (items, impls) = builder(&input)?;
+        builders.extend(items);
+        output.extend(impls);
+    }
 
     Ok(quote! {
         #[allow(dead_code, non_camel_case_types)]
         const _: () = { #output };
+        #builders
     })
 }
 
-fn for_struct(s: &ItemStruct) -> TokenStream {
+// adds `T: #bound` for every type parameter `T`
+fn bound_type_params(generics: &Generics, bound: TokenStream) -> Generics {
+    let mut generics = generics.clone();
+    let params = generics
+        .type_params()
+        .map(|p| p.ident.clone())
+        .collect::<Vec<_>>();
+    let where_clause = generics.make_where_clause();
+    for param in params {
+        where_clause.predicates.push(parse_quote!(#param: #bound));
+    }
+    generics
+}
+
+// #[reflect(impl(path::Trait))], the macro `path::Trait!` implements the trait, usually forwarding
+// to its reflected impl. It is given the generics of the type, and adds the bounds it requires on
+// the type parameters:
+//
+// path::Trait! {
+//     lifetimes ['a]
+//     types [T]
+//     impl ['a, T: Clone] Type<'a, T>
+//     where [T: Default,]
+// }
+fn forward(path: &Path, ident: &Ident, generics: &Generics) -> TokenStream {
+    let mut params = generics.params.clone();
+    for param in &mut params {
+        match param {
+            GenericParam::Type(param) => param.default = None,
+            GenericParam::Const(param) => param.default = None,
+            GenericParam::Lifetime(_) => {}
+        }
+    }
+    let (_, type_generics, where_clause) = generics.split_for_impl();
+    let lifetimes = generics.lifetimes().map(|param| &param.lifetime);
+    let types = generics.type_params().map(|param| &param.ident);
+    let predicates = where_clause
+        .map(|clause| &clause.predicates)
+        .into_iter()
+        .flatten();
+
+    quote! {
+        #path! {
+            lifetimes [#(#lifetimes),*]
+            types [#(#types),*]
+            impl [#params] #ident #type_generics
+            where [#(#predicates,)*]
+        }
+    }
+}
+
+// #[reflect(builder)], returns the builder structs and their impls
+fn builder(input: &Item) -> Result<(TokenStream, TokenStream)> {
+    let mut items = TokenStream::new();
+    let mut impls = TokenStream::new();
+    match input {
+        Item::Struct(s) => {
+            if let Fields::Unnamed(_) = s.fields {
+                return Err(Error::new_spanned(
+                    &s.fields,
+                    "`builder` requires named fields",
+                ));
+            }
+            check_setters(&s.fields)?;
+            let builder = Builder {
+                vis: &s.vis,
+                parent_ident: &s.ident,
+                struct_ident: &s.ident,
+                generics: &s.generics,
+                fields: &s.fields,
+                builder_ident: format_ident!("{}Builder", s.ident),
+                constructor: format_ident!("builder"),
+            };
+            items.extend(builder.item());
+            impls.extend(builder.impls());
+        }
+        // tuple and unit variants have no builders
+        Item::Enum(e) => {
+            for variant in &e.variants {
+                if let Fields::Named(_) = variant.fields {
+                    check_setters(&variant.fields)?;
+                    let builder = Builder {
+                        vis: &e.vis,
+                        parent_ident: &e.ident,
+                        struct_ident: &format_ident!("{}_{}", e.ident, variant.ident),
+                        generics: &e.generics,
+                        fields: &variant.fields,
+                        builder_ident: format_ident!("{}{}Builder", e.ident, variant.ident),
+                        constructor: format_ident!("{}_builder", snake_case(&variant.ident)),
+                    };
+                    items.extend(builder.item());
+                    impls.extend(builder.impls());
+                }
+            }
+        }
+        _ => {}
+    }
+    Ok((items, impls))
+}
+
+// the setters are named after the fields, next to `build` and the `__marker` field of the builder
+fn check_setters(fields: &Fields) -> Result<()> {
+    for ident in fields.iter().filter_map(|field| field.ident.as_ref()) {
+        if ident == "build" || ident == "__marker" {
+            return Err(Error::new_spanned(
+                ident,
+                format!("`{ident}` is reserved by `builder` and can't be the name of a field"),
+            ));
+        }
+    }
+    Ok(())
+}
+
+struct Builder<'a> {
+    vis: &'a Visibility,
+    parent_ident: &'a Ident,
+    /// The struct implementing `SizedStruct`, the parent or a variant struct
+    struct_ident: &'a Ident,
+    generics: &'a Generics,
+    fields: &'a Fields,
+    builder_ident: Ident,
+    /// The function of the parent returning the builder
+    constructor: Ident,
+}
+
+impl Builder<'_> {
+    fn item(&self) -> TokenStream {
+        let Self {
+            vis,
+            parent_ident,
+            generics,
+            builder_ident,
+            ..
+        } = self;
+        let (_, type_generics, where_clause) = generics.split_for_impl();
+        let doc = format!(
+            " A builder for [`{parent_ident}`], returned by [`{parent_ident}::{}`].",
+            self.constructor
+        );
+        let fields = self
+            .fields
+            .iter()
+            .map(|Field { ident, ty, .. }| quote!(#ident: ::core::option::Option<#ty>));
+
+        quote! {
+            #[doc = #doc]
+            #vis struct #builder_ident #generics #where_clause {
+                #(#fields,)*
+                __marker: ::core::marker::PhantomData<fn() -> #parent_ident #type_generics>,
+            }
+        }
+    }
+
+    fn impls(&self) -> TokenStream {
+        let Self {
+            vis,
+            parent_ident,
+            struct_ident,
+            generics,
+            builder_ident,
+            constructor,
+            ..
+        } = self;
+        let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+        let idents = self
+            .fields
+            .iter()
+            .map(|field| field.ident.as_ref().unwrap())
+            .collect::<Vec<_>>();
+        let field_struct_idents = (0..idents.len()).map(|i| format_ident!("{struct_ident}_{i}"));
+        let setters = self.fields.iter().map(|field| {
+            let (ident, ty) = (&field.ident, &field.ty);
+            let docs = field
+                .attrs
+                .iter()
+                .filter(|attr| attr.path().is_ident("doc"));
+            quote! {
+                #(#docs)*
+                #vis fn #ident(mut self, #ident: impl ::core::convert::Into<#ty>) -> Self {
+                    self.#ident = ::core::option::Option::Some(#ident.into());
+                    self
+                }
+            }
+        });
+        let values = idents.iter().rev().fold(
+            quote!(()),
+            |acc, ident| quote![::reflector::Cons(#ident, #acc)],
+        );
+        let from_values = quote! {
+            ::core::result::Result::Ok(
+                <#struct_ident #type_generics as ::reflector::SizedStruct>::from_values(#values),
+            )
+        };
+        let build = match idents.is_empty() {
+            true => from_values,
+            false => quote! {
+                let mut missing = ::std::vec::Vec::new();
+                #(
+                    let #idents = ::reflector::builder::field::<#field_struct_idents #type_generics>(
+                        self.#idents,
+                        &mut missing,
+                    );
+                )*
+                match (#(#idents,)*) {
+                    (#(::core::option::Option::Some(#idents),)*) => #from_values,
+                    _ => ::core::result::Result::Err(::reflector::builder::Error {
+                        ident: <#struct_ident #type_generics as ::reflector::Introspect>::IDENT,
+                        missing,
+                    }),
+                }
+            },
+        };
+
+        quote! {
+            impl #impl_generics #parent_ident #type_generics #where_clause {
+                #vis fn #constructor() -> #builder_ident #type_generics {
+                    ::core::default::Default::default()
+                }
+            }
+
+            impl #impl_generics ::core::default::Default for #builder_ident #type_generics #where_clause {
+                fn default() -> Self {
+                    Self {
+                        #(#idents: ::core::option::Option::None,)*
+                        __marker: ::core::marker::PhantomData,
+                    }
+                }
+            }
+
+            impl #impl_generics #builder_ident #type_generics #where_clause {
+                #(#setters)*
+
+                /// Builds the value, giving unset fields their `#[reflect(default)]`
+                #vis fn build(self) -> ::core::result::Result<#parent_ident #type_generics, ::reflector::builder::Error> {
+                    #build
+                }
+            }
+        }
+    }
+}
+
+// splits words before an upper case letter following a lower case one or a digit, and before the
+// last letter of an acronym followed by a lower case one: `HTTPRequest` is `http_request`
+fn snake_case(ident: &Ident) -> String {
+    let chars = ident.to_string().chars().collect::<Vec<_>>();
+    let mut out = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            let previous = chars[i - 1];
+            let next = chars.get(i + 1);
+            if previous.is_lowercase()
+                || previous.is_ascii_digit()
+                || previous.is_uppercase() && next.is_some_and(|c| c.is_lowercase())
+            {
+                out.push('_');
+            }
+        }
+        out.extend(c.to_lowercase());
+    }
+    out
+}
+
+fn for_struct(s: &ItemStruct, attrs: &ContainerAttrs) -> Result<TokenStream> {
     expand_struct(
         &s.vis,
         &s.ident,
         &s.ident,
-        &s.ident,
         &s.generics,
         &s.fields,
         None,
+        attrs,
     )
 }
 
@@ -43,12 +324,13 @@ fn expand_struct(
     parent_vis: &Visibility,
     parent_ident: &Ident,
     struct_ident: &Ident,
-    name: &Ident,
     generics: &Generics,
     fields: &Fields,
     variant: Option<&Variant>,
-) -> TokenStream {
+    attrs: &ContainerAttrs,
+) -> Result<TokenStream> {
     let (impl_generics, type_generics, ..) = generics.split_for_impl();
+    let name = variant.map_or(parent_ident, |v| &v.ident);
 
     let field_struct_idents = (0..fields.len())
         .map(|i| format_ident!("{struct_ident}_{i}"))
@@ -64,6 +346,7 @@ fn expand_struct(
         .iter()
         .enumerate()
         .map(|(i, field)| {
+            FieldAttrs::parse(field)?.check_version(field, attrs.version)?;
             generate_field_items(
                 parent_vis,
                 parent_ident,
@@ -74,7 +357,7 @@ fn expand_struct(
                 variant,
             )
         })
-        .collect::<TokenStream>();
+        .collect::<Result<TokenStream>>()?;
     let shape = match fields {
         Fields::Named(_) => quote!(NamedShape),
         Fields::Unnamed(_) => quote!(TupleShape),
@@ -104,26 +387,98 @@ fn expand_struct(
         }
     };
 
-    quote! {
+    let version = attrs.version;
+    let doc = doc_const(&attrs.doc);
+    let fingerprint = Literal::u64_suffixed(fingerprint(&format!(
+        "struct {name} {}{}",
+        if attrs.transparent {
+            "transparent "
+        } else {
+            ""
+        },
+        layout(fields)?
+    )));
+
+    let (repr, transparent) = match attrs.transparent {
+        true => (
+            quote!(TransparentRepr),
+            transparent(fields, &field_struct_idents, generics, struct_ident)?,
+        ),
+        false => (quote!(PlainRepr), quote!()),
+    };
+
+    Ok(quote! {
         #field_items
 
         impl #impl_generics ::reflector::Struct for #struct_ident #type_generics {
             type Fields = #field_list;
             type Shape = ::reflector::#shape;
+            type Repr = ::reflector::#repr;
         }
 
         #sized
+        #transparent
 
         impl #impl_generics ::reflector::Introspect for #struct_ident #type_generics {
             const IDENT: &'static str = stringify!(#name);
+            const VERSION: u32 = #version;
+            const FINGERPRINT: u64 = #fingerprint;
+            #doc
 
             type Root = #parent_ident #type_generics;
             type Kind = ::reflector::StructKind;
         }
-    }
+    })
+}
+
+// the inner field of a `#[reflect(transparent)]` struct is the only one which isn't zero-sized,
+// recognized by its type being `PhantomData` or `()`, like serde_derive does.
+fn transparent(
+    fields: &Fields,
+    field_struct_idents: &[Ident],
+    generics: &Generics,
+    struct_ident: &Ident,
+) -> Result<TokenStream> {
+    let is_zero_sized = |field: &Field| match &field.ty {
+        Type::Tuple(tuple) => tuple.elems.is_empty(),
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "PhantomData"),
+        _ => false,
+    };
+
+    let mut inner = fields.iter().enumerate().filter(|(_, f)| !is_zero_sized(f));
+    let (inner_idx, inner_member) = match (inner.next(), inner.next(), fields.len()) {
+        (Some((idx, _)), None, _) => (idx, fields.members().nth(idx).unwrap()),
+        (None, _, 1) => (0, fields.members().next().unwrap()),
+        _ => {
+            return Err(Error::new_spanned(
+                fields,
+                "`transparent` requires exactly one field which is not `PhantomData` or `()`",
+            ));
+        }
+    };
+    let others = fields.members().filter(|member| *member != inner_member);
+
+    let (impl_generics, type_generics, ..) = generics.split_for_impl();
+    let inner_struct = &field_struct_idents[inner_idx];
+    Ok(quote! {
+        impl #impl_generics ::reflector::TransparentStruct for #struct_ident #type_generics {
+            type Inner = #inner_struct #type_generics;
+
+            fn from_inner(x: <Self::Inner as ::reflector::Field>::Type) -> Self::Root {
+                Self::Root {
+                    #inner_member: x,
+                    #(#others: ::core::default::Default::default(),)*
+                }
+            }
+        }
+    })
 }
 
-fn for_enum(parent: &ItemEnum) -> TokenStream {
+fn for_enum(parent: &ItemEnum, attrs: &ContainerAttrs) -> Result<TokenStream> {
     let parent_ident = &parent.ident;
     let (impl_generics, type_generics, ..) = parent.generics.split_for_impl();
 
@@ -139,16 +494,29 @@ fn for_enum(parent: &ItemEnum) -> TokenStream {
             .map(|i| quote!(#i #type_generics)),
     );
 
-    let variants = (0..parent.variants.len()).map(|i| {
-        for_variant(
-            parent,
-            &parent.variants[i],
-            i as u32,
-            &variant_struct_idents[i],
-        )
-    });
+    let variants = (0..parent.variants.len())
+        .map(|i| {
+            for_variant(
+                parent,
+                &parent.variants[i],
+                i as u32,
+                &variant_struct_idents[i],
+                attrs.version,
+            )
+        })
+        .collect::<Result<Vec<_>>>()?;
 
-    quote! {
+    let version = attrs.version;
+    let doc = doc_const(&attrs.doc);
+    let mut variant_layouts = String::new();
+    for variant in &parent.variants {
+        variant_layouts += &format!("{} {};", variant.ident, layout(&variant.fields)?);
+    }
+    let fingerprint = Literal::u64_suffixed(fingerprint(&format!(
+        "enum {parent_ident} {{{variant_layouts}}}"
+    )));
+
+    Ok(quote! {
         #(#variants)*
 
         impl #impl_generics ::reflector::Enum for #parent_ident #type_generics {
@@ -158,11 +526,14 @@ fn for_enum(parent: &ItemEnum) -> TokenStream {
 
         impl #impl_generics ::reflector::Introspect for #parent_ident #type_generics {
             const IDENT: &'static str = stringify!(#parent_ident);
+            const VERSION: u32 = #version;
+            const FINGERPRINT: u64 = #fingerprint;
+            #doc
 
             type Root = #parent_ident #type_generics;
             type Kind = ::reflector::EnumKind;
         }
-    }
+    })
 }
 
 fn for_variant(
@@ -170,7 +541,8 @@ fn for_variant(
     variant: &Variant,
     index: u32,
     variant_struct_ident: &Ident,
-) -> TokenStream {
+    version: u32,
+) -> Result<TokenStream> {
     let generics = &parent.generics;
     let parent_ident = &parent.ident;
     let vis = &parent.vis;
@@ -190,20 +562,161 @@ fn for_variant(
         &parent.vis,
         &parent.ident,
         variant_struct_ident,
-        &variant.ident,
         &parent.generics,
         &variant.fields,
         Some(variant),
-    );
-    quote! {
-        #vis struct #variant_struct_ident #generics (#parent_ident #generics);
+        &ContainerAttrs {
+            version,
+            doc: doc(&variant.attrs),
+            ..ContainerAttrs::default()
+        },
+    )?;
+    Ok(quote! {
+        #vis struct #variant_struct_ident #generics (#parent_ident #type_generics);
         impl #impl_generics ::reflector::Variant for #variant_struct_ident #type_generics {
             const INDEX: u32 = #index;
 
             fn is_active(p: &Self::Root) -> bool { #is_active }
         }
         #struct_items
+    })
+}
+
+// a description of the fields' layout, for `Introspect::FINGERPRINT`
+fn layout(fields: &Fields) -> Result<String> {
+    let mut layout = String::new();
+    for (i, field) in fields.iter().enumerate() {
+        let attrs = FieldAttrs::parse(field)?;
+        match &field.ident {
+            Some(ident) => layout += &ident.to_string(),
+            None => layout += &i.to_string(),
+        }
+        let ty = &field.ty;
+        layout += &format!(": {}", quote!(#ty));
+        if attrs.flatten {
+            layout += " flatten";
+        }
+        match &attrs.with {
+            None => {}
+            Some(With::Adapter(adapter)) => layout += &format!(" adapter {}", quote!(#adapter)),
+            Some(With::Functions {
+                serialize,
+                deserialize,
+            }) => layout += &format!(" with {} {}", quote!(#serialize), quote!(#deserialize)),
+        }
+        if let Some(since) = attrs.since {
+            layout += &format!(" since {since}");
+        }
+        if let Some(removed) = attrs.removed {
+            layout += &format!(" removed {removed}");
+        }
+        layout += ";";
     }
+    Ok(match fields {
+        Fields::Named(_) => format!("{{{layout}}}"),
+        Fields::Unnamed(_) => format!("({layout})"),
+        Fields::Unit => layout,
+    })
+}
+
+fn doc_const(doc: &str) -> Option<TokenStream> {
+    (!doc.is_empty()).then(|| quote!(const DOC: &'static str = #doc;))
+}
+
+// #[reflect(type_hash)], a hash of the identifier, the structure and the type hashes of the fields
+// of the type and of every variant, requiring all type parameters to implement `TypeHash`
+fn type_hash_impl(item: &Item) -> Result<TokenStream> {
+    let (ident, generics, hash) = match item {
+        Item::Struct(s) => {
+            let structure = format!("struct {}{}", s.ident, structure(&s.fields));
+            (
+                &s.ident,
+                &s.generics,
+                type_hash(&structure, &s.ident, &s.fields)?,
+            )
+        }
+        Item::Enum(e) => {
+            let variants = e
+                .variants
+                .iter()
+                .map(|v| {
+                    let structure = format!("struct {}{}", v.ident, structure(&v.fields));
+                    type_hash(&structure, &e.ident, &v.fields)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let ident = &e.ident;
+            let hash = quote! {
+                ::reflector::hash_combine(concat!("enum ", stringify!(#ident)), &[#(#variants),*])
+            };
+            (ident, &e.generics, hash)
+        }
+        x => return Err(Error::new(x.span(), "unsupported item")),
+    };
+
+    let generics = bound_type_params(generics, quote!(::reflector::TypeHash));
+    let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::reflector::TypeHash for #ident #type_generics #where_clause {
+            const HASH: u64 = #hash;
+        }
+    })
+}
+
+// the names of named fields and the number of unnamed ones, for `TypeHash`
+fn structure(fields: &Fields) -> String {
+    match fields {
+        Fields::Named(_) => {
+            let names = fields
+                .iter()
+                .map(|f| format!("{};", f.ident.as_ref().unwrap()));
+            format!("{{{}}}", names.collect::<String>())
+        }
+        Fields::Unnamed(_) => format!("({})", ";".repeat(fields.len())),
+        Fields::Unit => String::new(),
+    }
+}
+
+// combines the hash of the structure with the type hashes of the fields, where references of the
+// type to itself are replaced by a placeholder
+fn type_hash(structure: &str, ident: &Ident, fields: &Fields) -> Result<TokenStream> {
+    struct ReplaceSelf<'a>(&'a Ident);
+    impl VisitMut for ReplaceSelf<'_> {
+        fn visit_type_mut(&mut self, ty: &mut Type) {
+            match ty {
+                Type::Path(path)
+                    if path.qself.is_none()
+                        && path.path.segments.len() == 1
+                        && (path.path.segments[0].ident == *self.0
+                            || path.path.segments[0].ident == "Self") =>
+                {
+                    *ty = parse_quote!(::reflector::Recursive)
+                }
+                _ => visit_mut::visit_type_mut(self, ty),
+            }
+        }
+    }
+
+    let mut hashes = Vec::new();
+    for field in fields {
+        let mut ty = field.ty.clone();
+        hashes.push(match FieldAttrs::parse(field)?.opaque {
+            true => quote!(::reflector::hash_combine(stringify!(#ty), &[])),
+            false => {
+                ReplaceSelf(ident).visit_type_mut(&mut ty);
+                quote!(<#ty as ::reflector::TypeHash>::HASH)
+            }
+        });
+    }
+    Ok(quote! {
+        ::reflector::hash_combine(#structure, &[#(#hashes),*])
+    })
+}
+
+// FNV-1a, which is simple and stable across platforms and compiler versions
+fn fingerprint(layout: &str) -> u64 {
+    layout.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
 }
 
 fn type_list(elements: impl DoubleEndedIterator<Item = TokenStream>) -> TokenStream {
@@ -221,7 +734,8 @@ fn generate_field_items(
     field_idx: u32,
     field: &Field,
     inside_variant: Option<&Variant>,
-) -> TokenStream {
+) -> Result<TokenStream> {
+    let attrs = FieldAttrs::parse(field)?;
     let field_type = &field.ty;
     let (impl_generics, type_generics, ..) = parent_generics.split_for_impl();
 
@@ -234,11 +748,69 @@ fn generate_field_items(
     );
 
     let ident = match &field.ident {
-        None => quote!(None),
-        Some(ident) => quote!(Some(stringify!(#ident))),
+        None => quote!(::core::option::Option::None),
+        Some(ident) => quote!(::core::option::Option::Some(stringify!(#ident))),
     };
 
-    quote! {
+    let sensitive = attrs.sensitive.then(|| {
+        quote!(
+            const SENSITIVE: bool = true;
+        )
+    });
+
+    let doc = doc_const(&doc(&field.attrs));
+    let since = attrs.since.map(|since| quote!(const SINCE: u32 = #since;));
+    let removed = attrs
+        .removed
+        .map(|removed| quote!(const REMOVED: ::core::option::Option<u32> = ::core::option::Option::Some(#removed);));
+    let aliases = (!attrs.aliases.is_empty()).then(|| {
+        let aliases = &attrs.aliases;
+        quote!(const ALIASES: &'static [&'static str] = &[#(#aliases),*];)
+    });
+    // fields which are missing from some versions need a default
+    let default = match &attrs.default {
+        Some(Some(function)) => Some(quote!(#function())),
+        None if attrs.since.is_none() && attrs.removed.is_none() => None,
+        _ => Some(quote!(::core::default::Default::default())),
+    };
+    let default = default.map(|default| {
+        quote! {
+            fn default_value() -> ::core::option::Option<Self::Type> {
+                ::core::option::Option::Some(#default)
+            }
+        }
+    });
+
+    let field_struct = quote!(#field_struct_ident #type_generics);
+    let (repr, with_items) = match &attrs.with {
+        _ if attrs.flatten => (quote!(::reflector::FlattenRepr), quote!()),
+        None => (quote!(::reflector::PlainRepr), quote!()),
+        Some(With::Adapter(adapter)) => (quote!(::reflector::WithRepr<#adapter>), quote!()),
+        Some(With::Functions {
+            serialize,
+            deserialize,
+        }) => (
+            quote!(::reflector::WithRepr<Self>),
+            with_functions(
+                parent_ident,
+                parent_generics,
+                &field_struct,
+                serialize.as_ref(),
+                deserialize.as_ref(),
+            ),
+        ),
+    };
+
+    let (compare_repr, compare_items) = match &attrs.compare {
+        None => (quote!(::reflector::PlainRepr), quote!()),
+        Some(Compare::Skip) => (quote!(::reflector::SkipRepr), quote!()),
+        Some(Compare::With(module)) => (
+            quote!(::reflector::WithRepr<Self>),
+            compare_with(parent_ident, parent_generics, &field_struct, module),
+        ),
+    };
+
+    Ok(quote! {
         #parent_vis struct #field_struct_ident #parent_generics(#parent_ident #type_generics);
         impl #impl_generics ::reflector::HasField<#field_struct_ident #type_generics> for #parent_ident #type_generics {
             type Type = #field_type;
@@ -246,12 +818,104 @@ fn generate_field_items(
         impl #impl_generics ::reflector::Field for #field_struct_ident #type_generics {
             type Type = <#parent_ident #type_generics as ::reflector::HasField<Self>>::Type;
             type Root = #parent_ident #type_generics;
+            type Repr = #repr;
+            type CompareRepr = #compare_repr;
 
-            const IDENT: Option<&'static str> = #ident;
+            const IDENT: ::core::option::Option<&'static str> = #ident;
             const INDEX: u32 = #field_idx;
+            #sensitive
+            #doc
+            #since
+            #removed
+            #aliases
+
+            fn try_get_ref(p: &Self::Root) -> ::core::option::Option<&Self::Type> { #accessor }
+            fn try_get_mut(p: &mut Self::Root) -> ::core::option::Option<&mut Self::Type> { #accessor }
+            #default
+        }
+        #with_items
+        #compare_items
+    })
+}
+
+// for `#[reflect(compare_with = module)]`, the field struct becomes the comparator, forwarding to
+// `module::eq`, `module::cmp` and `module::hash`.
+fn compare_with(
+    parent_ident: &Ident,
+    parent_generics: &Generics,
+    field_struct: &TokenStream,
+    module: &Path,
+) -> TokenStream {
+    let (impl_generics, type_generics, ..) = parent_generics.split_for_impl();
+    let field_type =
+        quote!(<#parent_ident #type_generics as ::reflector::HasField<#field_struct>>::Type);
+
+    quote! {
+        impl #impl_generics ::reflector::Comparator<#field_type> for #field_struct {
+            fn eq(a: &#field_type, b: &#field_type) -> bool {
+                #module::eq(a, b)
+            }
+
+            fn cmp(a: &#field_type, b: &#field_type) -> ::core::cmp::Ordering {
+                #module::cmp(a, b)
+            }
+
+            fn hash<__H: ::core::hash::Hasher>(value: &#field_type, state: &mut __H) {
+                #module::hash(value, state)
+            }
+        }
+    }
+}
+
+// for `#[reflect(with = ..)]` and friends, the field struct itself becomes the adapter, forwarding
+// to the given functions or to the field type's own impls.
+fn with_functions(
+    parent_ident: &Ident,
+    parent_generics: &Generics,
+    field_struct: &TokenStream,
+    serialize: Option<&Path>,
+    deserialize: Option<&Path>,
+) -> TokenStream {
+    let (impl_generics, type_generics, ..) = parent_generics.split_for_impl();
+    let field_type =
+        quote!(<#parent_ident #type_generics as ::reflector::HasField<#field_struct>>::Type);
+    let with = quote!(::reflector::with);
+
+    let mut de_generics = parent_generics.clone();
+    de_generics.params.insert(0, parse_quote!('__de));
+    let (de_impl_generics, ..) = de_generics.split_for_impl();
+
+    let serialize = match serialize {
+        Some(function) => quote!(#function(value, serializer)),
+        None => quote!(#with::Serialize::serialize(value, serializer)),
+    };
+    // falling back to the `Deserialize` impl of the field requires `'__de` to outlive borrowed data
+    let (deserialize, de_where_clause) = match deserialize {
+        Some(function) => (quote!(#function(deserializer)), quote!()),
+        None => (
+            quote!(#with::Deserialize::deserialize(deserializer)),
+            quote!(where #field_type: #with::Deserialize<'__de>),
+        ),
+    };
 
-            fn try_get_ref(p: &Self::Root) -> Option<&Self::Type> { #accessor }
-            fn try_get_mut(p: &mut Self::Root) -> Option<&mut Self::Type> { #accessor }
+    quote! {
+        impl #impl_generics #with::SerializeAs<#field_type> for #field_struct {
+            fn serialize_as<S>(value: &#field_type, serializer: S) -> ::core::result::Result<S::Ok, S::Error>
+            where
+                S: #with::Serializer,
+            {
+                #serialize
+            }
+        }
+        impl #de_impl_generics #with::DeserializeAs<'__de, #field_type> for #field_struct
+        #de_where_clause
+        {
+            fn deserialize_as<D>(deserializer: D) -> ::core::result::Result<#field_type, D::Error>
+            where
+                D: #with::Deserializer<'__de>,
+            {
+                #deserialize
+            }
         }
     }
 }
@@ -261,8 +925,8 @@ fn accessor(inside_variant: Option<&Variant>, field: &Member) -> TokenStream {
 
     quote! {
         match p {
-            Self::Root #(:: #inside_variant)* { #field: x, .. } => Some(x),
-            _ => None,
+            Self::Root #(:: #inside_variant)* { #field: x, .. } => ::core::option::Option::Some(x),
+            _ => ::core::option::Option::None,
         }
     }
 }
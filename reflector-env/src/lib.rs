@@ -0,0 +1,514 @@
+//! Configuration from environment variables for reflected structs.
+//!
+//! Every field is read from a variable named after its path, in upper case, where the prefix is
+//! followed by `_` and nested fields are separated by `__`: `server.port` with the prefix `APP`
+//! is read from `APP_SERVER__PORT`. Flattened fields don't add to the path.
+//!
+//! Values are parsed with `FromStr`, through [`FromEnv`], where `Option` fields are `None` when
+//! none of their variables are set, `Vec` fields are separated by commas, and fields marked
+//! `#[reflect(default)]` get their default. Nested structs are marked
+//! `#[reflect(impl(reflector_env::FromEnv))]`, or used through the `with::Reflect` adapter, while
+//! other fields with an adapter of `reflector_serde::with` are read through [`FromEnvAs`].
+//!
+//! Loading doesn't stop at the first problem, but reports every missing and invalid variable.
+//! The values of fields marked `#[reflect(sensitive)]` are left out of the errors.
+
+pub use env::{Env, Key};
+pub use error::{Error, ErrorKind, Errors};
+pub use reflect::ReflectEnv;
+
+/// Loads `T` from the environment variables of the process starting with `prefix`.
+///
+/// Variables whose name or value isn't valid unicode are ignored.
+pub fn from_env<T: ReflectEnv>(prefix: &str) -> Result<T, Errors> {
+    let vars = std::env::vars_os()
+        .filter_map(|(name, value)| Some((name.into_string().ok()?, value.into_string().ok()?)));
+    from_vars(prefix, vars)
+}
+
+/// Loads `T` from the given variables, e.g. a map, instead of the environment.
+pub fn from_vars<T, I, K, V>(prefix: &str, vars: I) -> Result<T, Errors>
+where
+    T: ReflectEnv,
+    I: IntoIterator<Item = (K, V)>,
+    K: Into<String>,
+    V: Into<String>,
+{
+    let mut env = Env::new(vars);
+    match T::from_env(&mut env, &Key::root(prefix)) {
+        Some(value) if env.errors.is_empty() => Ok(value),
+        _ => Err(Errors(env.errors)),
+    }
+}
+
+/// A type which can be read from environment variables.
+pub trait FromEnv: Sized {
+    /// Reads the value at `key`, adding every missing or invalid variable to the errors of `env`,
+    /// in which case it may return `None`.
+    fn from_env(env: &mut Env, key: &Key) -> Option<Self>;
+
+    /// Whether the value is given at all, which makes an `Option` `Some`.
+    fn is_set(env: &Env, key: &Key) -> bool {
+        env.var(key).is_some()
+    }
+}
+
+/// Implements [`FromEnv`] for a reflected struct through [`ReflectEnv`], with
+/// `#[reflect(impl(reflector_env::FromEnv))]`. Type parameters have to implement `FromEnv`.
+#[macro_export]
+macro_rules! FromEnv {
+    (
+        lifetimes [$($lifetime:lifetime),*]
+        types [$($param:ident),*]
+        impl [$($params:tt)*] $ty:ty
+        where [$($where:tt)*]
+    ) => {
+        impl<$($params)*> $crate::FromEnv for $ty
+        where
+            $($where)*
+            $($param: $crate::FromEnv,)*
+        {
+            fn from_env(env: &mut $crate::Env, key: &$crate::Key) -> ::core::option::Option<Self> {
+                <Self as $crate::ReflectEnv>::from_env(env, key)
+            }
+
+            fn is_set(env: &$crate::Env, key: &$crate::Key) -> bool {
+                <Self as $crate::ReflectEnv>::is_set(env, key)
+            }
+        }
+    };
+}
+
+/// Reads `T` for a field with an adapter of `reflector_serde::with`.
+pub trait FromEnvAs<T> {
+    fn from_env_as(env: &mut Env, key: &Key) -> Option<T>;
+
+    fn is_set(env: &Env, key: &Key) -> bool {
+        env.var(key).is_some()
+    }
+}
+
+mod env {
+    use std::collections::HashMap;
+
+    use crate::{Error, ErrorKind};
+
+    /// The variables which are read, and the errors so far.
+    pub struct Env {
+        vars: HashMap<String, String>,
+        pub(crate) errors: Vec<Error>,
+    }
+
+    /// The variable of a value, and its path from the root, e.g. `APP_SERVER__PORT` and
+    /// `server.port`.
+    #[derive(Clone, Debug)]
+    pub struct Key {
+        variable: String,
+        path: String,
+    }
+
+    impl Env {
+        pub fn new<I, K, V>(vars: I) -> Self
+        where
+            I: IntoIterator<Item = (K, V)>,
+            K: Into<String>,
+            V: Into<String>,
+        {
+            Self {
+                vars: vars
+                    .into_iter()
+                    .map(|(name, value)| (name.into(), value.into()))
+                    .collect(),
+                errors: Vec::new(),
+            }
+        }
+
+        pub fn var(&self, key: &Key) -> Option<&str> {
+            self.vars.get(&key.variable).map(String::as_str)
+        }
+
+        pub fn error(&mut self, key: &Key, kind: ErrorKind) {
+            self.errors.push(Error {
+                variable: key.variable.clone(),
+                path: key.path.clone(),
+                kind,
+            });
+        }
+
+        /// Removes the values from the errors since the first `from`
+        pub(crate) fn redact(&mut self, from: usize) {
+            for error in &mut self.errors[from..] {
+                if let ErrorKind::Invalid { value, .. } = &mut error.kind {
+                    *value = None;
+                }
+            }
+        }
+    }
+
+    impl Key {
+        pub fn root(prefix: &str) -> Self {
+            Self {
+                variable: prefix.to_owned(),
+                path: String::new(),
+            }
+        }
+
+        /// The key of the field `ident` within this one
+        pub fn field(&self, ident: &str) -> Self {
+            let upper = ident.to_uppercase();
+            let variable = match (self.path.is_empty(), self.variable.is_empty()) {
+                (true, true) => upper,
+                (true, false) => format!("{}_{upper}", self.variable),
+                (false, _) => format!("{}__{upper}", self.variable),
+            };
+            let path = match self.path.is_empty() {
+                true => ident.to_owned(),
+                false => format!("{}.{ident}", self.path),
+            };
+            Self { variable, path }
+        }
+
+        pub fn variable(&self) -> &str {
+            &self.variable
+        }
+
+        pub fn path(&self) -> &str {
+            &self.path
+        }
+    }
+}
+
+mod error {
+    use std::fmt::{self, Display, Formatter};
+
+    /// A missing or invalid variable.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct Error {
+        /// The name of the variable, e.g. `APP_SERVER__PORT`
+        pub variable: String,
+        /// The path of the field, e.g. `server.port`
+        pub path: String,
+        pub kind: ErrorKind,
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub enum ErrorKind {
+        Missing,
+        /// The value is `None` within fields marked `#[reflect(sensitive)]`, to keep secrets out
+        /// of logs.
+        Invalid {
+            value: Option<String>,
+            message: String,
+        },
+    }
+
+    /// Every missing and invalid variable.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct Errors(pub Vec<Error>);
+
+    impl Display for Error {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "{} ({}): ", self.variable, self.path)?;
+            match &self.kind {
+                ErrorKind::Missing => f.write_str("missing"),
+                ErrorKind::Invalid {
+                    value: Some(value),
+                    message,
+                } => write!(f, "invalid value '{value}', {message}"),
+                ErrorKind::Invalid {
+                    value: None,
+                    message,
+                } => write!(f, "invalid value, {message}"),
+            }
+        }
+    }
+
+    impl Display for Errors {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "invalid environment variables:")?;
+            for error in &self.0 {
+                write!(f, "\n  {error}")?;
+            }
+            Ok(())
+        }
+    }
+
+    impl std::error::Error for Error {}
+    impl std::error::Error for Errors {}
+}
+
+mod reflect {
+    use reflector::*;
+
+    use crate::{Env, FromEnv, FromEnvAs, Key};
+
+    /// Loading of reflected structs, implemented for any struct which can be introspected and
+    /// whose fields implement [`FromEnv`], or [`FromEnvAs`] for their adapter.
+    pub trait ReflectEnv: Sized {
+        fn from_env(env: &mut Env, key: &Key) -> Option<Self>;
+
+        /// Whether any field is set
+        fn is_set(env: &Env, key: &Key) -> bool;
+    }
+
+    impl<T> ReflectEnv for T
+    where
+        T: SizedStruct<Root = T, Fields: EnvFields<Values = T::FieldTypes>>,
+    {
+        fn from_env(env: &mut Env, key: &Key) -> Option<Self> {
+            T::Fields::from_env(env, key).map(T::from_values)
+        }
+
+        fn is_set(env: &Env, key: &Key) -> bool {
+            T::Fields::is_set(env, key)
+        }
+    }
+
+    // helper trait implemented recursively for a list of fields, e.g `(Field0, (Field1, ()))`
+    pub trait EnvFields {
+        type Values;
+
+        fn from_env(env: &mut Env, key: &Key) -> Option<Self::Values>;
+        fn is_set(env: &Env, key: &Key) -> bool;
+    }
+    // end of the recursion
+    impl EnvFields for () {
+        type Values = ();
+
+        fn from_env(_: &mut Env, _: &Key) -> Option<()> {
+            Some(())
+        }
+
+        fn is_set(_: &Env, _: &Key) -> bool {
+            false
+        }
+    }
+    impl<Head, Tail> EnvFields for Cons<Head, Tail>
+    where
+        Head: Field<Type: Sized> + EnvField<<Head as Field>::Repr>,
+        Tail: EnvFields,
+    {
+        type Values = Cons<Head::Type, Tail::Values>;
+
+        fn from_env(env: &mut Env, key: &Key) -> Option<Self::Values> {
+            // the tail is read even if the head failed, to report all errors
+            let errors = env.errors.len();
+            let head = Head::from_env(env, key);
+            if Head::SENSITIVE {
+                env.redact(errors);
+            }
+            let tail = Tail::from_env(env, key);
+            Some(Cons(head?, tail?))
+        }
+
+        fn is_set(env: &Env, key: &Key) -> bool {
+            Head::is_set(env, key) || Tail::is_set(env, key)
+        }
+    }
+
+    // helper trait implemented for every field, depending on its representation. `key` is the
+    // key of the parent.
+    pub trait EnvField<Repr>: Field<Type: Sized> {
+        fn from_env(env: &mut Env, key: &Key) -> Option<Self::Type>;
+        fn is_set(env: &Env, key: &Key) -> bool;
+    }
+
+    impl<F> EnvField<PlainRepr> for F
+    where
+        F: Field<Type: FromEnv>,
+    {
+        fn from_env(env: &mut Env, key: &Key) -> Option<F::Type> {
+            let key = field_key::<F>(key);
+            match F::default_value() {
+                Some(default) if !F::Type::is_set(env, &key) => Some(default),
+                _ => F::Type::from_env(env, &key),
+            }
+        }
+
+        fn is_set(env: &Env, key: &Key) -> bool {
+            F::Type::is_set(env, &field_key::<F>(key))
+        }
+    }
+
+    impl<F, A> EnvField<WithRepr<A>> for F
+    where
+        F: Field<Type: Sized>,
+        A: FromEnvAs<F::Type>,
+    {
+        fn from_env(env: &mut Env, key: &Key) -> Option<F::Type> {
+            let key = field_key::<F>(key);
+            match F::default_value() {
+                Some(default) if !A::is_set(env, &key) => Some(default),
+                _ => A::from_env_as(env, &key),
+            }
+        }
+
+        fn is_set(env: &Env, key: &Key) -> bool {
+            A::is_set(env, &field_key::<F>(key))
+        }
+    }
+
+    // the fields of the flattened struct are read as fields of the parent
+    impl<F, Inner> EnvField<FlattenRepr> for F
+    where
+        F: Field<Type = Inner>,
+        Inner: SizedStruct<Root = Inner, Fields: EnvFields<Values = Inner::FieldTypes>>,
+    {
+        fn from_env(env: &mut Env, key: &Key) -> Option<Inner> {
+            Inner::Fields::from_env(env, key).map(Inner::from_values)
+        }
+
+        fn is_set(env: &Env, key: &Key) -> bool {
+            Inner::Fields::is_set(env, key)
+        }
+    }
+
+    fn field_key<F: Field>(parent: &Key) -> Key {
+        const {
+            assert!(
+                F::IDENT.is_some(),
+                "fields read from the environment need names, or `#[reflect(flatten)]`"
+            )
+        };
+        parent.field(F::IDENT.unwrap())
+    }
+}
+
+// the adapters of `reflector_serde::with`
+mod adapters {
+    use std::{fmt::Display, str::FromStr};
+
+    use reflector_serde::with::{DisplayFromStr, Reflect, Same};
+
+    use crate::impls::from_str;
+    use crate::{Env, FromEnv, FromEnvAs, Key, ReflectEnv};
+
+    impl<T: FromEnv> FromEnvAs<T> for Same {
+        fn from_env_as(env: &mut Env, key: &Key) -> Option<T> {
+            T::from_env(env, key)
+        }
+
+        fn is_set(env: &Env, key: &Key) -> bool {
+            T::is_set(env, key)
+        }
+    }
+
+    impl<T: ReflectEnv> FromEnvAs<T> for Reflect {
+        fn from_env_as(env: &mut Env, key: &Key) -> Option<T> {
+            T::from_env(env, key)
+        }
+
+        fn is_set(env: &Env, key: &Key) -> bool {
+            T::is_set(env, key)
+        }
+    }
+
+    impl<T: FromStr<Err: Display>> FromEnvAs<T> for DisplayFromStr {
+        fn from_env_as(env: &mut Env, key: &Key) -> Option<T> {
+            from_str(env, key)
+        }
+    }
+}
+
+mod impls {
+    use std::{
+        ffi::OsString,
+        fmt::Display,
+        net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6},
+        path::PathBuf,
+        str::FromStr,
+    };
+
+    use crate::{Env, ErrorKind, FromEnv, Key};
+
+    pub fn from_str<T: FromStr<Err: Display>>(env: &mut Env, key: &Key) -> Option<T> {
+        let Some(value) = env.var(key) else {
+            env.error(key, ErrorKind::Missing);
+            return None;
+        };
+        parse(env, key, value.to_owned())
+    }
+
+    fn parse<T: FromStr<Err: Display>>(env: &mut Env, key: &Key, value: String) -> Option<T> {
+        match value.parse() {
+            Ok(value) => Some(value),
+            Err(error) => {
+                let message = error.to_string();
+                let value = Some(value);
+                env.error(key, ErrorKind::Invalid { value, message });
+                None
+            }
+        }
+    }
+
+    macro_rules! from_str {
+        ($($t:ty),*) => {$(
+            impl FromEnv for $t {
+                fn from_env(env: &mut Env, key: &Key) -> Option<Self> {
+                    from_str(env, key)
+                }
+            }
+        )*};
+    }
+
+    from_str!(
+        bool,
+        u8,
+        u16,
+        u32,
+        u64,
+        u128,
+        usize,
+        i8,
+        i16,
+        i32,
+        i64,
+        i128,
+        isize,
+        f32,
+        f64,
+        char,
+        String,
+        PathBuf,
+        OsString,
+        IpAddr,
+        Ipv4Addr,
+        Ipv6Addr,
+        SocketAddr,
+        SocketAddrV4,
+        SocketAddrV6
+    );
+
+    impl<T: FromEnv> FromEnv for Option<T> {
+        fn from_env(env: &mut Env, key: &Key) -> Option<Self> {
+            match T::is_set(env, key) {
+                true => T::from_env(env, key).map(Some),
+                false => Some(None),
+            }
+        }
+
+        fn is_set(env: &Env, key: &Key) -> bool {
+            T::is_set(env, key)
+        }
+    }
+
+    // comma separated, where an empty variable is an empty list
+    impl<T: FromStr<Err: Display>> FromEnv for Vec<T> {
+        fn from_env(env: &mut Env, key: &Key) -> Option<Self> {
+            let Some(value) = env.var(key) else {
+                env.error(key, ErrorKind::Missing);
+                return None;
+            };
+            if value.is_empty() {
+                return Some(Vec::new());
+            }
+            let elements = value.split(',').map(str::to_owned).collect::<Vec<_>>();
+            // every invalid element is reported
+            let elements = elements
+                .into_iter()
+                .map(|element| parse(env, key, element))
+                .collect::<Vec<_>>();
+            elements.into_iter().collect()
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+
+use reflector::Introspect;
+use reflector_env::{Error, ErrorKind, Errors, from_vars};
+use reflector_serde::with;
+
+#[derive(Debug, PartialEq, Introspect)]
+pub struct Config {
+    name: String,
+    #[reflect(adapter = with::Reflect)]
+    server: Server,
+    database: Option<Database>,
+    #[reflect(default)]
+    workers: usize,
+    #[reflect(flatten)]
+    log: Log,
+}
+
+#[derive(Debug, PartialEq, Introspect)]
+#[reflect(impl(reflector_env::FromEnv))]
+pub struct Server {
+    host: String,
+    port: u16,
+    allowed: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Introspect)]
+#[reflect(impl(reflector_env::FromEnv))]
+pub struct Database {
+    url: String,
+    pool_size: Option<u32>,
+}
+
+#[derive(Debug, PartialEq, Introspect)]
+pub struct Log {
+    verbose: bool,
+}
+
+fn vars(vars: &[(&str, &str)]) -> HashMap<String, String> {
+    vars.iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+#[test]
+fn load() {
+    let env = vars(&[
+        ("APP_NAME", "demo"),
+        ("APP_SERVER__HOST", "localhost"),
+        ("APP_SERVER__PORT", "8080"),
+        ("APP_SERVER__ALLOWED", "a,b"),
+        ("APP_VERBOSE", "true"),
+        ("OTHER", "ignored"),
+    ]);
+    assert_eq!(
+        from_vars::<Config, _, _, _>("APP", env),
+        Ok(Config {
+            name: "demo".into(),
+            server: Server {
+                host: "localhost".into(),
+                port: 8080,
+                allowed: vec!["a".into(), "b".into()],
+            },
+            database: None,
+            workers: 0,
+            log: Log { verbose: true },
+        })
+    );
+
+    let env = vars(&[
+        ("NAME", "demo"),
+        ("SERVER__HOST", "localhost"),
+        ("SERVER__PORT", "1"),
+        ("SERVER__ALLOWED", ""),
+        ("DATABASE__URL", "postgres://"),
+        ("WORKERS", "4"),
+        ("VERBOSE", "false"),
+    ]);
+    let config = from_vars::<Config, _, _, _>("", env).unwrap();
+    assert_eq!(config.server.allowed, Vec::<String>::new());
+    assert_eq!(
+        config.database,
+        Some(Database {
+            url: "postgres://".into(),
+            pool_size: None,
+        })
+    );
+    assert_eq!(config.workers, 4);
+}
+
+#[test]
+fn errors() {
+    let env = vars(&[
+        ("APP_SERVER__PORT", "http"),
+        ("APP_SERVER__ALLOWED", "a"),
+        ("APP_DATABASE__POOL_SIZE", "-1"),
+        ("APP_VERBOSE", "yes"),
+    ]);
+    let error = |variable: &str, path: &str, kind| Error {
+        variable: variable.into(),
+        path: path.into(),
+        kind,
+    };
+    let invalid = |value: &str, message: &str| ErrorKind::Invalid {
+        value: Some(value.into()),
+        message: message.into(),
+    };
+    let errors = from_vars::<Config, _, _, _>("APP", env).unwrap_err();
+    assert_eq!(
+        errors,
+        Errors(vec![
+            error("APP_NAME", "name", ErrorKind::Missing),
+            error("APP_SERVER__HOST", "server.host", ErrorKind::Missing),
+            error(
+                "APP_SERVER__PORT",
+                "server.port",
+                invalid("http", "invalid digit found in string")
+            ),
+            error("APP_DATABASE__URL", "database.url", ErrorKind::Missing),
+            error(
+                "APP_DATABASE__POOL_SIZE",
+                "database.pool_size",
+                invalid("-1", "invalid digit found in string")
+            ),
+            error(
+                "APP_VERBOSE",
+                "verbose",
+                invalid("yes", "provided string was not `true` or `false`")
+            ),
+        ])
+    );
+    assert_eq!(
+        errors.to_string(),
+        "invalid environment variables:
+  APP_NAME (name): missing
+  APP_SERVER__HOST (server.host): missing
+  APP_SERVER__PORT (server.port): invalid value 'http', invalid digit found in string
+  APP_DATABASE__URL (database.url): missing
+  APP_DATABASE__POOL_SIZE (database.pool_size): invalid value '-1', invalid digit found in string
+  APP_VERBOSE (verbose): invalid value 'yes', provided string was not `true` or `false`"
+    );
+}
+
+#[derive(Debug, PartialEq, Introspect)]
+pub struct Limits {
+    #[reflect(adapter = with::DisplayFromStr)]
+    timeout: u64,
+    sizes: Vec<u32>,
+}
+
+#[test]
+fn lists_and_adapters() {
+    let env = vars(&[("TIMEOUT", "30"), ("SIZES", "1,x,3,y")]);
+    let errors = from_vars::<Limits, _, _, _>("", env).unwrap_err();
+    assert_eq!(
+        errors.0.iter().map(|e| &e.kind).collect::<Vec<_>>(),
+        [
+            &ErrorKind::Invalid {
+                value: Some("x".into()),
+                message: "invalid digit found in string".into()
+            },
+            &ErrorKind::Invalid {
+                value: Some("y".into()),
+                message: "invalid digit found in string".into()
+            },
+        ]
+    );
+
+    let env = vars(&[("TIMEOUT", "30"), ("SIZES", "1,2")]);
+    assert_eq!(
+        from_vars::<Limits, _, _, _>("", env),
+        Ok(Limits {
+            timeout: 30,
+            sizes: vec![1, 2]
+        })
+    );
+}
+
+#[derive(Debug, PartialEq, Introspect)]
+pub struct Credentials {
+    user: String,
+    #[reflect(sensitive)]
+    pin: u32,
+    #[reflect(sensitive)]
+    backup_pins: Vec<u32>,
+}
+
+#[test]
+fn sensitive() {
+    let env = vars(&[
+        ("USER", "admin"),
+        ("PIN", "hunter2"),
+        ("BACKUP_PINS", "1,secret"),
+    ]);
+    let errors = from_vars::<Credentials, _, _, _>("", env).unwrap_err();
+    assert_eq!(
+        errors.0.iter().map(|e| &e.kind).collect::<Vec<_>>(),
+        [
+            &ErrorKind::Invalid {
+                value: None,
+                message: "invalid digit found in string".into()
+            },
+            &ErrorKind::Invalid {
+                value: None,
+                message: "invalid digit found in string".into()
+            },
+        ]
+    );
+    let message = errors.to_string();
+    assert!(!message.contains("hunter2") && !message.contains("secret"));
+    assert!(message.contains("PIN (pin): invalid value, invalid digit found in string"));
+}
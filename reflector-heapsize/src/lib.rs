@@ -1,35 +1,475 @@
-pub use reflect::Reflect;
+pub use context::{HeapSizeContext, SharedPolicy};
+pub use reflect::{Reflect, ReflectHeapSize, VariantUsage};
+pub use report::HeapReport;
+pub use size_classes::SizeClasses;
+
+pub mod testing;
 
 /// Compute the number of bytes a value uses on the heap
 pub trait HeapSize {
     const HAS_HEAP: bool;
 
-    fn heap_size(&self) -> usize;
+    /// Heap usage, counting shared allocations according to the [`SharedPolicy::default`].
+    fn heap_size(&self) -> usize {
+        self.heap_size_with(&mut HeapSizeContext::default())
+    }
+
+    /// Heap usage, with `Rc`s and `Arc`s counted according to the policy of the context.
+    ///
+    /// Implementations must pass the context on to the values they own.
+    fn heap_size_with(&self, cx: &mut HeapSizeContext) -> usize;
+
+    /// Bytes the value uses inline, i.e. `size_of_val(self)`, not including the heap.
+    fn shallow_size(&self) -> usize {
+        size_of_val(self)
+    }
+
+    /// Total footprint of the value: its [`shallow_size`](Self::shallow_size) and its
+    /// [`heap_size`](Self::heap_size).
+    fn deep_size(&self) -> usize {
+        self.deep_size_with(&mut HeapSizeContext::default())
+    }
+
+    fn deep_size_with(&self, cx: &mut HeapSizeContext) -> usize {
+        self.shallow_size() + self.heap_size_with(cx)
+    }
+
+    /// Like [`heap_size`](Self::heap_size), but broken down into the parts of the value.
+    fn heap_report(&self) -> HeapReport {
+        self.heap_report_with(&mut HeapSizeContext::default())
+    }
+
+    /// Like [`heap_size_with`](Self::heap_size_with), but broken down into the parts of the value,
+    /// e.g. the fields of a [`Reflect`]ed struct.
+    fn heap_report_with(&self, cx: &mut HeapSizeContext) -> HeapReport {
+        HeapReport::new("", self.heap_size_with(cx))
+    }
+}
+
+/// Implements [`HeapSize`] for a reflected type through [`ReflectHeapSize`], with
+/// `#[reflect(impl(reflector_heapsize::HeapSize))]`. Type parameters have to implement `HeapSize`.
+#[macro_export]
+macro_rules! HeapSize {
+    (
+        lifetimes [$($lifetime:lifetime),*]
+        types [$($param:ident),*]
+        impl [$($params:tt)*] $ty:ty
+        where [$($where:tt)*]
+    ) => {
+        impl<$($params)*> $crate::HeapSize for $ty
+        where
+            $($where)*
+            $($param: $crate::HeapSize,)*
+        {
+            const HAS_HEAP: bool = <Self as $crate::ReflectHeapSize>::HAS_HEAP;
+
+            fn heap_size_with(&self, cx: &mut $crate::HeapSizeContext) -> usize {
+                $crate::ReflectHeapSize::heap_size(self, cx)
+            }
+
+            fn heap_report_with(&self, cx: &mut $crate::HeapSizeContext) -> $crate::HeapReport {
+                $crate::ReflectHeapSize::heap_report(self, cx)
+            }
+        }
+    };
+}
+
+/// Marker for types which don't own heap memory, whose [`HeapSize::HAS_HEAP`] is false.
+///
+/// It is implemented by [`assert_no_heap!`] after checking a type, and for std types which don't
+/// own heap memory themselves, like primitives, and arrays, tuples and options of such types.
+///
+/// ```
+/// # use reflector_heapsize::NoHeap;
+/// fn realtime<T: NoHeap>(_: &T) {}
+///
+/// realtime(&[0.0f32; 64]);
+/// ```
+///
+/// ```compile_fail
+/// # use reflector_heapsize::NoHeap;
+/// # fn realtime<T: NoHeap>(_: &T) {}
+/// realtime(&vec![0.0f32; 64]);
+/// ```
+pub trait NoHeap: HeapSize {}
+
+/// Fails to compile if the type owns heap memory, i.e. if `HeapSize::HAS_HEAP` is true, and
+/// implements [`NoHeap`] for it otherwise, which requires the type to be local.
+///
+/// ```
+/// # use reflector_heapsize::{HeapSize, HeapSizeContext, assert_no_heap};
+/// struct Frame([f32; 64], Option<char>);
+/// # impl HeapSize for Frame {
+/// #     const HAS_HEAP: bool = false;
+/// #     fn heap_size_with(&self, _: &mut HeapSizeContext) -> usize { 0 }
+/// # }
+/// assert_no_heap!(Frame);
+/// ```
+///
+/// ```compile_fail
+/// # use reflector_heapsize::{HeapSize, HeapSizeContext, assert_no_heap};
+/// struct Buffer(Vec<f32>);
+/// # impl HeapSize for Buffer {
+/// #     const HAS_HEAP: bool = true;
+/// #     fn heap_size_with(&self, _: &mut HeapSizeContext) -> usize { 0 }
+/// # }
+/// assert_no_heap!(Buffer);
+/// ```
+#[macro_export]
+macro_rules! assert_no_heap {
+    ($t:ty) => {
+        const _: () = ::core::assert!(
+            !<$t as $crate::HeapSize>::HAS_HEAP,
+            ::core::concat!("`", ::core::stringify!($t), "` owns heap memory"),
+        );
+        impl $crate::NoHeap for $t {}
+    };
+}
+
+mod context {
+    use std::collections::HashSet;
+
+    use crate::SizeClasses;
+
+    /// How allocations that are shared via `Rc` or `Arc` are counted.
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub enum SharedPolicy {
+        /// Count every allocation the first time it is reached, no matter how many pointers to it
+        /// are found afterwards.
+        #[default]
+        CountOnce,
+        /// Count `1 / strong_count` of the allocation for every pointer to it, as if the
+        /// allocation was split evenly between its owners.
+        Proportional,
+        /// Don't count allocations which have more than one owner.
+        Skip,
+    }
+
+    /// State threaded through [`HeapSize::heap_size_with`](crate::HeapSize::heap_size_with),
+    /// tracking the shared allocations that were already visited.
+    #[derive(Debug, Default)]
+    pub struct HeapSizeContext {
+        policy: SharedPolicy,
+        size_classes: SizeClasses,
+        // allocations that were counted, for `CountOnce`
+        counted: HashSet<*const ()>,
+        // allocations currently being counted, to break reference cycles
+        entered: HashSet<*const ()>,
+    }
+
+    impl HeapSizeContext {
+        pub fn new(policy: SharedPolicy) -> Self {
+            Self {
+                policy,
+                ..Self::default()
+            }
+        }
+
+        /// Rounds every allocation up to its size class, see [`HeapSizeContext::allocation`].
+        pub fn with_size_classes(self, size_classes: SizeClasses) -> Self {
+            Self {
+                size_classes,
+                ..self
+            }
+        }
+
+        pub fn policy(&self) -> SharedPolicy {
+            self.policy
+        }
+
+        pub fn size_classes(&self) -> SizeClasses {
+            self.size_classes
+        }
+
+        /// Bytes used by an allocation of `size` bytes, `0` for no allocation at all.
+        ///
+        /// Implementations of `HeapSize` should pass the size of each allocation they own through
+        /// this, so that it can be rounded to the size classes of the allocator.
+        pub fn allocation(&self, size: usize) -> usize {
+            self.size_classes.round(size)
+        }
+
+        /// Counts a shared allocation at `ptr`, owned by `strong_count` pointers.
+        ///
+        /// `size` computes the full size of the allocation and is only called if it is counted.
+        pub fn shared(
+            &mut self,
+            ptr: *const (),
+            strong_count: usize,
+            size: impl FnOnce(&mut Self) -> usize,
+        ) -> usize {
+            let proportional = match self.policy {
+                SharedPolicy::CountOnce if !self.counted.insert(ptr) => return 0,
+                SharedPolicy::Skip if strong_count > 1 => return 0,
+                SharedPolicy::Proportional => strong_count.max(1),
+                _ => 1,
+            };
+            if !self.entered.insert(ptr) {
+                return 0;
+            }
+            let size = size(self);
+            self.entered.remove(&ptr);
+            size / proportional
+        }
+    }
+}
+
+mod size_classes {
+    /// Size classes of an allocator, i.e. the sizes that requested allocations are rounded up to.
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub enum SizeClasses {
+        /// Allocations use exactly the requested size
+        #[default]
+        Exact,
+        /// jemalloc: multiples of 16 up to 128 bytes, then four classes per doubling
+        Jemalloc,
+        /// mimalloc: multiples of 8 up to 64 bytes, then four bins per doubling up to 128 KiB,
+        /// larger allocations are rounded to whole pages.
+        Mimalloc,
+        /// Sizes are rounded up to the next entry of the ascending table, allocations larger than
+        /// the last entry are rounded to whole pages.
+        Table(&'static [usize]),
+    }
+
+    const PAGE: usize = 4096;
+    const WORD: usize = size_of::<usize>();
+
+    impl SizeClasses {
+        /// Rounds the requested `size` up to its size class.
+        pub fn round(self, size: usize) -> usize {
+            if size == 0 {
+                return 0;
+            }
+            match self {
+                SizeClasses::Exact => size,
+                SizeClasses::Jemalloc if size <= 8 => 8,
+                SizeClasses::Jemalloc if size <= 128 => size.next_multiple_of(16),
+                SizeClasses::Jemalloc => size.next_multiple_of(quarter(size)),
+                SizeClasses::Mimalloc if size <= 64 => size.next_multiple_of(WORD),
+                SizeClasses::Mimalloc if size <= 128 * 1024 => {
+                    let words = size.div_ceil(WORD);
+                    words.next_multiple_of(quarter(words)) * WORD
+                }
+                SizeClasses::Mimalloc => size.next_multiple_of(PAGE),
+                SizeClasses::Table(table) => match table.iter().find(|&&class| class >= size) {
+                    Some(&class) => class,
+                    None => size.next_multiple_of(PAGE),
+                },
+            }
+        }
+    }
+
+    // a quarter of the power of two below `n`, i.e. the spacing of four classes per doubling
+    fn quarter(n: usize) -> usize {
+        1 << ((n - 1).ilog2() - 2)
+    }
+}
+
+mod report {
+    use std::{
+        borrow::Cow,
+        fmt::{self, Display, Formatter, Write},
+    };
+
+    /// Heap usage of a value and its parts, e.g. fields and active variants.
+    ///
+    /// Displays as an indented table, `{:#}` additionally lists parts that don't use the heap.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct HeapReport {
+        /// Name of the type for the root, otherwise of the field or variant
+        pub name: Cow<'static, str>,
+        /// Total heap usage, including all children
+        pub size: usize,
+        pub children: Vec<HeapReport>,
+    }
+
+    impl HeapReport {
+        pub fn new(name: impl Into<Cow<'static, str>>, size: usize) -> Self {
+            Self {
+                name: name.into(),
+                size,
+                children: Vec::new(),
+            }
+        }
+
+        pub fn named(self, name: impl Into<Cow<'static, str>>) -> Self {
+            Self {
+                name: name.into(),
+                ..self
+            }
+        }
+
+        /// Finds a descendant by its path of names, e.g. `["config", "servers"]`.
+        pub fn get(&self, path: &[&str]) -> Option<&HeapReport> {
+            path.iter().try_fold(self, |report, name| {
+                report.children.iter().find(|child| child.name == *name)
+            })
+        }
+
+        /// Serializes the report as JSON, e.g. `{"name":"A","size":3,"children":[]}`.
+        pub fn to_json(&self) -> String {
+            let mut json = String::new();
+            self.write_json(&mut json).unwrap();
+            json
+        }
+
+        fn write_json(&self, out: &mut String) -> fmt::Result {
+            out.push_str("{\"name\":\"");
+            for c in self.name.chars() {
+                match c {
+                    '"' => out.push_str("\\\""),
+                    '\\' => out.push_str("\\\\"),
+                    c if c.is_control() => write!(out, "\\u{:04x}", c as u32)?,
+                    c => out.push(c),
+                }
+            }
+            write!(out, "\",\"size\":{},\"children\":[", self.size)?;
+            for (i, child) in self.children.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                child.write_json(out)?;
+            }
+            out.push_str("]}");
+            Ok(())
+        }
+
+        fn rows<'a>(&'a self, depth: usize, all: bool, rows: &mut Vec<(usize, &'a str, usize)>) {
+            rows.push((depth, &self.name, self.size));
+            for child in &self.children {
+                if all || child.size > 0 {
+                    child.rows(depth + 1, all, rows);
+                }
+            }
+        }
+    }
+
+    impl Display for HeapReport {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            let mut rows = Vec::new();
+            self.rows(0, f.alternate(), &mut rows);
+            let names = rows.iter().map(|(depth, name, _)| 2 * depth + name.len());
+            let names = names.max().unwrap_or(0);
+            let sizes = rows.iter().map(|(_, _, size)| size.to_string().len());
+            let sizes = sizes.max().unwrap_or(0);
+            for (depth, name, size) in rows {
+                let indent = 2 * depth;
+                let width = names - indent;
+                writeln!(f, "{:indent$}{name:<width$}  {size:>sizes$}", "")?;
+            }
+            Ok(())
+        }
+    }
 }
 
 // instead of a derive macro, this enables users to get an impl of `HeapSize` for their structs.
 mod reflect {
-    use super::HeapSize;
-    use reflector::*;
+    use super::{HeapReport, HeapSize, HeapSizeContext, NoHeap};
     use reflector::Cons;
+    use reflector::*;
+    use std::borrow::Cow;
 
     pub struct Reflect<'a, T>(pub &'a T);
 
-    trait ReflectHelper<Shape> {
+    /// Heap usage of reflected types, computed from their fields.
+    ///
+    /// Used by `#[reflect(impl(reflector_heapsize::HeapSize))]` to implement [`HeapSize`], which
+    /// allows reflected types to be nested in other types, e.g. `Vec<T>`.
+    pub trait ReflectHeapSize {
         const HAS_HEAP: bool;
 
-        fn heap_size(&self) -> usize;
+        fn heap_size(&self, cx: &mut HeapSizeContext) -> usize;
+
+        fn heap_report(&self, cx: &mut HeapSizeContext) -> HeapReport;
+
+        /// How much of the enum is used by the active variant, `None` for structs.
+        fn variant_usage(&self) -> Option<VariantUsage>;
+    }
+
+    /// Inline bytes used by the active variant of an enum, compared to the other variants.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct VariantUsage {
+        /// Name of the active variant
+        pub variant: &'static str,
+        /// Size of the fields of the active variant, without padding
+        pub used: usize,
+        /// Size of the fields of the largest variant, without padding
+        pub largest: usize,
+        /// Size of the enum
+        pub size: usize,
     }
 
-    impl<'a, T> HeapSize for Reflect<'a, T>
+    impl VariantUsage {
+        /// Bytes the active variant leaves unused, compared to the largest variant.
+        pub fn wasted(&self) -> usize {
+            self.largest - self.used
+        }
+
+        /// Bytes used for neither the fields of the active variant nor the largest variant, i.e.
+        /// the tag and padding.
+        pub fn overhead(&self) -> usize {
+            self.size.saturating_sub(self.largest)
+        }
+    }
+
+    impl<T> ReflectHeapSize for T
     where
         T: Introspect,
         T: ReflectHelper<T::Kind>,
     {
+        const HAS_HEAP: bool = <T as ReflectHelper<T::Kind>>::HAS_HEAP;
+
+        fn heap_size(&self, cx: &mut HeapSizeContext) -> usize {
+            <T as ReflectHelper<T::Kind>>::heap_size(self, cx)
+        }
+
+        fn heap_report(&self, cx: &mut HeapSizeContext) -> HeapReport {
+            let children = <T as ReflectHelper<T::Kind>>::heap_report(self, cx);
+            HeapReport {
+                name: T::IDENT.into(),
+                size: children.iter().map(|child| child.size).sum(),
+                children,
+            }
+        }
+
+        fn variant_usage(&self) -> Option<VariantUsage> {
+            <T as ReflectHelper<T::Kind>>::variant_usage(self)
+        }
+    }
+
+    trait ReflectHelper<Shape> {
+        const HAS_HEAP: bool;
+
+        fn heap_size(&self, cx: &mut HeapSizeContext) -> usize;
+
+        fn heap_report(&self, cx: &mut HeapSizeContext) -> Vec<HeapReport>;
+
+        fn variant_usage(&self) -> Option<VariantUsage>;
+    }
+
+    impl<'a, T: ReflectHeapSize> HeapSize for Reflect<'a, T> {
         const HAS_HEAP: bool = T::HAS_HEAP;
 
-        fn heap_size(&self) -> usize {
-            T::heap_size(self.0)
+        fn heap_size_with(&self, cx: &mut HeapSizeContext) -> usize {
+            T::heap_size(self.0, cx)
+        }
+
+        fn heap_report_with(&self, cx: &mut HeapSizeContext) -> HeapReport {
+            T::heap_report(self.0, cx)
+        }
+
+        // the wrapped value, not the reference to it
+        fn shallow_size(&self) -> usize {
+            size_of_val(self.0)
+        }
+    }
+
+    impl<T: ReflectHeapSize + NoHeap> NoHeap for Reflect<'_, T> {}
+
+    impl<T: ReflectHeapSize> Reflect<'_, T> {
+        /// See [`ReflectHeapSize::variant_usage`].
+        pub fn variant_usage(&self) -> Option<VariantUsage> {
+            self.0.variant_usage()
         }
     }
 
@@ -40,8 +480,18 @@ mod reflect {
     {
         const HAS_HEAP: bool = <T::Fields as HeapSizeFields<T>>::HAS_HEAP;
 
-        fn heap_size(&self) -> usize {
-            <T::Fields as HeapSizeFields<T>>::heap_size(self)
+        fn heap_size(&self, cx: &mut HeapSizeContext) -> usize {
+            <T::Fields as HeapSizeFields<T>>::heap_size(self, cx)
+        }
+
+        fn heap_report(&self, cx: &mut HeapSizeContext) -> Vec<HeapReport> {
+            let mut children = Vec::new();
+            <T::Fields as HeapSizeFields<T>>::heap_report(self, cx, &mut children);
+            children
+        }
+
+        fn variant_usage(&self) -> Option<VariantUsage> {
+            None
         }
     }
 
@@ -52,8 +502,24 @@ mod reflect {
     {
         const HAS_HEAP: bool = <T::Variants as HeapSizeVariants<T>>::HAS_HEAP;
 
-        fn heap_size(&self) -> usize {
-            <T::Variants as HeapSizeVariants<T>>::heap_size(self)
+        fn heap_size(&self, cx: &mut HeapSizeContext) -> usize {
+            <T::Variants as HeapSizeVariants<T>>::heap_size(self, cx)
+        }
+
+        fn heap_report(&self, cx: &mut HeapSizeContext) -> Vec<HeapReport> {
+            <T::Variants as HeapSizeVariants<T>>::heap_report(self, cx)
+                .into_iter()
+                .collect()
+        }
+
+        fn variant_usage(&self) -> Option<VariantUsage> {
+            let (variant, used) = <T::Variants as HeapSizeVariants<T>>::active(self)?;
+            Some(VariantUsage {
+                variant,
+                used,
+                largest: <T::Variants as HeapSizeVariants<T>>::LARGEST,
+                size: size_of::<T>(),
+            })
         }
     }
 
@@ -61,15 +527,19 @@ mod reflect {
     trait HeapSizeFields<P> {
         const HAS_HEAP: bool;
 
-        fn heap_size(parent: &P) -> usize;
+        fn heap_size(parent: &P, cx: &mut HeapSizeContext) -> usize;
+
+        fn heap_report(parent: &P, cx: &mut HeapSizeContext, out: &mut Vec<HeapReport>);
     }
     // end of the recursion
     impl<P> HeapSizeFields<P> for () {
         const HAS_HEAP: bool = false;
 
-        fn heap_size(_: &P) -> usize {
+        fn heap_size(_: &P, _: &mut HeapSizeContext) -> usize {
             0
         }
+
+        fn heap_report(_: &P, _: &mut HeapSizeContext, _: &mut Vec<HeapReport>) {}
     }
     impl<P, Head, Tail> HeapSizeFields<P> for Cons<Head, Tail>
     where
@@ -79,38 +549,106 @@ mod reflect {
     {
         const HAS_HEAP: bool = Head::Type::HAS_HEAP || Tail::HAS_HEAP;
 
-        fn heap_size(parent: &P) -> usize {
-            Head::try_get_ref(parent).unwrap().heap_size() + Tail::heap_size(parent)
+        fn heap_size(parent: &P, cx: &mut HeapSizeContext) -> usize {
+            let head = Head::try_get_ref(parent).unwrap().heap_size_with(cx);
+            head + Tail::heap_size(parent, cx)
+        }
+
+        fn heap_report(parent: &P, cx: &mut HeapSizeContext, out: &mut Vec<HeapReport>) {
+            let name = match Head::IDENT {
+                Some(ident) => Cow::Borrowed(ident),
+                None => Cow::Owned(Head::INDEX.to_string()),
+            };
+            let head = Head::try_get_ref(parent).unwrap().heap_report_with(cx);
+            out.push(head.named(name));
+            Tail::heap_report(parent, cx, out);
         }
     }
 
+    // sum of the sizes of the fields of a variant, which are always sized
+    trait InlineSize {
+        const SIZE: usize;
+    }
+    impl InlineSize for () {
+        const SIZE: usize = 0;
+    }
+    impl<Head, Tail> InlineSize for Cons<Head, Tail>
+    where
+        Head: Field<Type: Sized>,
+        Tail: InlineSize,
+    {
+        const SIZE: usize = size_of::<Head::Type>() + Tail::SIZE;
+    }
+
     // helper trait implemented recursively for a list of fields, e.g `(Field0, (Field1, ()))`
     trait HeapSizeVariants<P> {
         const HAS_HEAP: bool;
+        // inline size of the largest variant
+        const LARGEST: usize;
+
+        fn heap_size(parent: &P, cx: &mut HeapSizeContext) -> usize;
 
-        fn heap_size(parent: &P) -> usize;
+        fn heap_report(parent: &P, cx: &mut HeapSizeContext) -> Option<HeapReport>;
+
+        // name and inline size of the active variant
+        fn active(parent: &P) -> Option<(&'static str, usize)>;
     }
     // end of the recursion
     impl<P> HeapSizeVariants<P> for () {
         const HAS_HEAP: bool = false;
+        const LARGEST: usize = 0;
 
-        fn heap_size(_: &P) -> usize {
+        fn heap_size(_: &P, _: &mut HeapSizeContext) -> usize {
             0
         }
+
+        fn heap_report(_: &P, _: &mut HeapSizeContext) -> Option<HeapReport> {
+            None
+        }
+
+        fn active(_: &P) -> Option<(&'static str, usize)> {
+            None
+        }
     }
     impl<P, Head, Tail> HeapSizeVariants<P> for Cons<Head, Tail>
     where
         Head: Variant<Root = P>,
-        Head::Fields: HeapSizeFields<P>,
+        Head::Fields: HeapSizeFields<P> + InlineSize,
         Tail: HeapSizeVariants<P>,
     {
         const HAS_HEAP: bool = Head::Fields::HAS_HEAP || Tail::HAS_HEAP;
+        const LARGEST: usize = if Head::Fields::SIZE > Tail::LARGEST {
+            Head::Fields::SIZE
+        } else {
+            Tail::LARGEST
+        };
 
-        fn heap_size(parent: &P) -> usize {
+        fn heap_size(parent: &P, cx: &mut HeapSizeContext) -> usize {
             if Head::is_active(parent) {
-                Head::Fields::heap_size(parent)
+                Head::Fields::heap_size(parent, cx)
             } else {
-                Tail::heap_size(parent)
+                Tail::heap_size(parent, cx)
+            }
+        }
+
+        fn heap_report(parent: &P, cx: &mut HeapSizeContext) -> Option<HeapReport> {
+            if !Head::is_active(parent) {
+                return Tail::heap_report(parent, cx);
+            }
+            let mut children = Vec::new();
+            Head::Fields::heap_report(parent, cx, &mut children);
+            Some(HeapReport {
+                name: Head::IDENT.into(),
+                size: children.iter().map(|child| child.size).sum(),
+                children,
+            })
+        }
+
+        fn active(parent: &P) -> Option<(&'static str, usize)> {
+            if Head::is_active(parent) {
+                Some((Head::IDENT, Head::Fields::SIZE))
+            } else {
+                Tail::active(parent)
             }
         }
     }
@@ -118,78 +656,358 @@ mod reflect {
 
 // very boring impls for primitive types & stuff from std
 mod impls {
-    use super::HeapSize;
+    use super::{HeapReport, HeapSize, HeapSizeContext, NoHeap};
+    use std::{
+        alloc::Layout,
+        borrow::{Cow, ToOwned},
+        cell::{Cell, RefCell},
+        collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet, LinkedList, VecDeque},
+        marker::PhantomData,
+        rc::Rc,
+        sync::Arc,
+    };
 
     macro_rules! primitives {
         ($($t:ty),*) => {$(
             impl HeapSize for $t  {
                 const HAS_HEAP: bool = false;
 
-                fn heap_size(&self) -> usize { 0 }
+                fn heap_size_with(&self, _: &mut HeapSizeContext) -> usize { 0 }
             }
+
+            impl NoHeap for $t {}
         )*};
     }
 
     primitives!(
-        u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, f32, f64, str
+        u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize
+    );
+    primitives!(f32, f64, bool, char, str, ());
+
+    impl<T: ?Sized> NoHeap for PhantomData<T> {}
+    impl<T: NoHeap + ?Sized> NoHeap for &T {}
+    impl<T: NoHeap + ?Sized> NoHeap for &mut T {}
+    impl<T: NoHeap> NoHeap for [T] {}
+    impl<const N: usize, T: NoHeap> NoHeap for [T; N] {}
+    impl<T: NoHeap> NoHeap for Option<T> {}
+    impl<T: NoHeap, E: NoHeap> NoHeap for Result<T, E> {}
+    impl<B: ToOwned<Owned: NoHeap> + ?Sized> NoHeap for Cow<'_, B> {}
+    impl<T: NoHeap + Copy> NoHeap for Cell<T> {}
+    impl<T: NoHeap + ?Sized> NoHeap for RefCell<T> {}
+
+    impl<T: ?Sized> HeapSize for PhantomData<T> {
+        const HAS_HEAP: bool = false;
+
+        fn heap_size_with(&self, _: &mut HeapSizeContext) -> usize {
+            0
+        }
+    }
+
+    macro_rules! tuples {
+        ($(($($t:ident),+)),*) => {$(
+            #[allow(non_snake_case)]
+            impl<$($t: HeapSize),+> HeapSize for ($($t,)+) {
+                const HAS_HEAP: bool = $($t::HAS_HEAP)||+;
+
+                fn heap_size_with(&self, cx: &mut HeapSizeContext) -> usize {
+                    let ($($t,)+) = self;
+                    0 $(+ $t.heap_size_with(cx))+
+                }
+            }
+
+            impl<$($t: NoHeap),+> NoHeap for ($($t,)+) {}
+        )*};
+    }
+
+    tuples!(
+        (A),
+        (A, B),
+        (A, B, C),
+        (A, B, C, D),
+        (A, B, C, D, E),
+        (A, B, C, D, E, F),
+        (A, B, C, D, E, F, G),
+        (A, B, C, D, E, F, G, H),
+        (A, B, C, D, E, F, G, H, I),
+        (A, B, C, D, E, F, G, H, I, J),
+        (A, B, C, D, E, F, G, H, I, J, K),
+        (A, B, C, D, E, F, G, H, I, J, K, L)
     );
 
+    // sum of the heap usage of all elements
+    fn elements<'a, T: HeapSize + 'a>(
+        iter: impl IntoIterator<Item = &'a T>,
+        cx: &mut HeapSizeContext,
+    ) -> usize {
+        if T::HAS_HEAP {
+            iter.into_iter()
+                .map(|t| t.heap_size_with(cx))
+                .sum::<usize>()
+        } else {
+            0
+        }
+    }
+
     impl<T: HeapSize> HeapSize for [T] {
         const HAS_HEAP: bool = T::HAS_HEAP;
 
-        fn heap_size(&self) -> usize {
-            if T::HAS_HEAP {
-                self.iter().map(T::heap_size).sum::<usize>()
-            } else {
-                0
-            }
+        fn heap_size_with(&self, cx: &mut HeapSizeContext) -> usize {
+            elements(self, cx)
         }
     }
 
     impl<T: HeapSize + ?Sized> HeapSize for &T {
         const HAS_HEAP: bool = T::HAS_HEAP;
 
-        fn heap_size(&self) -> usize {
-            T::heap_size(self)
+        fn heap_size_with(&self, cx: &mut HeapSizeContext) -> usize {
+            T::heap_size_with(self, cx)
+        }
+
+        fn heap_report_with(&self, cx: &mut HeapSizeContext) -> HeapReport {
+            T::heap_report_with(self, cx)
+        }
+    }
+
+    impl<T: HeapSize + ?Sized> HeapSize for &mut T {
+        const HAS_HEAP: bool = T::HAS_HEAP;
+
+        fn heap_size_with(&self, cx: &mut HeapSizeContext) -> usize {
+            T::heap_size_with(self, cx)
+        }
+
+        fn heap_report_with(&self, cx: &mut HeapSizeContext) -> HeapReport {
+            T::heap_report_with(self, cx)
+        }
+    }
+
+    impl<T: HeapSize> HeapSize for Option<T> {
+        const HAS_HEAP: bool = T::HAS_HEAP;
+
+        fn heap_size_with(&self, cx: &mut HeapSizeContext) -> usize {
+            self.as_ref().map_or(0, |t| t.heap_size_with(cx))
+        }
+
+        fn heap_report_with(&self, cx: &mut HeapSizeContext) -> HeapReport {
+            match self {
+                Some(t) => t.heap_report_with(cx),
+                None => HeapReport::new("", 0),
+            }
+        }
+    }
+
+    impl<T: HeapSize, E: HeapSize> HeapSize for Result<T, E> {
+        const HAS_HEAP: bool = T::HAS_HEAP || E::HAS_HEAP;
+
+        fn heap_size_with(&self, cx: &mut HeapSizeContext) -> usize {
+            match self {
+                Ok(t) => t.heap_size_with(cx),
+                Err(e) => e.heap_size_with(cx),
+            }
+        }
+    }
+
+    impl<B: ToOwned<Owned: HeapSize> + ?Sized> HeapSize for Cow<'_, B> {
+        const HAS_HEAP: bool = B::Owned::HAS_HEAP;
+
+        fn heap_size_with(&self, cx: &mut HeapSizeContext) -> usize {
+            match self {
+                Cow::Borrowed(_) => 0,
+                Cow::Owned(owned) => owned.heap_size_with(cx),
+            }
+        }
+    }
+
+    impl HeapSize for String {
+        const HAS_HEAP: bool = true;
+
+        fn heap_size_with(&self, cx: &mut HeapSizeContext) -> usize {
+            cx.allocation(self.capacity())
         }
     }
 
     impl<T: HeapSize> HeapSize for Vec<T> {
         const HAS_HEAP: bool = true;
 
-        fn heap_size(&self) -> usize {
-            let direct = self.capacity() * size_of::<T>();
-            if T::HAS_HEAP {
-                let indirect = self.iter().map(T::heap_size).sum::<usize>();
-                direct + indirect
-            } else {
-                direct
-            }
+        fn heap_size_with(&self, cx: &mut HeapSizeContext) -> usize {
+            cx.allocation(self.capacity() * size_of::<T>()) + elements(self, cx)
+        }
+    }
+
+    impl<T: HeapSize> HeapSize for VecDeque<T> {
+        const HAS_HEAP: bool = true;
+
+        fn heap_size_with(&self, cx: &mut HeapSizeContext) -> usize {
+            cx.allocation(self.capacity() * size_of::<T>()) + elements(self, cx)
+        }
+    }
+
+    impl<T: HeapSize> HeapSize for BinaryHeap<T> {
+        const HAS_HEAP: bool = true;
+
+        fn heap_size_with(&self, cx: &mut HeapSizeContext) -> usize {
+            cx.allocation(self.capacity() * size_of::<T>()) + elements(self, cx)
+        }
+    }
+
+    // every node holds the element and the two links
+    impl<T: HeapSize> HeapSize for LinkedList<T> {
+        const HAS_HEAP: bool = true;
+
+        fn heap_size_with(&self, cx: &mut HeapSizeContext) -> usize {
+            let node = cx.allocation(size_of::<(T, usize, usize)>());
+            self.len() * node + elements(self, cx)
         }
     }
 
     impl<T: HeapSize + ?Sized> HeapSize for Box<T> {
         const HAS_HEAP: bool = true;
 
-        fn heap_size(&self) -> usize {
-            let direct = size_of_val::<T>(self);
-            if T::HAS_HEAP {
-                direct + T::heap_size(self)
-            } else {
-                direct
+        fn heap_size_with(&self, cx: &mut HeapSizeContext) -> usize {
+            cx.allocation(size_of_val::<T>(self)) + T::heap_size_with(self, cx)
+        }
+
+        // the box itself is accounted to the parent, its parts are those of the value
+        fn heap_report_with(&self, cx: &mut HeapSizeContext) -> HeapReport {
+            let report = T::heap_report_with(self, cx);
+            HeapReport {
+                size: cx.allocation(size_of_val::<T>(self)) + report.size,
+                ..report
             }
         }
     }
 
+    impl<T: HeapSize + Copy> HeapSize for Cell<T> {
+        const HAS_HEAP: bool = T::HAS_HEAP;
+
+        fn heap_size_with(&self, cx: &mut HeapSizeContext) -> usize {
+            self.get().heap_size_with(cx)
+        }
+    }
+
+    // values that are mutably borrowed while measuring are not counted
+    impl<T: HeapSize + ?Sized> HeapSize for RefCell<T> {
+        const HAS_HEAP: bool = T::HAS_HEAP;
+
+        fn heap_size_with(&self, cx: &mut HeapSizeContext) -> usize {
+            self.try_borrow().map_or(0, |t| t.heap_size_with(cx))
+        }
+    }
+
+    // `Rc` and `Arc` allocate the strong and weak counters next to the value
+    fn counted<T: ?Sized>(value: &T, cx: &HeapSizeContext) -> usize {
+        let counters = Layout::new::<[usize; 2]>();
+        let (layout, _) = counters.extend(Layout::for_value(value)).unwrap();
+        cx.allocation(layout.pad_to_align().size())
+    }
+
+    impl<T: HeapSize + ?Sized> HeapSize for Rc<T> {
+        const HAS_HEAP: bool = true;
+
+        fn heap_size_with(&self, cx: &mut HeapSizeContext) -> usize {
+            let ptr = Rc::as_ptr(self) as *const ();
+            cx.shared(ptr, Rc::strong_count(self), |cx| {
+                counted::<T>(self, cx) + T::heap_size_with(self, cx)
+            })
+        }
+    }
+
+    impl<T: HeapSize + ?Sized> HeapSize for Arc<T> {
+        const HAS_HEAP: bool = true;
+
+        fn heap_size_with(&self, cx: &mut HeapSizeContext) -> usize {
+            let ptr = Arc::as_ptr(self) as *const ();
+            cx.shared(ptr, Arc::strong_count(self), |cx| {
+                counted::<T>(self, cx) + T::heap_size_with(self, cx)
+            })
+        }
+    }
+
+    // hashbrown's swiss table: one allocation holding the buckets, followed by a control byte per
+    // bucket and a trailing group of control bytes for unaligned probing.
+    const GROUP_WIDTH: usize = if cfg!(all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        target_feature = "sse2"
+    )) {
+        16
+    } else {
+        8
+    };
+
+    fn hash_table<T>(capacity: usize, cx: &HeapSizeContext) -> usize {
+        if capacity == 0 {
+            return 0;
+        }
+        // inverse of the load factor of 7/8, small tables are allowed to fill up all but one bucket
+        let buckets = if capacity < 8 {
+            capacity + 1
+        } else {
+            capacity / 7 * 8
+        };
+        let align = align_of::<T>().max(GROUP_WIDTH);
+        cx.allocation((buckets * size_of::<T>()).next_multiple_of(align) + buckets + GROUP_WIDTH)
+    }
+
+    impl<K: HeapSize, V: HeapSize, S> HeapSize for HashMap<K, V, S> {
+        const HAS_HEAP: bool = true;
+
+        fn heap_size_with(&self, cx: &mut HeapSizeContext) -> usize {
+            hash_table::<(K, V)>(self.capacity(), cx)
+                + elements(self.keys(), cx)
+                + elements(self.values(), cx)
+        }
+    }
+
+    impl<T: HeapSize, S> HeapSize for HashSet<T, S> {
+        const HAS_HEAP: bool = true;
+
+        fn heap_size_with(&self, cx: &mut HeapSizeContext) -> usize {
+            hash_table::<T>(self.capacity(), cx) + elements(self, cx)
+        }
+    }
+
+    // B-trees store up to 11 entries per node, internal nodes additionally point to 12 children.
+    // The exact shape of the tree depends on the insertion order, this assumes full nodes.
+    const BTREE_CAPACITY: usize = 11;
+
+    fn btree<K, V>(len: usize, cx: &HeapSizeContext) -> usize {
+        if len == 0 {
+            return 0;
+        }
+        // parent pointer, index in the parent, length, keys and values
+        let leaf = size_of::<(usize, u16, u16, [K; BTREE_CAPACITY], [V; BTREE_CAPACITY])>();
+        let internal = cx.allocation(leaf + size_of::<[usize; BTREE_CAPACITY + 1]>());
+        let leaf = cx.allocation(leaf);
+
+        let mut nodes = len.div_ceil(BTREE_CAPACITY);
+        let mut size = nodes * leaf;
+        while nodes > 1 {
+            nodes = nodes.div_ceil(BTREE_CAPACITY + 1);
+            size += nodes * internal;
+        }
+        size
+    }
+
+    impl<K: HeapSize, V: HeapSize> HeapSize for BTreeMap<K, V> {
+        const HAS_HEAP: bool = true;
+
+        fn heap_size_with(&self, cx: &mut HeapSizeContext) -> usize {
+            btree::<K, V>(self.len(), cx) + elements(self.keys(), cx) + elements(self.values(), cx)
+        }
+    }
+
+    impl<T: HeapSize> HeapSize for BTreeSet<T> {
+        const HAS_HEAP: bool = true;
+
+        fn heap_size_with(&self, cx: &mut HeapSizeContext) -> usize {
+            btree::<T, ()>(self.len(), cx) + elements(self, cx)
+        }
+    }
+
     impl<const N: usize, T: HeapSize> HeapSize for [T; N] {
         const HAS_HEAP: bool = T::HAS_HEAP;
 
-        fn heap_size(&self) -> usize {
-            if T::HAS_HEAP {
-                self.iter().map(T::heap_size).sum::<usize>()
-            } else {
-                0
-            }
+        fn heap_size_with(&self, cx: &mut HeapSizeContext) -> usize {
+            elements(self, cx)
         }
     }
 }
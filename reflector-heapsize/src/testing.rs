@@ -0,0 +1,96 @@
+//! Support for verifying [`HeapSize`](crate::HeapSize) estimates against actual allocations.
+//!
+//! ```
+//! use std::alloc::System;
+//!
+//! use reflector_heapsize::{HeapSize, SizeClasses, testing::CountingAllocator};
+//!
+//! #[global_allocator]
+//! static ALLOCATOR: CountingAllocator = CountingAllocator::new(System);
+//!
+//! fn main() {
+//!     let (value, allocated) = <CountingAllocator>::measure(SizeClasses::Exact, || vec![0u8; 100]);
+//!     assert_eq!(value.heap_size() as isize, allocated);
+//! }
+//! ```
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+
+use crate::SizeClasses;
+
+/// A global allocator which counts the bytes allocated by the current thread.
+///
+/// Only allocations made inside [`CountingAllocator::measure`] are counted, rounded to the
+/// given size classes.
+pub struct CountingAllocator<A = System>(A);
+
+thread_local! {
+    // `None` if the current thread doesn't measure
+    static COUNTER: Cell<Option<(SizeClasses, isize)>> = const { Cell::new(None) };
+}
+
+impl<A> CountingAllocator<A> {
+    pub const fn new(allocator: A) -> Self {
+        Self(allocator)
+    }
+}
+
+impl<A: GlobalAlloc> CountingAllocator<A> {
+    /// Runs `f`, returning its result and the bytes it allocated and didn't free.
+    ///
+    /// Returns `0` bytes if no `CountingAllocator` is the global allocator. `A` is the wrapped
+    /// allocator, `<CountingAllocator>::measure` refers to the default.
+    pub fn measure<R>(size_classes: SizeClasses, f: impl FnOnce() -> R) -> (R, isize) {
+        let outer = COUNTER.replace(Some((size_classes, 0)));
+        let result = f();
+        let (_, allocated) = COUNTER.replace(outer).unwrap();
+        (result, allocated)
+    }
+}
+
+fn count(layout: Layout, sign: isize) {
+    let _ = COUNTER.try_with(|counter| {
+        if let Some((size_classes, allocated)) = counter.get() {
+            let size = size_classes.round(layout.size()) as isize;
+            counter.set(Some((size_classes, allocated + sign * size)));
+        }
+    });
+}
+
+unsafe impl<A: GlobalAlloc> GlobalAlloc for CountingAllocator<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { self.0.alloc(layout) };
+        if !ptr.is_null() {
+            count(layout, 1);
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { self.0.alloc_zeroed(layout) };
+        if !ptr.is_null() {
+            count(layout, 1);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { self.0.dealloc(ptr, layout) };
+        count(layout, -1);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new = unsafe { self.0.realloc(ptr, layout, new_size) };
+        if !new.is_null() {
+            count(layout, -1);
+            count(
+                Layout::from_size_align(new_size, layout.align()).unwrap(),
+                1,
+            );
+        }
+        new
+    }
+}
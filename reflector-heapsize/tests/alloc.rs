@@ -0,0 +1,112 @@
+use std::{
+    alloc::System,
+    collections::{BTreeMap, HashMap, HashSet, LinkedList, VecDeque},
+    rc::Rc,
+    sync::Arc,
+};
+
+use reflector::Introspect;
+use reflector_heapsize::{
+    HeapSize, HeapSizeContext, Reflect, SizeClasses, testing::CountingAllocator,
+};
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator::new(System);
+
+#[derive(Introspect)]
+#[reflect(impl(reflector_heapsize::HeapSize))]
+pub struct Entry {
+    key: String,
+    values: Vec<u32>,
+}
+
+#[derive(Introspect)]
+pub struct Everything {
+    string: String,
+    vec: Vec<Entry>,
+    boxed: Box<[u16]>,
+    deque: VecDeque<u64>,
+    list: LinkedList<Entry>,
+    map: HashMap<u32, String>,
+    set: HashSet<u8>,
+    btree: BTreeMap<String, u8>,
+    rc: Rc<Entry>,
+    arc: Arc<str>,
+    option: Option<Box<Entry>>,
+}
+
+fn entry(key: &str, n: u32) -> Entry {
+    Entry {
+        key: key.to_string(),
+        values: (0..n).collect(),
+    }
+}
+
+fn everything() -> Everything {
+    Everything {
+        string: String::from("hello world"),
+        vec: vec![entry("a", 3), entry("bcd", 100)],
+        boxed: vec![1; 33].into_boxed_slice(),
+        deque: (0..20).collect(),
+        list: [entry("x", 1), entry("", 0)].into_iter().collect(),
+        map: (0..50).map(|i| (i, i.to_string())).collect(),
+        set: (0..3).collect(),
+        btree: (0..5).map(|i| (format!("key {i}"), i)).collect(),
+        rc: Rc::new(entry("rc", 7)),
+        arc: Arc::from("shared"),
+        option: Some(Box::new(entry("boxed", 2))),
+    }
+}
+
+fn check(size_classes: SizeClasses) {
+    let (value, allocated) = <CountingAllocator>::measure(size_classes, everything);
+    let cx = &mut HeapSizeContext::default().with_size_classes(size_classes);
+    assert_eq!(Reflect(&value).heap_size_with(cx) as isize, allocated);
+}
+
+#[test]
+fn exact() {
+    check(SizeClasses::Exact);
+}
+
+#[test]
+fn jemalloc() {
+    check(SizeClasses::Jemalloc);
+}
+
+#[test]
+fn mimalloc() {
+    check(SizeClasses::Mimalloc);
+}
+
+#[test]
+fn table() {
+    check(SizeClasses::Table(&[16, 64, 256, 1024]));
+}
+
+#[test]
+fn size_classes() {
+    let jemalloc = [1, 8, 9, 100, 129, 200, 257, 1000, 5000, 20000, 70000];
+    let rounded = jemalloc.map(|size| SizeClasses::Jemalloc.round(size));
+    assert_eq!(
+        rounded,
+        [8, 8, 16, 112, 160, 224, 320, 1024, 5120, 20480, 81920]
+    );
+
+    let mimalloc = [1, 9, 64, 65, 100, 129, 1000, 200_000];
+    let rounded = mimalloc.map(|size| SizeClasses::Mimalloc.round(size));
+    assert_eq!(rounded, [8, 16, 64, 80, 112, 160, 1024, 200_704]);
+
+    assert_eq!(SizeClasses::Exact.round(13), 13);
+    assert_eq!(SizeClasses::Table(&[32]).round(33), 4096);
+    assert_eq!(SizeClasses::Jemalloc.round(0), 0);
+}
+
+#[test]
+fn freed() {
+    let (_, allocated) = <CountingAllocator>::measure(SizeClasses::Exact, || {
+        let vec = vec![0u8; 1000];
+        drop(vec);
+    });
+    assert_eq!(allocated, 0);
+}
@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+
+use reflector::Introspect;
+use reflector_heapsize::{HeapReport, HeapSize, Reflect};
+
+#[derive(Introspect)]
+#[reflect(impl(reflector_heapsize::HeapSize))]
+pub struct Server {
+    host: String,
+    port: u16,
+}
+
+#[derive(Introspect)]
+#[reflect(impl(reflector_heapsize::HeapSize))]
+pub enum Backend {
+    Memory,
+    Remote(Server, Vec<u8>),
+}
+
+#[derive(Introspect)]
+pub struct Config {
+    name: String,
+    primary: Box<Server>,
+    backend: Backend,
+    fallback: Option<Backend>,
+    limits: HashMap<u32, u32>,
+}
+
+fn config() -> Config {
+    Config {
+        name: String::from("config"),
+        primary: Box::new(Server {
+            host: String::from("localhost"),
+            port: 80,
+        }),
+        backend: Backend::Remote(
+            Server {
+                host: String::from("example.com"),
+                port: 443,
+            },
+            vec![0; 100],
+        ),
+        fallback: Some(Backend::Memory),
+        limits: HashMap::new(),
+    }
+}
+
+#[test]
+fn tree() {
+    let config = config();
+    let report = Reflect(&config).heap_report();
+    assert_eq!(report.size, Reflect(&config).heap_size());
+    assert_eq!(report.name, "Config");
+
+    let names = report.children.iter().map(|c| &*c.name).collect::<Vec<_>>();
+    assert_eq!(names, ["name", "primary", "backend", "fallback", "limits"]);
+
+    let primary = report.get(&["primary"]).unwrap();
+    assert_eq!(primary.size, size_of::<Server>() + 9);
+    assert_eq!(report.get(&["primary", "host"]).unwrap().size, 9);
+
+    let remote = report.get(&["backend", "Remote"]).unwrap();
+    assert_eq!(remote.size, 111);
+    assert_eq!(
+        report
+            .get(&["backend", "Remote", "0", "host"])
+            .unwrap()
+            .size,
+        11
+    );
+    assert_eq!(report.get(&["backend", "Remote", "1"]).unwrap().size, 100);
+
+    let fallback = report.get(&["fallback", "Memory"]).unwrap();
+    assert_eq!(fallback.size, 0);
+    assert!(fallback.children.is_empty());
+}
+
+#[test]
+fn table() {
+    let report = Reflect(&config()).heap_report();
+    let expected = format!(
+        "\
+Config        {total}
+  name          6
+  primary      {primary}
+    host        9
+  backend     111
+    Remote    111
+      0        11
+        host   11
+      1       100
+",
+        total = report.size,
+        primary = size_of::<Server>() + 9,
+    );
+    assert_eq!(report.to_string(), expected);
+
+    let all = format!("{report:#}");
+    assert!(all.contains("\n        port    0\n"));
+    assert!(all.contains("\n  fallback      0\n    Memory      0\n"));
+}
+
+#[test]
+fn json() {
+    let mut report = HeapReport::new("Root", 3);
+    report.children.push(HeapReport::new("a \"quoted\"", 3));
+    report.children.push(HeapReport::new("0", 0));
+    assert_eq!(
+        report.to_json(),
+        r#"{"name":"Root","size":3,"children":[{"name":"a \"quoted\"","size":3,"children":[]},{"name":"0","size":0,"children":[]}]}"#
+    );
+}
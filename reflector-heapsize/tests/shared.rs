@@ -0,0 +1,70 @@
+use std::{cell::RefCell, rc::Rc, sync::Arc};
+
+use reflector::Introspect;
+use reflector_heapsize::{HeapSize, HeapSizeContext, Reflect, SharedPolicy};
+
+fn heap_size(value: &impl HeapSize, policy: SharedPolicy) -> usize {
+    value.heap_size_with(&mut HeapSizeContext::new(policy))
+}
+
+#[test]
+fn shared() {
+    // 16 bytes of counters, 24 of `Vec` and 100 on the heap
+    let shared = Rc::new(vec![0u8; 100]);
+    let pointers = vec![shared.clone(), shared.clone(), shared.clone(), shared];
+    let vec = 4 * size_of::<Rc<Vec<u8>>>();
+
+    assert_eq!(heap_size(&pointers, SharedPolicy::CountOnce), vec + 140);
+    assert_eq!(heap_size(&pointers, SharedPolicy::Proportional), vec + 140);
+    assert_eq!(heap_size(&pointers, SharedPolicy::Skip), vec);
+    assert_eq!(pointers.heap_size(), vec + 140);
+
+    // only two of the three owners are reachable
+    let shared = Arc::new(String::from("abcd"));
+    let pointers = (shared.clone(), shared.clone());
+    assert_eq!(heap_size(&pointers, SharedPolicy::CountOnce), 44);
+    assert_eq!(heap_size(&pointers, SharedPolicy::Proportional), 28);
+    assert_eq!(heap_size(&pointers, SharedPolicy::Skip), 0);
+    drop(shared);
+    assert_eq!(heap_size(&pointers, SharedPolicy::Proportional), 44);
+
+    let unique = Rc::new(1u64);
+    assert_eq!(heap_size(&unique, SharedPolicy::Skip), 24);
+}
+
+#[test]
+fn cycle() {
+    #[derive(Introspect)]
+    pub struct Node {
+        data: Vec<u8>,
+        next: RefCell<Option<Rc<Node>>>,
+    }
+
+    impl HeapSize for Node {
+        const HAS_HEAP: bool = true;
+
+        fn heap_size_with(&self, cx: &mut HeapSizeContext) -> usize {
+            Reflect(self).heap_size_with(cx)
+        }
+    }
+
+    let a = Rc::new(Node {
+        data: vec![0; 10],
+        next: RefCell::new(None),
+    });
+    let b = Rc::new(Node {
+        data: vec![0; 20],
+        next: RefCell::new(Some(a.clone())),
+    });
+    *a.next.borrow_mut() = Some(b.clone());
+
+    let node = 16 + size_of::<Node>();
+    assert_eq!(heap_size(&a, SharedPolicy::CountOnce), 2 * node + 30);
+    // `a` is one of two owners of the first node, which owns half of the second one
+    let proportional = (node + 10 + (node + 20) / 2) / 2;
+    assert_eq!(heap_size(&a, SharedPolicy::Proportional), proportional);
+    assert_eq!(heap_size(&a, SharedPolicy::Skip), 0);
+
+    // break the cycle, so the nodes are freed
+    a.next.take();
+}
@@ -1,5 +1,5 @@
 use reflector::Introspect;
-use reflector_heapsize::{HeapSize, Reflect};
+use reflector_heapsize::{HeapSize, HeapSizeContext, Reflect};
 
 #[test]
 fn asdf() {
@@ -33,8 +33,8 @@ fn tuple_struct() {
     impl HeapSize for MyStruct {
         const HAS_HEAP: bool = Reflect::<Self>::HAS_HEAP;
 
-        fn heap_size(&self) -> usize {
-            Reflect(self).heap_size()
+        fn heap_size_with(&self, cx: &mut HeapSizeContext) -> usize {
+            Reflect(self).heap_size_with(cx)
         }
     }
 
@@ -105,3 +105,132 @@ fn gigantic() {
         f90: u8, f91: u8, f92: u8, f93: u8, f94: u8, f95: u8, f96: u8, f97: u8, f98: u8, f99: u8, 
     }
 }
+
+#[test]
+fn nested() {
+    #[derive(Introspect)]
+    #[reflect(impl(reflector_heapsize::HeapSize))]
+    pub struct Inner {
+        name: String,
+    }
+
+    #[derive(Introspect)]
+    #[reflect(impl(reflector_heapsize::HeapSize))]
+    pub enum Tree<T> {
+        Leaf(T),
+        Node(Vec<Tree<T>>),
+    }
+
+    #[derive(Introspect)]
+    pub struct Outer {
+        many: Vec<Inner>,
+        boxed: Box<Inner>,
+        tree: Tree<Inner>,
+    }
+
+    let inner = |name: &str| Inner { name: name.into() };
+    let outer = Outer {
+        many: vec![inner("a"), inner("bc")],
+        boxed: Box::new(inner("def")),
+        tree: Tree::Node(vec![Tree::Leaf(inner("g")), Tree::Node(vec![])]),
+    };
+
+    let many = 2 * size_of::<Inner>() + 3;
+    let boxed = size_of::<Inner>() + 3;
+    let tree = 2 * size_of::<Tree<Inner>>() + 1;
+    assert_eq!(Reflect(&outer).heap_size(), many + boxed + tree);
+    assert_eq!(outer.boxed.heap_size(), size_of::<Inner>() + 3);
+    assert_eq!(Tree::Leaf(0u8).heap_size(), 0);
+
+    let report = Reflect(&outer).heap_report();
+    let boxed = report.get(&["boxed", "name"]).unwrap();
+    assert_eq!(boxed.size, 3);
+}
+
+#[test]
+fn deep_and_shallow() {
+    #[derive(Introspect)]
+    #[reflect(impl(reflector_heapsize::HeapSize))]
+    pub struct Point {
+        x: u32,
+        y: u32,
+        label: String,
+    }
+
+    let point = Point {
+        x: 1,
+        y: 2,
+        label: String::from("origin"),
+    };
+    assert_eq!(point.shallow_size(), size_of::<Point>());
+    assert_eq!(point.deep_size(), size_of::<Point>() + 6);
+    assert_eq!(Reflect(&point).shallow_size(), size_of::<Point>());
+    assert_eq!(Reflect(&point).deep_size(), point.deep_size());
+    assert_eq!(Reflect(&point).variant_usage(), None);
+
+    let points = vec![point];
+    assert_eq!(
+        points.deep_size(),
+        size_of::<Vec<Point>>() + size_of::<Point>() + 6
+    );
+    assert_eq!("abc".deep_size(), 3);
+}
+
+#[test]
+fn variant_usage() {
+    #[derive(Introspect)]
+    pub enum Message {
+        Quit,
+        Move { x: i32, y: i32 },
+        Write(String),
+    }
+
+    let usage = Reflect(&Message::Quit).variant_usage().unwrap();
+    assert_eq!(usage.variant, "Quit");
+    assert_eq!(usage.used, 0);
+    assert_eq!(usage.largest, size_of::<String>());
+    assert_eq!(usage.size, size_of::<Message>());
+    assert_eq!(usage.wasted(), size_of::<String>());
+
+    let usage = Reflect(&Message::Move { x: 0, y: 0 })
+        .variant_usage()
+        .unwrap();
+    assert_eq!((usage.variant, usage.used), ("Move", 8));
+    assert_eq!(usage.wasted(), size_of::<String>() - 8);
+
+    let usage = Reflect(&Message::Write(String::new()))
+        .variant_usage()
+        .unwrap();
+    assert_eq!(usage.wasted(), 0);
+    assert_eq!(usage.overhead(), size_of::<Message>() - size_of::<String>());
+}
+
+#[test]
+fn no_heap() {
+    use reflector_heapsize::{NoHeap, assert_no_heap};
+
+    #[derive(Clone, Copy, Default, Introspect)]
+    #[reflect(impl(reflector_heapsize::HeapSize))]
+    pub struct Frame {
+        left: f32,
+        right: f32,
+    }
+
+    #[derive(Introspect)]
+    #[reflect(impl(reflector_heapsize::HeapSize))]
+    pub enum Command {
+        Gain(f32),
+        Mute,
+        Frames([Frame; 4]),
+    }
+
+    assert_no_heap!(Frame);
+    assert_no_heap!(Command);
+
+    fn realtime<T: NoHeap>(command: T) -> T {
+        command
+    }
+    realtime(Command::Mute);
+    realtime(Some((Command::Frames([Frame::default(); 4]), 'x')));
+    realtime(Reflect(&Command::Gain(0.5)));
+}
@@ -0,0 +1,126 @@
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet, LinkedList, VecDeque},
+    rc::Rc,
+    sync::Arc,
+};
+
+use reflector::Introspect;
+use reflector_heapsize::{HeapSize, Reflect};
+
+#[test]
+fn primitives() {
+    assert_eq!(true.heap_size(), 0);
+    assert_eq!('x'.heap_size(), 0);
+    assert_eq!(0usize.heap_size(), 0);
+    assert_eq!(().heap_size(), 0);
+    const { assert!(!<(bool, char, u8)>::HAS_HEAP) };
+}
+
+#[test]
+fn string() {
+    let mut s = String::with_capacity(100);
+    s.push_str("hello");
+    assert_eq!(s.heap_size(), 100);
+    assert_eq!(String::new().heap_size(), 0);
+    assert_eq!(Cow::Borrowed("hello").heap_size(), 0);
+    assert_eq!(Cow::<str>::Owned(s).heap_size(), 100);
+}
+
+#[test]
+fn option_result_tuple() {
+    assert_eq!(None::<Vec<u8>>.heap_size(), 0);
+    assert_eq!(Some(vec![0u8; 8]).heap_size(), 8);
+    assert_eq!(Ok::<_, String>(vec![0u16; 8]).heap_size(), 16);
+    assert_eq!(Err::<u8, _>(String::from("abc")).heap_size(), 3);
+    assert_eq!((vec![0u8; 2], 1u32, String::from("abcd")).heap_size(), 6);
+    const { assert!(!<Option<u32>>::HAS_HEAP) };
+}
+
+#[test]
+fn sequences() {
+    let deque = VecDeque::<u32>::with_capacity(10);
+    assert_eq!(deque.heap_size(), deque.capacity() * 4);
+
+    let heap = BinaryHeap::from(vec![1u64, 2, 3]);
+    assert_eq!(heap.heap_size(), 24);
+
+    let list = LinkedList::from([String::from("ab"), String::from("c")]);
+    assert_eq!(list.heap_size(), 2 * (size_of::<String>() + 16) + 3);
+}
+
+#[test]
+fn rc_and_arc() {
+    // two counters, the value and padding
+    assert_eq!(Rc::new(5u32).heap_size(), 24);
+    assert_eq!(Arc::new(5u32).heap_size(), 24);
+    assert_eq!(Rc::<str>::from("abc").heap_size(), 24);
+    assert_eq!(Arc::new(String::from("abc")).heap_size(), 16 + 24 + 3);
+    assert_eq!(Rc::<[u64]>::from([1, 2, 3]).heap_size(), 16 + 24);
+}
+
+#[test]
+fn hash_map() {
+    assert_eq!(HashMap::<u64, u64>::new().heap_size(), 0);
+
+    let mut map = HashMap::<u64, u64>::with_capacity(3);
+    map.insert(1, 1);
+    assert_eq!(map.capacity(), 3);
+    let group = if cfg!(target_arch = "x86_64") { 16 } else { 8 };
+    // 4 buckets of 16 bytes, 4 control bytes and a trailing group
+    assert_eq!(map.heap_size(), 4 * 16 + 4 + group);
+
+    let mut map = HashMap::<u8, String>::with_capacity(100);
+    map.insert(1, String::from("abc"));
+    // 128 buckets at a load factor of 7/8
+    assert_eq!(map.capacity(), 112);
+    let buckets = 128 * size_of::<(u8, String)>();
+    assert_eq!(map.heap_size(), buckets + 128 + group + 3);
+
+    let set = HashSet::<u32>::with_capacity(7);
+    assert_eq!(set.capacity(), 7);
+    assert_eq!(set.heap_size(), 32 + 8 + group);
+}
+
+#[test]
+fn btree_map() {
+    assert_eq!(BTreeMap::<u32, u32>::new().heap_size(), 0);
+
+    let map = BTreeMap::from([(1u32, 1u32), (2, 2), (3, 3)]);
+    // a single leaf: parent, index, length and 11 keys and values
+    assert_eq!(map.heap_size(), 104);
+
+    let set = BTreeSet::from([String::from("a"), String::from("bc")]);
+    assert_eq!(set.heap_size(), 16 + 11 * size_of::<String>() + 3);
+
+    // 12 full leaves need a parent
+    let map = (0..132u64).map(|i| (i, ())).collect::<BTreeMap<_, _>>();
+    let leaf = 8 + 8 + 11 * 8;
+    assert_eq!(map.heap_size(), 12 * leaf + leaf + 12 * 8);
+}
+
+#[test]
+fn in_struct() {
+    #[derive(Introspect)]
+    pub struct Config {
+        name: String,
+        enabled: bool,
+        separator: char,
+        tags: Option<Vec<String>>,
+        limits: HashMap<String, (u32, u32)>,
+        shared: Arc<str>,
+    }
+
+    let config = Config {
+        name: String::from("config"),
+        enabled: true,
+        separator: ',',
+        tags: Some(vec![String::from("a")]),
+        limits: HashMap::new(),
+        shared: Arc::from("shared"),
+    };
+    assert_eq!(
+        Reflect(&config).heap_size(),
+        6 + size_of::<String>() + 1 + 24
+    );
+}
@@ -0,0 +1,723 @@
+//! Layered configuration for reflected structs, merged field by field from several sources.
+//!
+//! Every layer is a [`Partial`] of the struct, in which every field is optional. Its fields are
+//! derived from the reflected field list: fields whose type is marked
+//! `#[reflect(impl(reflector_merge::Merge))]` are partials of their own, other fields are replaced
+//! as a whole, and flattened fields are partials within their parent. Partials are deserialized from any serde format, e.g. a config file, or
+//! built from a complete value, e.g. the defaults.
+//!
+//! [`merge`] combines the layers in order of increasing priority, e.g. defaults, file, env and
+//! command line, where every field gets the value of the last layer setting it. Fields which no
+//! layer sets get their `#[reflect(default)]`, `Option` fields get `None`, and every other one is
+//! reported as missing. The [`Provenance`] of the result tells which layer supplied every value.
+
+use std::collections::HashMap;
+
+pub use merged::{Error, Merged, Provenance, Source};
+pub use reflect::{Partial, ReflectMerge};
+
+/// Merges the layers into a complete value, in order of increasing priority.
+///
+/// Layers are named in the [`Provenance`] of the result, e.g. `"file"` or a path.
+pub fn merge<T, I, L>(layers: I) -> Result<Merged<T>, Error>
+where
+    T: ReflectMerge,
+    I: IntoIterator<Item = (L, Partial<T>)>,
+    L: Into<String>,
+{
+    let mut cx = Context::default();
+    let mut merged = Partial::<T>::default();
+    for (name, layer) in layers {
+        cx.layers.push(name.into());
+        T::merge(&mut merged, layer, &mut cx, "");
+    }
+    match T::complete(merged, &mut cx, "") {
+        Some(value) if cx.missing.is_empty() => Ok(Merged {
+            value,
+            provenance: Provenance(cx.provenance),
+        }),
+        _ => Err(Error {
+            missing: cx.missing,
+        }),
+    }
+}
+
+/// A type which is merged from layers through its partial form.
+///
+/// Most types are replaced as a whole, with `Option<Self>` as their partial, which
+/// [`Context::merge_value`] and [`Context::complete_value`] implement.
+pub trait Merge: Sized {
+    /// The value of a single layer, `Default` being the value of a layer which sets nothing
+    type Partial: Default;
+
+    /// A partial setting the whole value
+    fn to_partial(&self) -> Self::Partial;
+
+    /// Merges `layer` into `into`, replacing everything it sets
+    fn merge(into: &mut Self::Partial, layer: Self::Partial, cx: &mut Context, path: &str);
+
+    /// The value of the merged layers, or `None` if anything is missing, which is then added to
+    /// the context
+    fn complete(partial: Self::Partial, cx: &mut Context, path: &str) -> Option<Self>;
+
+    /// Whether no layer sets anything
+    fn is_unset(partial: &Self::Partial) -> bool;
+
+    /// The value when no layer sets it, like `None` for `Option`
+    fn unset() -> Option<Self> {
+        None
+    }
+}
+
+/// Implements [`Merge`] for a reflected struct through [`ReflectMerge`], with [`Partial`] as its
+/// partial form, with `#[reflect(impl(reflector_merge::Merge))]`. Type parameters have to
+/// implement `Merge`.
+#[macro_export]
+macro_rules! Merge {
+    (
+        lifetimes [$($lifetime:lifetime),*]
+        types [$($param:ident),*]
+        impl [$($params:tt)*] $ty:ty
+        where [$($where:tt)*]
+    ) => {
+        impl<$($params)*> $crate::Merge for $ty
+        where
+            $($where)*
+            $($param: $crate::Merge,)*
+        {
+            type Partial = $crate::Partial<Self>;
+
+            fn to_partial(&self) -> Self::Partial {
+                $crate::Partial::from(self)
+            }
+
+            fn merge(into: &mut Self::Partial, layer: Self::Partial, cx: &mut $crate::Context, path: &str) {
+                <Self as $crate::ReflectMerge>::merge(into, layer, cx, path)
+            }
+
+            fn complete(
+                partial: Self::Partial,
+                cx: &mut $crate::Context,
+                path: &str,
+            ) -> ::core::option::Option<Self> {
+                <Self as $crate::ReflectMerge>::complete(partial, cx, path)
+            }
+
+            fn is_unset(partial: &Self::Partial) -> bool {
+                partial.is_empty()
+            }
+        }
+    };
+}
+
+/// The state of [`merge`]: which layer set every value, and what is missing.
+///
+/// Paths are the field names from the root, joined by `.`, e.g. `server.port`.
+#[derive(Default)]
+pub struct Context {
+    layers: Vec<String>,
+    sources: HashMap<String, usize>,
+    provenance: Vec<(String, Source)>,
+    missing: Vec<String>,
+}
+
+impl Context {
+    /// Merges a value which is replaced as a whole
+    pub fn merge_value<T>(&mut self, into: &mut Option<T>, layer: Option<T>, path: &str) {
+        if let Some(value) = layer {
+            *into = Some(value);
+            self.sources.insert(path.to_owned(), self.layers.len() - 1);
+        }
+    }
+
+    /// Completes a value which is replaced as a whole, adding it to the missing values if unset
+    pub fn complete_value<T>(&mut self, partial: Option<T>, path: &str) -> Option<T> {
+        match partial {
+            Some(value) => {
+                let source = match self.sources.get(path) {
+                    Some(&layer) => Source::Layer(self.layers[layer].clone()),
+                    None => Source::Default,
+                };
+                self.provenance.push((path.to_owned(), source));
+                Some(value)
+            }
+            None => {
+                self.missing.push(path.to_owned());
+                None
+            }
+        }
+    }
+
+    /// Records that no layer set the value at `path`, which gets its default
+    pub fn default_value(&mut self, path: &str) {
+        self.provenance.push((path.to_owned(), Source::Default));
+    }
+}
+
+mod merged {
+    use std::fmt::{self, Display, Formatter};
+
+    /// The result of [`merge`](crate::merge).
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct Merged<T> {
+        pub value: T,
+        pub provenance: Provenance,
+    }
+
+    /// The source of every value, by path, in the order of the fields. Values within a partial of
+    /// their own have an entry for each of their fields, rather than for themselves.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct Provenance(pub Vec<(String, Source)>);
+
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub enum Source {
+        /// The layer of the given name
+        Layer(String),
+        /// None of the layers, the value is the default of the field
+        Default,
+    }
+
+    /// The values no layer set, which have no default.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct Error {
+        pub missing: Vec<String>,
+    }
+
+    impl Provenance {
+        /// The source of the value at `path`, e.g. `server.port`
+        pub fn source(&self, path: &str) -> Option<&Source> {
+            self.0
+                .iter()
+                .find(|(p, _)| p == path)
+                .map(|(_, source)| source)
+        }
+    }
+
+    impl Display for Provenance {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            for (path, source) in &self.0 {
+                writeln!(f, "{path}: {source}")?;
+            }
+            Ok(())
+        }
+    }
+
+    impl Display for Source {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            match self {
+                Source::Layer(name) => f.write_str(name),
+                Source::Default => f.write_str("default"),
+            }
+        }
+    }
+
+    impl Display for Error {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "missing values: {}", self.missing.join(", "))
+        }
+    }
+
+    impl std::error::Error for Error {}
+}
+
+mod reflect {
+    use std::fmt::{self, Formatter};
+    use std::marker::PhantomData;
+
+    use reflector::*;
+    use reflector_serde::with::DeserializeAs;
+    use serde::de::{DeserializeSeed, Error as _, MapAccess, Visitor};
+    use serde::{Deserialize, Deserializer};
+
+    use crate::{Context, Merge};
+
+    /// A single layer of `T`, in which every field is optional.
+    pub struct Partial<T: ReflectMerge>(<T::Fields as MergeFields<T>>::Partials);
+
+    /// Merging of reflected structs, implemented for any struct which can be introspected and
+    /// whose fields implement [`Merge`], or are flattened or have an adapter.
+    pub trait ReflectMerge: Sized + SizedStruct<Root = Self, Fields: MergeFields<Self>> {
+        fn merge(into: &mut Partial<Self>, layer: Partial<Self>, cx: &mut Context, path: &str) {
+            Self::Fields::merge(&mut into.0, layer.0, cx, path)
+        }
+
+        fn complete(partial: Partial<Self>, cx: &mut Context, path: &str) -> Option<Self>;
+    }
+
+    impl<T> ReflectMerge for T
+    where
+        T: SizedStruct<Root = T, Fields: MergeFields<T, Values = T::FieldTypes>>,
+    {
+        fn complete(partial: Partial<T>, cx: &mut Context, path: &str) -> Option<T> {
+            T::Fields::complete(partial.0, cx, path).map(T::from_values)
+        }
+    }
+
+    impl<T: ReflectMerge> Partial<T> {
+        /// Whether the layer sets nothing
+        pub fn is_empty(&self) -> bool {
+            T::Fields::is_unset(&self.0)
+        }
+    }
+
+    impl<T: ReflectMerge> Default for Partial<T> {
+        fn default() -> Self {
+            Self(Default::default())
+        }
+    }
+
+    /// A layer setting every field
+    impl<T: ReflectMerge> From<&T> for Partial<T> {
+        fn from(value: &T) -> Self {
+            Self(T::Fields::to_partials(value))
+        }
+    }
+
+    // helper trait implemented recursively for a list of fields, e.g `(Field0, (Field1, ()))`.
+    // `path` is the path of the parent.
+    pub trait MergeFields<Root> {
+        type Values;
+        type Partials: Default;
+
+        fn to_partials(value: &Root) -> Self::Partials;
+        fn merge(into: &mut Self::Partials, layer: Self::Partials, cx: &mut Context, path: &str);
+        fn complete(partials: Self::Partials, cx: &mut Context, path: &str)
+        -> Option<Self::Values>;
+        fn is_unset(partials: &Self::Partials) -> bool;
+    }
+    // end of the recursion
+    impl<Root> MergeFields<Root> for () {
+        type Values = ();
+        type Partials = ();
+
+        fn to_partials(_: &Root) {}
+        fn merge(_: &mut (), _: (), _: &mut Context, _: &str) {}
+
+        fn complete(_: (), _: &mut Context, _: &str) -> Option<()> {
+            Some(())
+        }
+
+        fn is_unset(_: &()) -> bool {
+            true
+        }
+    }
+    impl<Root, Head, Tail> MergeFields<Root> for Cons<Head, Tail>
+    where
+        Head: Field<Root = Root, Type: Sized> + MergeField<<Head as Field>::Repr>,
+        Tail: MergeFields<Root>,
+    {
+        type Values = Cons<Head::Type, Tail::Values>;
+        type Partials = Cons<Head::Partial, Tail::Partials>;
+
+        fn to_partials(value: &Root) -> Self::Partials {
+            let field = Head::try_get_ref(value).expect("fields of structs are always present");
+            Cons(Head::to_partial(field), Tail::to_partials(value))
+        }
+
+        fn merge(into: &mut Self::Partials, layer: Self::Partials, cx: &mut Context, path: &str) {
+            Head::merge(&mut into.0, layer.0, cx, path);
+            Tail::merge(&mut into.1, layer.1, cx, path);
+        }
+
+        fn complete(
+            partials: Self::Partials,
+            cx: &mut Context,
+            path: &str,
+        ) -> Option<Self::Values> {
+            // the tail is completed even if the head failed, to report everything missing
+            let head = Head::complete(partials.0, cx, path);
+            let tail = Tail::complete(partials.1, cx, path);
+            Some(Cons(head?, tail?))
+        }
+
+        fn is_unset(partials: &Self::Partials) -> bool {
+            Head::is_unset(&partials.0) && Tail::is_unset(&partials.1)
+        }
+    }
+
+    // helper trait implemented for every field, depending on its representation. `path` is the
+    // path of the parent.
+    pub trait MergeField<Repr>: Field<Type: Sized> {
+        type Partial: Default;
+
+        fn to_partial(value: &Self::Type) -> Self::Partial;
+        fn merge(into: &mut Self::Partial, layer: Self::Partial, cx: &mut Context, path: &str);
+        fn complete(partial: Self::Partial, cx: &mut Context, path: &str) -> Option<Self::Type>;
+        fn is_unset(partial: &Self::Partial) -> bool;
+    }
+
+    impl<F> MergeField<PlainRepr> for F
+    where
+        F: Field<Type: Merge>,
+    {
+        type Partial = <F::Type as Merge>::Partial;
+
+        fn to_partial(value: &F::Type) -> Self::Partial {
+            value.to_partial()
+        }
+
+        fn merge(into: &mut Self::Partial, layer: Self::Partial, cx: &mut Context, path: &str) {
+            F::Type::merge(into, layer, cx, &field_path::<F>(path))
+        }
+
+        fn complete(partial: Self::Partial, cx: &mut Context, path: &str) -> Option<F::Type> {
+            let path = field_path::<F>(path);
+            if F::Type::is_unset(&partial)
+                && let Some(default) = F::default_value().or_else(F::Type::unset)
+            {
+                cx.default_value(&path);
+                return Some(default);
+            }
+            F::Type::complete(partial, cx, &path)
+        }
+
+        fn is_unset(partial: &Self::Partial) -> bool {
+            F::Type::is_unset(partial)
+        }
+    }
+
+    // fields with an adapter are replaced as a whole, the adapter only deserializes them
+    impl<F, A> MergeField<WithRepr<A>> for F
+    where
+        F: Field<Type: Sized + Clone>,
+    {
+        type Partial = Option<F::Type>;
+
+        fn to_partial(value: &F::Type) -> Self::Partial {
+            Some(value.clone())
+        }
+
+        fn merge(into: &mut Self::Partial, layer: Self::Partial, cx: &mut Context, path: &str) {
+            cx.merge_value(into, layer, &field_path::<F>(path))
+        }
+
+        fn complete(partial: Self::Partial, cx: &mut Context, path: &str) -> Option<F::Type> {
+            let path = field_path::<F>(path);
+            match F::default_value() {
+                Some(default) if partial.is_none() => {
+                    cx.default_value(&path);
+                    Some(default)
+                }
+                _ => cx.complete_value(partial, &path),
+            }
+        }
+
+        fn is_unset(partial: &Self::Partial) -> bool {
+            partial.is_none()
+        }
+    }
+
+    // the fields of the flattened struct are merged as fields of the parent
+    impl<F, Inner> MergeField<FlattenRepr> for F
+    where
+        F: Field<Type = Inner>,
+        Inner: ReflectMerge,
+    {
+        type Partial = Partial<Inner>;
+
+        fn to_partial(value: &Inner) -> Self::Partial {
+            Partial::from(value)
+        }
+
+        fn merge(into: &mut Self::Partial, layer: Self::Partial, cx: &mut Context, path: &str) {
+            Inner::merge(into, layer, cx, path)
+        }
+
+        fn complete(partial: Self::Partial, cx: &mut Context, path: &str) -> Option<Inner> {
+            Inner::complete(partial, cx, path)
+        }
+
+        fn is_unset(partial: &Self::Partial) -> bool {
+            partial.is_empty()
+        }
+    }
+
+    fn field_path<F: Field>(parent: &str) -> String {
+        const {
+            assert!(
+                F::IDENT.is_some(),
+                "merged fields need names, or `#[reflect(flatten)]`"
+            )
+        };
+        match parent {
+            "" => F::IDENT.unwrap().to_owned(),
+            parent => format!("{parent}.{}", F::IDENT.unwrap()),
+        }
+    }
+
+    // partials are deserialized from maps of field names or aliases to their partial values,
+    // where `null` sets nothing and unknown fields are errors
+    impl<'de, T> Deserialize<'de> for Partial<T>
+    where
+        T: ReflectMerge<Fields: DeserializeFields<'de, T>>,
+    {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct Visit<T>(PhantomData<T>);
+
+            impl<'de, T> Visitor<'de> for Visit<T>
+            where
+                T: ReflectMerge<Fields: DeserializeFields<'de, T>>,
+            {
+                type Value = Partial<T>;
+
+                fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+                    write!(formatter, "struct {}", T::IDENT)
+                }
+
+                fn visit_map<M: MapAccess<'de>>(self, mut map: M) -> Result<Partial<T>, M::Error> {
+                    let mut partial = Partial::<T>::default();
+                    while let Some(key) = map.next_key::<String>()? {
+                        if !T::Fields::deserialize_field(&mut partial.0, &key, &mut map)? {
+                            return Err(M::Error::custom(format_args!("unknown field `{key}`")));
+                        }
+                    }
+                    Ok(partial)
+                }
+            }
+
+            deserializer.deserialize_map(Visit(PhantomData))
+        }
+    }
+
+    pub trait DeserializeFields<'de, Root>: MergeFields<Root> {
+        /// Deserializes the value of `key` if it belongs to one of the fields
+        fn deserialize_field<M: MapAccess<'de>>(
+            partials: &mut Self::Partials,
+            key: &str,
+            map: &mut M,
+        ) -> Result<bool, M::Error>;
+    }
+
+    impl<'de, Root> DeserializeFields<'de, Root> for () {
+        fn deserialize_field<M: MapAccess<'de>>(
+            _: &mut (),
+            _: &str,
+            _: &mut M,
+        ) -> Result<bool, M::Error> {
+            Ok(false)
+        }
+    }
+
+    impl<'de, Root, Head, Tail> DeserializeFields<'de, Root> for Cons<Head, Tail>
+    where
+        Head: Field<Root = Root, Type: Sized> + DeserializeField<'de, <Head as Field>::Repr>,
+        Tail: DeserializeFields<'de, Root>,
+    {
+        fn deserialize_field<M: MapAccess<'de>>(
+            partials: &mut Self::Partials,
+            key: &str,
+            map: &mut M,
+        ) -> Result<bool, M::Error> {
+            Ok(Head::deserialize_field(&mut partials.0, key, map)?
+                || Tail::deserialize_field(&mut partials.1, key, map)?)
+        }
+    }
+
+    pub trait DeserializeField<'de, Repr>: MergeField<Repr> {
+        fn deserialize_field<M: MapAccess<'de>>(
+            partial: &mut Self::Partial,
+            key: &str,
+            map: &mut M,
+        ) -> Result<bool, M::Error>;
+    }
+
+    impl<'de, F> DeserializeField<'de, PlainRepr> for F
+    where
+        F: MergeField<PlainRepr, Partial: Deserialize<'de>>,
+    {
+        fn deserialize_field<M: MapAccess<'de>>(
+            partial: &mut Self::Partial,
+            key: &str,
+            map: &mut M,
+        ) -> Result<bool, M::Error> {
+            if !is_field::<F>(key) {
+                return Ok(false);
+            }
+            *partial = map.next_value()?;
+            Ok(true)
+        }
+    }
+
+    impl<'de, F, A> DeserializeField<'de, WithRepr<A>> for F
+    where
+        F: Field<Type: Sized> + MergeField<WithRepr<A>, Partial = Option<<F as Field>::Type>>,
+        A: DeserializeAs<'de, F::Type>,
+    {
+        fn deserialize_field<M: MapAccess<'de>>(
+            partial: &mut Self::Partial,
+            key: &str,
+            map: &mut M,
+        ) -> Result<bool, M::Error> {
+            if !is_field::<F>(key) {
+                return Ok(false);
+            }
+            *partial = map.next_value_seed(Adapted::<A, F::Type>(PhantomData))?;
+            Ok(true)
+        }
+    }
+
+    impl<'de, F, Inner> DeserializeField<'de, FlattenRepr> for F
+    where
+        F: Field<Type = Inner> + MergeField<FlattenRepr, Partial = Partial<Inner>>,
+        Inner: ReflectMerge<Fields: DeserializeFields<'de, Inner>>,
+    {
+        fn deserialize_field<M: MapAccess<'de>>(
+            partial: &mut Self::Partial,
+            key: &str,
+            map: &mut M,
+        ) -> Result<bool, M::Error> {
+            Inner::Fields::deserialize_field(&mut partial.0, key, map)
+        }
+    }
+
+    fn is_field<F: Field>(key: &str) -> bool {
+        F::IDENT == Some(key) || F::ALIASES.contains(&key)
+    }
+
+    // deserializes `Option<T>` through the adapter `A`, `null` being `None`
+    struct Adapted<A, T>(PhantomData<(A, T)>);
+
+    impl<'de, A, T> DeserializeSeed<'de> for Adapted<A, T>
+    where
+        A: DeserializeAs<'de, T>,
+    {
+        type Value = Option<T>;
+
+        fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Option<T>, D::Error> {
+            struct Visit<A, T>(PhantomData<(A, T)>);
+
+            impl<'de, A, T> Visitor<'de> for Visit<A, T>
+            where
+                A: DeserializeAs<'de, T>,
+            {
+                type Value = Option<T>;
+
+                fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+                    formatter.write_str("optional value")
+                }
+
+                fn visit_none<E: serde::de::Error>(self) -> Result<Option<T>, E> {
+                    Ok(None)
+                }
+
+                fn visit_unit<E: serde::de::Error>(self) -> Result<Option<T>, E> {
+                    Ok(None)
+                }
+
+                fn visit_some<D: Deserializer<'de>>(
+                    self,
+                    deserializer: D,
+                ) -> Result<Option<T>, D::Error> {
+                    A::deserialize_as(deserializer).map(Some)
+                }
+            }
+
+            deserializer.deserialize_option(Visit::<A, T>(PhantomData))
+        }
+    }
+}
+
+// types which are replaced as a whole
+mod impls {
+    use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+    use std::ffi::OsString;
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    use crate::{Context, Merge};
+
+    macro_rules! whole {
+        ($(impl<$($p:ident),*> for $t:ty;)*) => {$(
+            impl<$($p: Clone),*> Merge for $t {
+                type Partial = Option<Self>;
+
+                fn to_partial(&self) -> Option<Self> {
+                    Some(self.clone())
+                }
+
+                fn merge(into: &mut Option<Self>, layer: Option<Self>, cx: &mut Context, path: &str) {
+                    cx.merge_value(into, layer, path)
+                }
+
+                fn complete(partial: Option<Self>, cx: &mut Context, path: &str) -> Option<Self> {
+                    cx.complete_value(partial, path)
+                }
+
+                fn is_unset(partial: &Option<Self>) -> bool {
+                    partial.is_none()
+                }
+            }
+        )*};
+    }
+
+    macro_rules! scalars {
+        ($($t:ty),*) => {
+            whole!($(impl<> for $t;)*);
+        };
+    }
+
+    scalars!(
+        bool,
+        char,
+        u8,
+        u16,
+        u32,
+        u64,
+        u128,
+        usize,
+        i8,
+        i16,
+        i32,
+        i64,
+        i128,
+        isize,
+        f32,
+        f64,
+        String,
+        PathBuf,
+        OsString,
+        Duration,
+        IpAddr,
+        Ipv4Addr,
+        Ipv6Addr,
+        SocketAddr,
+        SocketAddrV4,
+        SocketAddrV6
+    );
+
+    whole!(
+        impl<T> for Vec<T>;
+        impl<T> for VecDeque<T>;
+        impl<T> for BTreeSet<T>;
+        impl<K, V> for BTreeMap<K, V>;
+        impl<T, S> for HashSet<T, S>;
+        impl<K, V, S> for HashMap<K, V, S>;
+    );
+
+    // `None` when no layer sets it. A layer can't set it back to `None`.
+    impl<T: Clone> Merge for Option<T> {
+        type Partial = Option<Self>;
+
+        fn to_partial(&self) -> Option<Self> {
+            // a `None` sets nothing, so it can't override other layers
+            self.clone().map(Some)
+        }
+
+        fn merge(into: &mut Option<Self>, layer: Option<Self>, cx: &mut Context, path: &str) {
+            cx.merge_value(into, layer, path)
+        }
+
+        fn complete(partial: Option<Self>, cx: &mut Context, path: &str) -> Option<Self> {
+            cx.complete_value(partial, path)
+        }
+
+        fn is_unset(partial: &Option<Self>) -> bool {
+            partial.is_none()
+        }
+
+        fn unset() -> Option<Self> {
+            Some(None)
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use std::path::PathBuf;
+
+use reflector::Introspect;
+use reflector_merge::{Error, Partial, Source, merge};
+use reflector_serde::with;
+use serde_json::json;
+
+#[derive(Debug, PartialEq, Introspect)]
+pub struct Config {
+    name: String,
+    server: Server,
+    log_file: Option<PathBuf>,
+    #[reflect(default)]
+    workers: usize,
+    tags: Vec<String>,
+    #[reflect(flatten)]
+    limits: Limits,
+}
+
+#[derive(Debug, PartialEq, Introspect)]
+#[reflect(impl(reflector_merge::Merge))]
+pub struct Server {
+    #[reflect(renamed_from = "hostname")]
+    host: String,
+    port: u16,
+}
+
+#[derive(Debug, PartialEq, Introspect)]
+pub struct Limits {
+    max_connections: u32,
+}
+
+fn layer(value: serde_json::Value) -> Partial<Config> {
+    serde_json::from_value(value).unwrap()
+}
+
+fn defaults() -> Partial<Config> {
+    Partial::from(&Config {
+        name: "app".into(),
+        server: Server {
+            host: "localhost".into(),
+            port: 80,
+        },
+        log_file: None,
+        workers: 1,
+        tags: vec!["default".into()],
+        limits: Limits {
+            max_connections: 100,
+        },
+    })
+}
+
+#[test]
+fn layers() {
+    let file = layer(json!({
+        "server": { "hostname": "example.com" },
+        "tags": ["a", "b"],
+        "max_connections": 10,
+    }));
+    let env = layer(json!({ "server": { "port": 8080 }, "log_file": "app.log" }));
+    let cli = layer(json!({ "tags": ["c"], "log_file": null }));
+
+    let merged = merge([
+        ("defaults", defaults()),
+        ("file", file),
+        ("env", env),
+        ("cli", cli),
+    ])
+    .unwrap();
+    assert_eq!(
+        merged.value,
+        Config {
+            name: "app".into(),
+            server: Server {
+                host: "example.com".into(),
+                port: 8080,
+            },
+            log_file: Some("app.log".into()),
+            workers: 1,
+            tags: vec!["c".into()],
+            limits: Limits {
+                max_connections: 10
+            },
+        }
+    );
+    assert_eq!(
+        merged.provenance.source("server.port"),
+        Some(&Source::Layer("env".into()))
+    );
+    assert_eq!(merged.provenance.source("server"), None);
+    assert_eq!(
+        merged.provenance.to_string(),
+        "name: defaults
+server.host: file
+server.port: env
+log_file: env
+workers: defaults
+tags: cli
+max_connections: file
+"
+    );
+}
+
+#[test]
+fn missing() {
+    let merged = merge([(
+        "file",
+        layer(json!({ "name": "app", "server": { "host": "h", "port": 1 }, "tags": [] })),
+    )]);
+    assert_eq!(merged.unwrap_err().missing, ["max_connections"]);
+
+    let merged = merge([
+        ("file", layer(json!({ "server": { "port": 1 } }))),
+        ("env", Partial::default()),
+    ]);
+    let error = merged.unwrap_err();
+    assert_eq!(
+        error,
+        Error {
+            missing: vec![
+                "name".into(),
+                "server.host".into(),
+                "tags".into(),
+                "max_connections".into()
+            ]
+        }
+    );
+    assert_eq!(
+        error.to_string(),
+        "missing values: name, server.host, tags, max_connections"
+    );
+
+    // unset fields get their defaults
+    let merged = merge([(
+        "file",
+        layer(json!({
+            "name": "app",
+            "server": { "host": "h", "port": 1 },
+            "tags": [],
+            "max_connections": 1,
+        })),
+    )])
+    .unwrap();
+    assert_eq!(merged.value.log_file, None);
+    assert_eq!(merged.value.workers, 0);
+    assert_eq!(merged.provenance.source("log_file"), Some(&Source::Default));
+    assert_eq!(merged.provenance.source("workers"), Some(&Source::Default));
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Level(u8);
+
+impl std::fmt::Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::str::FromStr for Level {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(Level)
+    }
+}
+
+#[derive(Debug, PartialEq, Introspect)]
+pub struct Logging {
+    #[reflect(adapter = with::DisplayFromStr)]
+    level: Level,
+    #[reflect(adapter = with::DisplayFromStr, default = default_target)]
+    target: Level,
+}
+
+fn default_target() -> Level {
+    Level(9)
+}
+
+#[test]
+fn deserialize() {
+    let parse = |value| serde_json::from_value::<Partial<Logging>>(value);
+    let merged = merge([
+        ("file", parse(json!({ "level": "1" })).unwrap()),
+        (
+            "env",
+            parse(json!({ "level": "2", "target": null })).unwrap(),
+        ),
+    ])
+    .unwrap();
+    assert_eq!(
+        merged.value,
+        Logging {
+            level: Level(2),
+            target: Level(9)
+        }
+    );
+    assert_eq!(
+        merged.provenance.to_string(),
+        "level: env\ntarget: default\n"
+    );
+
+    assert!(parse(json!({})).unwrap().is_empty());
+    assert!(!parse(json!({ "level": "1" })).unwrap().is_empty());
+    assert_eq!(
+        parse(json!({ "level": "high" })).err().unwrap().to_string(),
+        "invalid digit found in string"
+    );
+    assert_eq!(
+        parse(json!({ "lvl": "1" })).err().unwrap().to_string(),
+        "unknown field `lvl`"
+    );
+    let nested = json!({ "server": { "port": 1, "ssl": true } });
+    assert_eq!(
+        serde_json::from_value::<Partial<Config>>(nested)
+            .err()
+            .unwrap()
+            .to_string(),
+        "unknown field `ssl`"
+    );
+}
@@ -0,0 +1,1072 @@
+//! JSON merge patches (RFC 7396) and JSON patches (RFC 6902) for reflected types.
+//!
+//! Values are represented like `serde_json` represents std types and `#[derive(Serialize)]` types:
+//! named structs as objects, tuple structs as arrays, newtype structs as their only field, unit
+//! variants as strings and other variants as objects with a single entry. Paths are JSON pointers
+//! into that representation, e.g. `/server/port` or `/mode/Slow/delay`.
+//!
+//! Any reflected type can be patched with [`merge`] and [`apply`], as long as its fields implement
+//! [`Patch`] and [`FromValue`], like std types and reflected types marked
+//! `#[reflect(impl(reflector_patch::Patch))]` do.
+//! Fields marked `#[reflect(flatten)]` or `#[reflect(with = ..)]` and transparent structs are not
+//! supported.
+
+pub use error::{Error, ErrorKind};
+pub use operation::Operation;
+pub use reflect::ReflectPatch;
+pub use serde_json::Value;
+
+/// Called with the part of a value at a token, see [`Patch::child`].
+pub type Visit<'a> = &'a mut dyn FnMut(&mut dyn Patch) -> Result<Value, Error>;
+
+/// A value which can be patched in place.
+///
+/// Tokens are the unescaped parts of a JSON pointer, e.g. field names, indices and map keys. All
+/// errors are relative to the value, the callers prepend the tokens leading to it.
+pub trait Patch {
+    /// The current value, e.g. for `test` and `copy` operations
+    fn to_value(&self) -> Result<Value, Error>;
+
+    /// Replaces the value
+    fn set(&mut self, value: Value) -> Result<(), Error>;
+
+    /// Calls `visit` with the part of the value at `token`, or returns `None` if there is none
+    fn child(&mut self, token: &str, visit: Visit<'_>) -> Option<Result<Value, Error>> {
+        let _ = (token, visit);
+        None
+    }
+
+    /// Adds a part at `token`, replacing it if it exists
+    fn add(&mut self, token: &str, value: Value) -> Result<(), Error> {
+        let mut value = Some(value);
+        self.child(token, &mut |child| {
+            child.set(value.take().unwrap())?;
+            Ok(Value::Null)
+        })
+        .unwrap_or_else(not_found)
+        .map(drop)
+    }
+
+    /// Removes the part at `token`, returning its value
+    fn remove(&mut self, token: &str) -> Result<Value, Error> {
+        self.child(token, &mut |child| child.take())
+            .unwrap_or_else(not_found)
+    }
+
+    /// Removes the value itself, which is only possible for optional values
+    fn take(&mut self) -> Result<Value, Error> {
+        Err(Error::new(ErrorKind::CannotRemove))
+    }
+
+    /// Whether an object in a merge patch is merged into the value, instead of replacing it
+    fn is_object(&self) -> bool {
+        false
+    }
+
+    /// Whether any token can be added, rather than just those of a fixed set of fields
+    fn is_map(&self) -> bool {
+        false
+    }
+}
+
+/// Constructs a value from its JSON representation.
+pub trait FromValue: Sized {
+    fn from_value(value: Value) -> Result<Self, Error>;
+}
+
+/// Implements [`Patch`] and [`FromValue`] for a reflected type through [`ReflectPatch`], with
+/// `#[reflect(impl(reflector_patch::Patch))]`. Type parameters have to implement both.
+#[macro_export]
+macro_rules! Patch {
+    (
+        lifetimes [$($lifetime:lifetime),*]
+        types [$($param:ident),*]
+        impl [$($params:tt)*] $ty:ty
+        where [$($where:tt)*]
+    ) => {
+        impl<$($params)*> $crate::Patch for $ty
+        where
+            $($where)*
+            $($param: $crate::Patch + $crate::FromValue,)*
+        {
+            fn to_value(&self) -> ::core::result::Result<$crate::Value, $crate::Error> {
+                <Self as $crate::ReflectPatch>::to_value(self)
+            }
+
+            fn set(&mut self, value: $crate::Value) -> ::core::result::Result<(), $crate::Error> {
+                *self = <Self as $crate::ReflectPatch>::from_value(value)?;
+                ::core::result::Result::Ok(())
+            }
+
+            fn child(
+                &mut self,
+                token: &str,
+                visit: $crate::Visit<'_>,
+            ) -> ::core::option::Option<::core::result::Result<$crate::Value, $crate::Error>> {
+                <Self as $crate::ReflectPatch>::child(self, token, visit)
+            }
+
+            fn add(&mut self, token: &str, value: $crate::Value) -> ::core::result::Result<(), $crate::Error> {
+                <Self as $crate::ReflectPatch>::add(self, token, value)
+            }
+
+            fn is_object(&self) -> bool {
+                <Self as $crate::ReflectPatch>::is_object(self)
+            }
+        }
+
+        impl<$($params)*> $crate::FromValue for $ty
+        where
+            $($where)*
+            $($param: $crate::Patch + $crate::FromValue,)*
+        {
+            fn from_value(value: $crate::Value) -> ::core::result::Result<Self, $crate::Error> {
+                <Self as $crate::ReflectPatch>::from_value(value)
+            }
+        }
+    };
+}
+
+/// Applies a JSON merge patch: objects are merged recursively, `null` removes optional values and
+/// map entries, and everything else replaces the value.
+///
+/// The value is left untouched if the patch fails.
+pub fn merge<T: ReflectPatch + Clone>(value: &mut T, patch: Value) -> Result<(), Error> {
+    let mut patched = value.clone();
+    merge_into(&mut reflect::Root(&mut patched), patch)?;
+    *value = patched;
+    Ok(())
+}
+
+/// Applies the operations of a JSON patch in order.
+///
+/// The value is left untouched if any of the operations fails.
+pub fn apply<T: ReflectPatch + Clone>(
+    value: &mut T,
+    operations: &[Operation],
+) -> Result<(), Error> {
+    let mut patched = value.clone();
+    for operation in operations {
+        operation.apply(&mut reflect::Root(&mut patched))?;
+    }
+    *value = patched;
+    Ok(())
+}
+
+fn merge_into(target: &mut dyn Patch, patch: Value) -> Result<(), Error> {
+    let entries = match patch {
+        Value::Object(entries) if target.is_object() => entries,
+        patch => return target.set(patch),
+    };
+
+    for (token, value) in entries {
+        let result = if value.is_null() {
+            // removing an absent entry of a map does nothing, an unknown field is an error
+            if target.is_map() && target.child(&token, &mut |_| Ok(Value::Null)).is_none() {
+                Ok(())
+            } else {
+                target.remove(&token).map(drop)
+            }
+        } else {
+            let mut value = Some(value);
+            let merged = target.child(&token, &mut |child| {
+                merge_into(child, value.take().unwrap())?;
+                Ok(Value::Null)
+            });
+            match merged {
+                Some(result) => result.map(drop),
+                None => target.add(&token, value.take().unwrap()),
+            }
+        };
+        result.map_err(|err| err.at(&token))?;
+    }
+    Ok(())
+}
+
+fn not_found<T>() -> Result<T, Error> {
+    Err(Error::new(ErrorKind::NotFound))
+}
+
+fn invalid(message: impl Into<String>) -> Error {
+    Error::new(ErrorKind::InvalidValue(message.into()))
+}
+
+// `/0` is an index, while `/00` and `/+0` are not
+fn index(token: &str) -> Option<usize> {
+    let canonical = token == "0" || !token.starts_with('0');
+    let digits = !token.is_empty() && token.bytes().all(|b| b.is_ascii_digit());
+    (canonical && digits).then(|| token.parse().ok()).flatten()
+}
+
+mod error {
+    use std::fmt::{self, Display, Formatter};
+
+    use serde_json::Value;
+
+    /// Why a patch could not be applied, and where.
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Error {
+        /// The tokens of the JSON pointer to the failing location
+        pub path: Vec<String>,
+        pub kind: ErrorKind,
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum ErrorKind {
+        /// There is no field, element or entry at the path
+        NotFound,
+        /// The value doesn't match the type at the path
+        InvalidValue(String),
+        /// The value at the path is required, and can only be replaced
+        CannotRemove,
+        /// A `test` operation failed
+        TestFailed { expected: Value, actual: Value },
+        /// The patch itself is malformed, e.g. a path isn't a JSON pointer
+        InvalidPatch(String),
+    }
+
+    impl Error {
+        pub fn new(kind: ErrorKind) -> Self {
+            Self {
+                path: Vec::new(),
+                kind,
+            }
+        }
+
+        /// Moves the error into the part of a value at `token`
+        pub fn at(mut self, token: &str) -> Self {
+            self.path.insert(0, token.to_owned());
+            self
+        }
+
+        /// The path as a JSON pointer, e.g. `/servers/0/port`
+        pub fn pointer(&self) -> String {
+            self.path
+                .iter()
+                .map(|token| format!("/{}", token.replace('~', "~0").replace('/', "~1")))
+                .collect()
+        }
+    }
+
+    impl Display for Error {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            if !self.path.is_empty() {
+                write!(f, "{}: ", self.pointer())?;
+            }
+            match &self.kind {
+                ErrorKind::NotFound => f.write_str("path not found"),
+                ErrorKind::InvalidValue(message) => write!(f, "invalid value, {message}"),
+                ErrorKind::CannotRemove => f.write_str("required value cannot be removed"),
+                ErrorKind::TestFailed { expected, actual } => {
+                    write!(f, "test failed, expected {expected} but found {actual}")
+                }
+                ErrorKind::InvalidPatch(message) => write!(f, "invalid patch, {message}"),
+            }
+        }
+    }
+
+    impl std::error::Error for Error {}
+}
+
+mod operation {
+    use serde::{Deserialize, Serialize};
+    use serde_json::Value;
+
+    use crate::{Error, ErrorKind, Patch, not_found};
+
+    /// An operation of a JSON patch, `{ "op": "add", "path": "/a/b", "value": 1 }` in JSON.
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(tag = "op", rename_all = "lowercase")]
+    pub enum Operation {
+        /// Adds an element or entry, or replaces the value at `path`. `/-` appends to arrays.
+        Add {
+            path: String,
+            value: Value,
+        },
+        Remove {
+            path: String,
+        },
+        /// Replaces the value at `path`, which has to exist
+        Replace {
+            path: String,
+            value: Value,
+        },
+        Move {
+            from: String,
+            path: String,
+        },
+        Copy {
+            from: String,
+            path: String,
+        },
+        /// Checks that `path` has the given value
+        Test {
+            path: String,
+            value: Value,
+        },
+    }
+
+    impl Operation {
+        pub fn apply(&self, target: &mut dyn Patch) -> Result<(), Error> {
+            match self {
+                Operation::Add { path, value } => add(target, &tokens(path)?, value.clone()),
+                Operation::Remove { path } => remove(target, &tokens(path)?).map(drop),
+                Operation::Replace { path, value } => {
+                    let set = &mut |target: &mut dyn Patch| {
+                        target.set(value.clone())?;
+                        Ok(Value::Null)
+                    };
+                    walk(target, &tokens(path)?, set).map(drop)
+                }
+                Operation::Move { from, path } => {
+                    let (from, path) = (tokens(from)?, tokens(path)?);
+                    if path.len() > from.len() && path.starts_with(&from) {
+                        let message = "a value can't be moved into itself";
+                        return Err(Error::new(ErrorKind::InvalidPatch(message.into())));
+                    }
+                    let value = remove(target, &from)?;
+                    add(target, &path, value)
+                }
+                Operation::Copy { from, path } => {
+                    let value = walk(target, &tokens(from)?, &mut |value| value.to_value())?;
+                    add(target, &tokens(path)?, value)
+                }
+                Operation::Test { path, value } => {
+                    let path = tokens(path)?;
+                    let actual = walk(target, &path, &mut |value| value.to_value())?;
+                    if actual == *value {
+                        return Ok(());
+                    }
+                    let kind = ErrorKind::TestFailed {
+                        expected: value.clone(),
+                        actual,
+                    };
+                    Err(Error { path, kind })
+                }
+            }
+        }
+    }
+
+    // the unescaped tokens of a JSON pointer
+    fn tokens(pointer: &str) -> Result<Vec<String>, Error> {
+        if pointer.is_empty() {
+            return Ok(Vec::new());
+        }
+        let Some(pointer) = pointer.strip_prefix('/') else {
+            let message = format!("`{pointer}` is not a JSON pointer");
+            return Err(Error::new(ErrorKind::InvalidPatch(message)));
+        };
+        let unescape = |token: &str| token.replace("~1", "/").replace("~0", "~");
+        Ok(pointer.split('/').map(unescape).collect())
+    }
+
+    // calls `visit` with the value at `path`
+    fn walk(
+        target: &mut dyn Patch,
+        path: &[String],
+        visit: &mut dyn FnMut(&mut dyn Patch) -> Result<Value, Error>,
+    ) -> Result<Value, Error> {
+        let Some((token, rest)) = path.split_first() else {
+            return visit(target);
+        };
+        target
+            .child(token, &mut |child| walk(child, rest, visit))
+            .unwrap_or_else(not_found)
+            .map_err(|err| err.at(token))
+    }
+
+    fn add(target: &mut dyn Patch, path: &[String], value: Value) -> Result<(), Error> {
+        let Some((token, parent)) = path.split_last() else {
+            return target.set(value);
+        };
+        let mut value = Some(value);
+        let add = &mut |parent: &mut dyn Patch| {
+            parent
+                .add(token, value.take().unwrap())
+                .map_err(|err| err.at(token))?;
+            Ok(Value::Null)
+        };
+        walk(target, parent, add).map(drop)
+    }
+
+    fn remove(target: &mut dyn Patch, path: &[String]) -> Result<Value, Error> {
+        let Some((token, parent)) = path.split_last() else {
+            return target.take();
+        };
+        let remove =
+            &mut |parent: &mut dyn Patch| parent.remove(token).map_err(|err| err.at(token));
+        walk(target, parent, remove)
+    }
+}
+
+mod reflect {
+    use std::marker::PhantomData;
+
+    use reflector::*;
+    use serde_json::{Map, Value};
+
+    use crate::{Error, ErrorKind, FromValue, Patch, Visit, index, invalid, not_found};
+
+    /// Patches reflected types through their fields, implemented for any type which can be
+    /// introspected and whose fields implement [`Patch`] and [`FromValue`].
+    pub trait ReflectPatch: Sized {
+        fn to_value(&self) -> Result<Value, Error>;
+        fn from_value(value: Value) -> Result<Self, Error>;
+        fn child(&mut self, token: &str, visit: Visit<'_>) -> Option<Result<Value, Error>>;
+        fn add(&mut self, token: &str, value: Value) -> Result<(), Error>;
+        fn is_object(&self) -> bool;
+    }
+
+    impl<T> ReflectPatch for T
+    where
+        T: Introspect,
+        T: PatchKind<T::Kind>,
+    {
+        fn to_value(&self) -> Result<Value, Error> {
+            T::to_value(self)
+        }
+
+        fn from_value(value: Value) -> Result<Self, Error> {
+            T::from_value(value)
+        }
+
+        fn child(&mut self, token: &str, visit: Visit<'_>) -> Option<Result<Value, Error>> {
+            T::child(self, token, visit)
+        }
+
+        fn add(&mut self, token: &str, value: Value) -> Result<(), Error> {
+            T::add(self, token, value)
+        }
+
+        fn is_object(&self) -> bool {
+            T::IS_OBJECT
+        }
+    }
+
+    // the value being patched by `merge` and `apply`
+    pub(crate) struct Root<'a, T>(pub &'a mut T);
+
+    impl<T: ReflectPatch> Patch for Root<'_, T> {
+        fn to_value(&self) -> Result<Value, Error> {
+            self.0.to_value()
+        }
+
+        fn set(&mut self, value: Value) -> Result<(), Error> {
+            *self.0 = T::from_value(value)?;
+            Ok(())
+        }
+
+        fn child(&mut self, token: &str, visit: Visit<'_>) -> Option<Result<Value, Error>> {
+            self.0.child(token, visit)
+        }
+
+        fn add(&mut self, token: &str, value: Value) -> Result<(), Error> {
+            self.0.add(token, value)
+        }
+
+        fn is_object(&self) -> bool {
+            self.0.is_object()
+        }
+    }
+
+    pub trait PatchKind<Kind>: Sized {
+        const IS_OBJECT: bool;
+
+        fn to_value(&self) -> Result<Value, Error>;
+        fn from_value(value: Value) -> Result<Self, Error>;
+        fn child(&mut self, token: &str, visit: Visit<'_>) -> Option<Result<Value, Error>>;
+        fn add(&mut self, token: &str, value: Value) -> Result<(), Error>;
+    }
+
+    impl<T> PatchKind<StructKind> for T
+    where
+        T: SizedStruct<Root = T, Repr = PlainRepr>,
+        T: PatchShape<T, T::Shape>,
+        T::Fields: PatchFields<T>,
+    {
+        const IS_OBJECT: bool = <T as PatchShape<_, _>>::IS_OBJECT;
+
+        fn to_value(&self) -> Result<Value, Error> {
+            <T as PatchShape<_, _>>::to_value(self)
+        }
+
+        fn from_value(value: Value) -> Result<Self, Error> {
+            <T as PatchShape<_, _>>::from_value(value)
+        }
+
+        fn child(&mut self, token: &str, visit: Visit<'_>) -> Option<Result<Value, Error>> {
+            T::Fields::child(self, token, visit)
+        }
+
+        // fields can only be replaced
+        fn add(&mut self, token: &str, value: Value) -> Result<(), Error> {
+            let mut value = Some(value);
+            let set = &mut |field: &mut dyn Patch| {
+                field.set(value.take().unwrap())?;
+                Ok(Value::Null)
+            };
+            T::Fields::child(self, token, set)
+                .unwrap_or_else(not_found)
+                .map(drop)
+        }
+    }
+
+    impl<T> PatchKind<EnumKind> for T
+    where
+        T: Enum,
+        T::Variants: PatchVariants<T>,
+    {
+        const IS_OBJECT: bool = true;
+
+        fn to_value(&self) -> Result<Value, Error> {
+            T::Variants::to_value(self)
+        }
+
+        // `"Unit"` or `{ "Variant": payload }`
+        fn from_value(value: Value) -> Result<Self, Error> {
+            let (variant, payload) = match value {
+                Value::String(variant) => (variant, Value::Null),
+                Value::Object(entries) if entries.len() == 1 => entries.into_iter().next().unwrap(),
+                value => {
+                    return Err(invalid(format!(
+                        "expected a variant of `{}`, found {value}",
+                        T::IDENT
+                    )));
+                }
+            };
+            match T::Variants::from_value(&variant, payload) {
+                Some(result) => result.map_err(|err| err.at(&variant)),
+                None => Err(invalid(format!(
+                    "unknown variant `{variant}` of `{}`",
+                    T::IDENT
+                ))),
+            }
+        }
+
+        fn child(&mut self, token: &str, visit: Visit<'_>) -> Option<Result<Value, Error>> {
+            T::Variants::child(self, token, visit)
+        }
+
+        // switches to the variant `token`
+        fn add(&mut self, token: &str, value: Value) -> Result<(), Error> {
+            *self = T::Variants::from_value(token, value).unwrap_or_else(not_found)?;
+            Ok(())
+        }
+    }
+
+    // the representation of a struct or variant, by its shape
+    pub trait PatchShape<Root, Shape> {
+        const IS_OBJECT: bool;
+        const IS_UNIT: bool = false;
+
+        fn to_value(root: &Root) -> Result<Value, Error>;
+        fn from_value(value: Value) -> Result<Root, Error>;
+    }
+
+    // struct S; and enum E { S, .. }, as `null`
+    impl<S> PatchShape<S::Root, UnitShape> for S
+    where
+        S: SizedStruct<FieldTypes = ()>,
+    {
+        const IS_OBJECT: bool = false;
+        const IS_UNIT: bool = true;
+
+        fn to_value(_: &S::Root) -> Result<Value, Error> {
+            Ok(Value::Null)
+        }
+
+        fn from_value(value: Value) -> Result<S::Root, Error> {
+            match value {
+                Value::Null => Ok(S::from_values(())),
+                value => Err(invalid(format!("expected `{}`, found {value}", S::IDENT))),
+            }
+        }
+    }
+
+    // struct S(..); and enum E { S(..), .. }, as an array, or as the only field
+    impl<S> PatchShape<S::Root, TupleShape> for S
+    where
+        S: SizedStruct<Fields: PatchFields<S::Root, Values = S::FieldTypes>>,
+    {
+        const IS_OBJECT: bool = false;
+
+        fn to_value(root: &S::Root) -> Result<Value, Error> {
+            let mut values = Vec::new();
+            S::Fields::to_array(root, &mut values)?;
+            match S::Fields::LENGTH {
+                1 => Ok(values.pop().unwrap()),
+                _ => Ok(Value::Array(values)),
+            }
+        }
+
+        fn from_value(value: Value) -> Result<S::Root, Error> {
+            let values = match value {
+                value if S::Fields::LENGTH == 1 => vec![value],
+                Value::Array(values) if values.len() == S::Fields::LENGTH => values,
+                value => {
+                    let expected = S::Fields::LENGTH;
+                    let message = format!("expected an array of {expected} values, found {value}");
+                    return Err(invalid(message));
+                }
+            };
+            S::Fields::from_array(&mut values.into_iter()).map(S::from_values)
+        }
+    }
+
+    // struct S { .. } and enum E { S { .. }, .. }, as an object
+    impl<S> PatchShape<S::Root, NamedShape> for S
+    where
+        S: SizedStruct<Fields: PatchFields<S::Root, Values = S::FieldTypes>>,
+    {
+        const IS_OBJECT: bool = true;
+
+        fn to_value(root: &S::Root) -> Result<Value, Error> {
+            let mut entries = Map::new();
+            S::Fields::to_object(root, &mut entries)?;
+            Ok(Value::Object(entries))
+        }
+
+        fn from_value(value: Value) -> Result<S::Root, Error> {
+            let Value::Object(mut entries) = value else {
+                return Err(invalid(format!("expected `{}`, found {value}", S::IDENT)));
+            };
+            let values = S::Fields::from_object(&mut entries)?;
+            match entries.keys().next() {
+                Some(unknown) => Err(Error::new(ErrorKind::NotFound).at(unknown)),
+                None => Ok(S::from_values(values)),
+            }
+        }
+    }
+
+    // helper trait implemented recursively for a list of fields, e.g `(Field0, (Field1, ()))`
+    pub trait PatchFields<P>: List {
+        type Values;
+
+        fn to_array(parent: &P, values: &mut Vec<Value>) -> Result<(), Error>;
+        fn to_object(parent: &P, entries: &mut Map<String, Value>) -> Result<(), Error>;
+        fn from_array(values: &mut impl Iterator<Item = Value>) -> Result<Self::Values, Error>;
+        fn from_object(entries: &mut Map<String, Value>) -> Result<Self::Values, Error>;
+        fn child(parent: &mut P, token: &str, visit: Visit<'_>) -> Option<Result<Value, Error>>;
+    }
+    // end of the recursion
+    impl<P> PatchFields<P> for () {
+        type Values = ();
+
+        fn to_array(_: &P, _: &mut Vec<Value>) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn to_object(_: &P, _: &mut Map<String, Value>) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn from_array(_: &mut impl Iterator<Item = Value>) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn from_object(_: &mut Map<String, Value>) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn child(_: &mut P, _: &str, _: Visit<'_>) -> Option<Result<Value, Error>> {
+            None
+        }
+    }
+    impl<P, Head, Tail> PatchFields<P> for Cons<Head, Tail>
+    where
+        Head: Field<Root = P, Repr = PlainRepr, Type: Patch + FromValue>,
+        Tail: PatchFields<P>,
+    {
+        type Values = Cons<Head::Type, Tail::Values>;
+
+        fn to_array(parent: &P, values: &mut Vec<Value>) -> Result<(), Error> {
+            let value = Head::try_get_ref(parent).unwrap();
+            values.push(
+                value
+                    .to_value()
+                    .map_err(|err| err.at(&Head::INDEX.to_string()))?,
+            );
+            Tail::to_array(parent, values)
+        }
+
+        fn to_object(parent: &P, entries: &mut Map<String, Value>) -> Result<(), Error> {
+            let (ident, value) = (Head::IDENT.unwrap(), Head::try_get_ref(parent).unwrap());
+            entries.insert(ident.into(), value.to_value().map_err(|err| err.at(ident))?);
+            Tail::to_object(parent, entries)
+        }
+
+        fn from_array(values: &mut impl Iterator<Item = Value>) -> Result<Self::Values, Error> {
+            let value = values.next().unwrap();
+            let head =
+                Head::Type::from_value(value).map_err(|err| err.at(&Head::INDEX.to_string()))?;
+            Ok(Cons(head, Tail::from_array(values)?))
+        }
+
+        // missing fields are `null`, so that optional fields may be left out
+        fn from_object(entries: &mut Map<String, Value>) -> Result<Self::Values, Error> {
+            let ident = Head::IDENT.unwrap();
+            let head = match entries.remove(ident) {
+                Some(value) => Head::Type::from_value(value).map_err(|err| err.at(ident))?,
+                None => Head::Type::from_value(Value::Null)
+                    .map_err(|_| invalid(format!("missing field `{ident}`")))?,
+            };
+            Ok(Cons(head, Tail::from_object(entries)?))
+        }
+
+        fn child(parent: &mut P, token: &str, visit: Visit<'_>) -> Option<Result<Value, Error>> {
+            let matches = match Head::IDENT {
+                Some(ident) => ident == token,
+                None => index(token) == Some(Head::INDEX as usize),
+            };
+            if matches {
+                // fields of the active variant are always present
+                Head::try_get_mut(parent).map(|value| visit(value))
+            } else {
+                Tail::child(parent, token, visit)
+            }
+        }
+    }
+
+    // helper trait implemented recursively for a list of variants, e.g `(Variant0, (Variant1, ()))`
+    pub trait PatchVariants<P> {
+        fn to_value(parent: &P) -> Result<Value, Error>;
+        fn from_value(variant: &str, payload: Value) -> Option<Result<P, Error>>;
+        fn child(parent: &mut P, token: &str, visit: Visit<'_>) -> Option<Result<Value, Error>>;
+    }
+    // end of the recursion, one of the variants is always active
+    impl<P> PatchVariants<P> for () {
+        fn to_value(_: &P) -> Result<Value, Error> {
+            unreachable!()
+        }
+
+        fn from_value(_: &str, _: Value) -> Option<Result<P, Error>> {
+            None
+        }
+
+        fn child(_: &mut P, _: &str, _: Visit<'_>) -> Option<Result<Value, Error>> {
+            None
+        }
+    }
+    impl<P, Head, Tail> PatchVariants<P> for Cons<Head, Tail>
+    where
+        Head: Variant<Root = P> + SizedStruct + PatchShape<P, Head::Shape>,
+        Head::Fields: PatchFields<P>,
+        Tail: PatchVariants<P>,
+    {
+        fn to_value(parent: &P) -> Result<Value, Error> {
+            if !Head::is_active(parent) {
+                return Tail::to_value(parent);
+            }
+            if <Head as PatchShape<_, _>>::IS_UNIT {
+                return Ok(Value::String(Head::IDENT.into()));
+            }
+            let payload =
+                <Head as PatchShape<_, _>>::to_value(parent).map_err(|err| err.at(Head::IDENT))?;
+            Ok(Value::Object(Map::from_iter([(
+                Head::IDENT.into(),
+                payload,
+            )])))
+        }
+
+        fn from_value(variant: &str, payload: Value) -> Option<Result<P, Error>> {
+            if variant == Head::IDENT {
+                Some(<Head as PatchShape<_, _>>::from_value(payload))
+            } else {
+                Tail::from_value(variant, payload)
+            }
+        }
+
+        fn child(parent: &mut P, token: &str, visit: Visit<'_>) -> Option<Result<Value, Error>> {
+            if Head::is_active(parent) && token == Head::IDENT {
+                Some(visit(&mut VariantPatch::<Head>(parent, PhantomData)))
+            } else {
+                Tail::child(parent, token, visit)
+            }
+        }
+    }
+
+    // the payload of the active variant `V`
+    struct VariantPatch<'a, V: Variant>(&'a mut V::Root, PhantomData<V>);
+
+    impl<V> Patch for VariantPatch<'_, V>
+    where
+        V: Variant + SizedStruct + PatchShape<V::Root, V::Shape>,
+        V::Fields: PatchFields<V::Root>,
+    {
+        fn to_value(&self) -> Result<Value, Error> {
+            <V as PatchShape<_, _>>::to_value(self.0)
+        }
+
+        fn set(&mut self, value: Value) -> Result<(), Error> {
+            *self.0 = <V as PatchShape<_, _>>::from_value(value)?;
+            Ok(())
+        }
+
+        fn child(&mut self, token: &str, visit: Visit<'_>) -> Option<Result<Value, Error>> {
+            V::Fields::child(self.0, token, visit)
+        }
+
+        fn is_object(&self) -> bool {
+            <V as PatchShape<_, _>>::IS_OBJECT
+        }
+    }
+}
+
+mod impls {
+    use std::{
+        collections::{BTreeMap, HashMap},
+        fmt::Display,
+        hash::{BuildHasher, Hash},
+        net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+        path::PathBuf,
+        str::FromStr,
+        time::Duration,
+    };
+
+    use serde_json::{Map, Value};
+
+    use crate::{Error, FromValue, Patch, Visit, index, invalid, not_found};
+
+    // values which are replaced as a whole, through their serde impls
+    macro_rules! leaves {
+        ($($t:ty),*) => {$(
+            impl Patch for $t {
+                fn to_value(&self) -> Result<Value, Error> {
+                    serde_json::to_value(self).map_err(|err| invalid(err.to_string()))
+                }
+
+                fn set(&mut self, value: Value) -> Result<(), Error> {
+                    *self = Self::from_value(value)?;
+                    Ok(())
+                }
+            }
+
+            impl FromValue for $t {
+                fn from_value(value: Value) -> Result<Self, Error> {
+                    serde_json::from_value(value).map_err(|err| invalid(err.to_string()))
+                }
+            }
+        )*};
+    }
+
+    leaves!(
+        u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize
+    );
+    leaves!(f32, f64, bool, char, (), String, PathBuf, Duration);
+    leaves!(IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr);
+
+    // `null` for `None`
+    impl<T: Patch + FromValue> Patch for Option<T> {
+        fn to_value(&self) -> Result<Value, Error> {
+            self.as_ref().map_or(Ok(Value::Null), T::to_value)
+        }
+
+        fn set(&mut self, value: Value) -> Result<(), Error> {
+            *self = Self::from_value(value)?;
+            Ok(())
+        }
+
+        fn child(&mut self, token: &str, visit: Visit<'_>) -> Option<Result<Value, Error>> {
+            self.as_mut()?.child(token, visit)
+        }
+
+        fn add(&mut self, token: &str, value: Value) -> Result<(), Error> {
+            self.as_mut()
+                .map_or_else(not_found, |inner| inner.add(token, value))
+        }
+
+        fn remove(&mut self, token: &str) -> Result<Value, Error> {
+            self.as_mut()
+                .map_or_else(not_found, |inner| inner.remove(token))
+        }
+
+        fn take(&mut self) -> Result<Value, Error> {
+            Option::take(self).map_or(Ok(Value::Null), |inner| inner.to_value())
+        }
+
+        fn is_object(&self) -> bool {
+            self.as_ref().is_some_and(T::is_object)
+        }
+
+        fn is_map(&self) -> bool {
+            self.as_ref().is_some_and(T::is_map)
+        }
+    }
+
+    impl<T: FromValue> FromValue for Option<T> {
+        fn from_value(value: Value) -> Result<Self, Error> {
+            match value {
+                Value::Null => Ok(None),
+                value => T::from_value(value).map(Some),
+            }
+        }
+    }
+
+    impl<T: Patch + ?Sized> Patch for Box<T> {
+        fn to_value(&self) -> Result<Value, Error> {
+            (**self).to_value()
+        }
+
+        fn set(&mut self, value: Value) -> Result<(), Error> {
+            (**self).set(value)
+        }
+
+        fn child(&mut self, token: &str, visit: Visit<'_>) -> Option<Result<Value, Error>> {
+            (**self).child(token, visit)
+        }
+
+        fn add(&mut self, token: &str, value: Value) -> Result<(), Error> {
+            (**self).add(token, value)
+        }
+
+        fn remove(&mut self, token: &str) -> Result<Value, Error> {
+            (**self).remove(token)
+        }
+
+        fn take(&mut self) -> Result<Value, Error> {
+            (**self).take()
+        }
+
+        fn is_object(&self) -> bool {
+            (**self).is_object()
+        }
+
+        fn is_map(&self) -> bool {
+            (**self).is_map()
+        }
+    }
+
+    impl<T: FromValue> FromValue for Box<T> {
+        fn from_value(value: Value) -> Result<Self, Error> {
+            T::from_value(value).map(Box::new)
+        }
+    }
+
+    // elements by their index, with `-` to append
+    impl<T: Patch + FromValue> Patch for Vec<T> {
+        fn to_value(&self) -> Result<Value, Error> {
+            let values = self
+                .iter()
+                .enumerate()
+                .map(|(i, value)| value.to_value().map_err(|err| err.at(&i.to_string())));
+            values.collect::<Result<_, _>>().map(Value::Array)
+        }
+
+        fn set(&mut self, value: Value) -> Result<(), Error> {
+            *self = Self::from_value(value)?;
+            Ok(())
+        }
+
+        fn child(&mut self, token: &str, visit: Visit<'_>) -> Option<Result<Value, Error>> {
+            let value = self.get_mut(index(token)?)?;
+            Some(visit(value))
+        }
+
+        fn add(&mut self, token: &str, value: Value) -> Result<(), Error> {
+            let index = match token {
+                "-" => self.len(),
+                token => index(token)
+                    .filter(|&i| i <= self.len())
+                    .map_or_else(not_found, Ok)?,
+            };
+            self.insert(index, T::from_value(value)?);
+            Ok(())
+        }
+
+        fn remove(&mut self, token: &str) -> Result<Value, Error> {
+            let index = index(token)
+                .filter(|&i| i < self.len())
+                .map_or_else(not_found, Ok)?;
+            Vec::remove(self, index).to_value()
+        }
+    }
+
+    impl<T: FromValue> FromValue for Vec<T> {
+        fn from_value(value: Value) -> Result<Self, Error> {
+            let Value::Array(values) = value else {
+                return Err(invalid(format!("expected an array, found {value}")));
+            };
+            let values = values
+                .into_iter()
+                .enumerate()
+                .map(|(i, value)| T::from_value(value).map_err(|err| err.at(&i.to_string())));
+            values.collect()
+        }
+    }
+
+    // entries by the `Display` and `FromStr` impls of their keys
+    macro_rules! maps {
+        ($($t:ident<K: $($bound:path),+ $(; $s:ident: $($s_bound:path),+)?>),*) => {$(
+            impl<K, V $(, $s)?> Patch for $t<K, V $(, $s)?>
+            where
+                K: FromStr + Display $(+ $bound)+,
+                V: Patch + FromValue,
+                $($s: $($s_bound +)+)?
+            {
+                fn to_value(&self) -> Result<Value, Error> {
+                    let mut entries = Map::new();
+                    for (key, value) in self {
+                        let key = key.to_string();
+                        let value = value.to_value().map_err(|err| err.at(&key))?;
+                        entries.insert(key, value);
+                    }
+                    Ok(Value::Object(entries))
+                }
+
+                fn set(&mut self, value: Value) -> Result<(), Error> {
+                    *self = Self::from_value(value)?;
+                    Ok(())
+                }
+
+                fn child(&mut self, token: &str, visit: Visit<'_>) -> Option<Result<Value, Error>> {
+                    let value = self.get_mut(&token.parse().ok()?)?;
+                    Some(visit(value))
+                }
+
+                fn add(&mut self, token: &str, value: Value) -> Result<(), Error> {
+                    self.insert(key(token)?, V::from_value(value)?);
+                    Ok(())
+                }
+
+                fn remove(&mut self, token: &str) -> Result<Value, Error> {
+                    let key = token.parse().map_or_else(|_| not_found(), Ok)?;
+                    $t::remove(self, &key).map_or_else(not_found, |value| value.to_value())
+                }
+
+                fn is_object(&self) -> bool {
+                    true
+                }
+
+                fn is_map(&self) -> bool {
+                    true
+                }
+            }
+
+            impl<K, V $(, $s)?> FromValue for $t<K, V $(, $s)?>
+            where
+                K: FromStr $(+ $bound)+,
+                V: FromValue,
+                $($s: $($s_bound +)+)?
+            {
+                fn from_value(value: Value) -> Result<Self, Error> {
+                    let Value::Object(entries) = value else {
+                        return Err(invalid(format!("expected an object, found {value}")));
+                    };
+                    let entries = entries.into_iter().map(|(token, value)| {
+                        let value = V::from_value(value).map_err(|err| err.at(&token))?;
+                        Ok((key(&token).map_err(|err| err.at(&token))?, value))
+                    });
+                    entries.collect()
+                }
+            }
+        )*};
+    }
+
+    maps!(BTreeMap<K: Ord>, HashMap<K: Eq, Hash; S: BuildHasher, Default>);
+
+    fn key<K: FromStr>(token: &str) -> Result<K, Error> {
+        token
+            .parse()
+            .map_err(|_| invalid(format!("invalid key `{token}`")))
+    }
+}
@@ -0,0 +1,260 @@
+use std::collections::BTreeMap;
+
+use reflector::Introspect;
+use reflector_patch::{Error, ErrorKind, Operation, apply, merge};
+use serde_json::{Value, json};
+
+#[derive(Clone, Debug, PartialEq, Introspect)]
+#[reflect(impl(reflector_patch::Patch))]
+pub struct Server {
+    host: String,
+    port: u16,
+}
+
+#[derive(Clone, Debug, PartialEq, Introspect)]
+#[reflect(impl(reflector_patch::Patch))]
+pub enum Mode {
+    Fast,
+    Slow { delay: u32 },
+    Fixed(u8, u8),
+}
+
+#[derive(Clone, Debug, PartialEq, Introspect)]
+pub struct Settings {
+    server: Server,
+    mode: Mode,
+    workers: Option<usize>,
+    routes: Vec<String>,
+    limits: BTreeMap<String, u32>,
+    backup: Option<Server>,
+}
+
+fn settings() -> Settings {
+    Settings {
+        server: Server {
+            host: String::from("localhost"),
+            port: 80,
+        },
+        mode: Mode::Slow { delay: 5 },
+        workers: Some(4),
+        routes: vec![String::from("/"), String::from("/api")],
+        limits: BTreeMap::from([(String::from("a"), 1), (String::from("b"), 2)]),
+        backup: None,
+    }
+}
+
+fn operations(operations: Value) -> Vec<Operation> {
+    serde_json::from_value(operations).unwrap()
+}
+
+fn error(path: &[&str], kind: ErrorKind) -> Error {
+    Error {
+        path: path.iter().map(|token| token.to_string()).collect(),
+        kind,
+    }
+}
+
+#[test]
+fn merge_patch() {
+    let mut value = settings();
+    merge(
+        &mut value,
+        json!({
+            "server": { "port": 8080 },
+            "mode": { "Slow": { "delay": 10 } },
+            "workers": null,
+            "routes": ["/health"],
+            "limits": { "a": null, "b": 3, "c": 4, "missing": null },
+            "backup": { "host": "backup", "port": 81 },
+        }),
+    )
+    .unwrap();
+
+    let mut expected = settings();
+    expected.server.port = 8080;
+    expected.mode = Mode::Slow { delay: 10 };
+    expected.workers = None;
+    expected.routes = vec![String::from("/health")];
+    expected.limits = BTreeMap::from([(String::from("b"), 3), (String::from("c"), 4)]);
+    expected.backup = Some(Server {
+        host: String::from("backup"),
+        port: 81,
+    });
+    assert_eq!(value, expected);
+
+    // other variants replace the whole enum
+    merge(&mut value, json!({ "mode": "Fast" })).unwrap();
+    assert_eq!(value.mode, Mode::Fast);
+    merge(&mut value, json!({ "mode": { "Fixed": [1, 2] } })).unwrap();
+    assert_eq!(value.mode, Mode::Fixed(1, 2));
+    merge(&mut value, json!({ "backup": { "port": 82 } })).unwrap();
+    assert_eq!(value.backup.as_ref().unwrap().port, 82);
+}
+
+#[test]
+fn merge_errors() {
+    let mut value = settings();
+    let mut check = |patch, expected| {
+        assert_eq!(merge(&mut value, patch), Err(expected));
+        assert_eq!(value, settings());
+    };
+
+    check(
+        json!({ "server": { "port": "80" } }),
+        error(
+            &["server", "port"],
+            ErrorKind::InvalidValue(String::from("invalid type: string \"80\", expected u16")),
+        ),
+    );
+    check(
+        json!({ "server": { "prot": 80 } }),
+        error(&["server", "prot"], ErrorKind::NotFound),
+    );
+    check(
+        json!({ "server": { "host": null } }),
+        error(&["server", "host"], ErrorKind::CannotRemove),
+    );
+    check(
+        json!({ "workers": 2, "mode": { "Medium": {} } }),
+        error(&["mode", "Medium"], ErrorKind::NotFound),
+    );
+    check(
+        json!({ "backup": { "host": "backup" } }),
+        error(
+            &["backup"],
+            ErrorKind::InvalidValue(String::from("missing field `port`")),
+        ),
+    );
+}
+
+#[test]
+fn json_patch() {
+    let mut value = settings();
+    apply(
+        &mut value,
+        &operations(json!([
+            { "op": "test", "path": "/server/host", "value": "localhost" },
+            { "op": "replace", "path": "/server/port", "value": 443 },
+            { "op": "add", "path": "/routes/-", "value": "/health" },
+            { "op": "add", "path": "/routes/0", "value": "/v2" },
+            { "op": "remove", "path": "/routes/2" },
+            { "op": "replace", "path": "/mode/Slow/delay", "value": 1 },
+            { "op": "remove", "path": "/workers" },
+            { "op": "add", "path": "/limits/c", "value": 3 },
+            { "op": "move", "from": "/limits/a", "path": "/limits/d" },
+            { "op": "copy", "from": "/server", "path": "/backup" },
+            { "op": "replace", "path": "/backup/host", "value": "backup" },
+        ])),
+    )
+    .unwrap();
+
+    let mut expected = settings();
+    expected.server.port = 443;
+    expected.routes = vec![
+        String::from("/v2"),
+        String::from("/"),
+        String::from("/health"),
+    ];
+    expected.mode = Mode::Slow { delay: 1 };
+    expected.workers = None;
+    expected.limits = BTreeMap::from([
+        (String::from("b"), 2),
+        (String::from("c"), 3),
+        (String::from("d"), 1),
+    ]);
+    expected.backup = Some(Server {
+        host: String::from("backup"),
+        port: 443,
+    });
+    assert_eq!(value, expected);
+
+    // the whole value, and switching variants
+    let mut value = settings();
+    let replaced = json!({
+        "server": { "host": "example.com", "port": 1 },
+        "mode": "Fast",
+        "workers": null,
+        "routes": [],
+        "limits": {},
+        "backup": null,
+    });
+    apply(
+        &mut value,
+        &operations(json!([
+            { "op": "replace", "path": "", "value": replaced },
+            { "op": "add", "path": "/mode/Fixed", "value": [3, 4] },
+            { "op": "test", "path": "/mode", "value": { "Fixed": [3, 4] } },
+            { "op": "test", "path": "/mode/Fixed/1", "value": 4 },
+        ])),
+    )
+    .unwrap();
+    assert_eq!(value.server.host, "example.com");
+    assert_eq!(value.mode, Mode::Fixed(3, 4));
+}
+
+#[test]
+fn json_patch_errors() {
+    let mut value = settings();
+    let mut check = |patch, expected| {
+        assert_eq!(apply(&mut value, &operations(patch)), Err(expected));
+        assert_eq!(value, settings());
+    };
+
+    check(
+        json!([
+            { "op": "replace", "path": "/server/port", "value": 443 },
+            { "op": "test", "path": "/server/port", "value": 80 },
+        ]),
+        error(
+            &["server", "port"],
+            ErrorKind::TestFailed {
+                expected: json!(80),
+                actual: json!(443),
+            },
+        ),
+    );
+    check(
+        json!([{ "op": "replace", "path": "/mode/Fast", "value": null }]),
+        error(&["mode", "Fast"], ErrorKind::NotFound),
+    );
+    check(
+        json!([{ "op": "add", "path": "/routes/3", "value": "/" }]),
+        error(&["routes", "3"], ErrorKind::NotFound),
+    );
+    check(
+        json!([{ "op": "add", "path": "/routes/-", "value": 1 }]),
+        error(
+            &["routes", "-"],
+            ErrorKind::InvalidValue(String::from("invalid type: integer `1`, expected a string")),
+        ),
+    );
+    check(
+        json!([{ "op": "remove", "path": "/server" }]),
+        error(&["server"], ErrorKind::CannotRemove),
+    );
+    check(
+        json!([{ "op": "move", "from": "/server", "path": "/server/host" }]),
+        error(
+            &[],
+            ErrorKind::InvalidPatch(String::from("a value can't be moved into itself")),
+        ),
+    );
+    check(
+        json!([{ "op": "remove", "path": "routes" }]),
+        error(
+            &[],
+            ErrorKind::InvalidPatch(String::from("`routes` is not a JSON pointer")),
+        ),
+    );
+}
+
+#[test]
+fn display() {
+    let mut value = settings();
+    let err = apply(
+        &mut value,
+        &operations(json!([{ "op": "add", "path": "/limits/a~1b/x", "value": 1 }])),
+    )
+    .unwrap_err();
+    assert_eq!(err.to_string(), "/limits/a~1b: path not found");
+}
@@ -0,0 +1,636 @@
+//! JSON Schema (draft 2020-12) for reflected types.
+//!
+//! The schemas describe exactly what `reflector-serde` produces: named structs are objects
+//! requiring all of their fields, newtype structs are their only field, other tuple structs are
+//! arrays, and enums are externally tagged, with unit variants as strings. Flattened fields are
+//! inlined into their parent, transparent structs are their inner field, and fields with an
+//! adapter are described by [`SchemaAs`].
+//!
+//! Structs and enums are added to `$defs` and referenced from there, so recursive types work.
+//! Nested types are described through [`JsonSchema`], which is implemented for std types and for
+//! reflected types marked `#[reflect(impl(reflector_schema::JsonSchema))]`.
+
+pub use serde_json::Value;
+
+pub use generator::SchemaGenerator;
+pub use reflect::ReflectSchema;
+
+/// The schema of any type which can be introspected, with the definitions of all nested structs
+/// and enums.
+pub fn schema_for<T: ReflectSchema>() -> Value {
+    let mut generator = SchemaGenerator::default();
+    let schema = T::schema(&mut generator);
+    generator.root(schema)
+}
+
+/// A type with a JSON representation.
+pub trait JsonSchema {
+    fn schema(generator: &mut SchemaGenerator) -> Value;
+}
+
+/// Implements [`JsonSchema`] for a reflected type through [`ReflectSchema`], with
+/// `#[reflect(impl(reflector_schema::JsonSchema))]`. Type parameters have to implement
+/// `JsonSchema`.
+#[macro_export]
+macro_rules! JsonSchema {
+    (
+        lifetimes [$($lifetime:lifetime),*]
+        types [$($param:ident),*]
+        impl [$($params:tt)*] $ty:ty
+        where [$($where:tt)*]
+    ) => {
+        impl<$($params)*> $crate::JsonSchema for $ty
+        where
+            $($where)*
+            $($param: $crate::JsonSchema,)*
+        {
+            fn schema(generator: &mut $crate::SchemaGenerator) -> $crate::Value {
+                <Self as $crate::ReflectSchema>::schema(generator)
+            }
+        }
+    };
+}
+
+/// Describes the representation of `T` used by an adapter of `reflector_serde::with`.
+pub trait SchemaAs<T: ?Sized> {
+    fn schema_as(generator: &mut SchemaGenerator) -> Value;
+}
+
+mod generator {
+    use std::{
+        any::type_name,
+        collections::{BTreeMap, HashMap},
+    };
+
+    use serde_json::{Map, Value, json};
+
+    use crate::JsonSchema;
+
+    /// Collects the definitions of the structs and enums referenced by a schema.
+    #[derive(Default)]
+    pub struct SchemaGenerator {
+        definitions: BTreeMap<String, Value>,
+        // definition names by type, since e.g. `A<u8>` and `A<String>` share their identifier
+        names: HashMap<&'static str, String>,
+    }
+
+    impl SchemaGenerator {
+        pub fn subschema<T: JsonSchema + ?Sized>(&mut self) -> Value {
+            T::schema(self)
+        }
+
+        /// Adds the schema of `T` to `$defs` as `name`, or as `name2`, `name3` and so on if the
+        /// name is taken by another type, and returns a reference to it.
+        pub fn definition<T: ?Sized>(
+            &mut self,
+            name: &str,
+            schema: impl FnOnce(&mut Self) -> Value,
+        ) -> Value {
+            if let Some(name) = self.names.get(type_name::<T>()) {
+                return reference(name);
+            }
+
+            let mut unique = name.to_owned();
+            for i in 2.. {
+                if !self.definitions.contains_key(&unique) {
+                    break;
+                }
+                unique = format!("{name}{i}");
+            }
+            self.names.insert(type_name::<T>(), unique.clone());
+            // reserved while the schema is generated, which may refer to itself
+            self.definitions.insert(unique.clone(), Value::Bool(true));
+            let schema = schema(self);
+            self.definitions.insert(unique.clone(), schema);
+            reference(&unique)
+        }
+
+        pub fn definitions(&self) -> &BTreeMap<String, Value> {
+            &self.definitions
+        }
+
+        /// Turns `schema` into a root schema, with the `$schema` keyword and all definitions
+        pub fn root(self, schema: Value) -> Value {
+            let mut root = Map::new();
+            root.insert(
+                "$schema".into(),
+                "https://json-schema.org/draft/2020-12/schema".into(),
+            );
+            match schema {
+                Value::Object(schema) => root.extend(schema),
+                schema => {
+                    root.insert("allOf".into(), json!([schema]));
+                }
+            }
+            if !self.definitions.is_empty() {
+                root.insert(
+                    "$defs".into(),
+                    Value::Object(Map::from_iter(self.definitions)),
+                );
+            }
+            Value::Object(root)
+        }
+    }
+
+    fn reference(name: &str) -> Value {
+        json!({ "$ref": format!("#/$defs/{name}") })
+    }
+}
+
+mod reflect {
+    use reflector::*;
+    use serde_json::{Map, Value, json};
+
+    use crate::{JsonSchema, SchemaAs, SchemaGenerator};
+
+    /// The schema of reflected types, implemented for any type which can be introspected and
+    /// whose fields implement [`JsonSchema`], or [`SchemaAs`] for their adapter.
+    pub trait ReflectSchema {
+        fn schema(generator: &mut SchemaGenerator) -> Value;
+    }
+
+    impl<T> ReflectSchema for T
+    where
+        T: Introspect,
+        T: SchemaKind<T::Kind>,
+    {
+        fn schema(generator: &mut SchemaGenerator) -> Value {
+            <T as SchemaKind<_>>::schema(generator)
+        }
+    }
+
+    pub trait SchemaKind<Kind> {
+        fn schema(generator: &mut SchemaGenerator) -> Value;
+    }
+
+    impl<T> SchemaKind<StructKind> for T
+    where
+        T: Struct<Root = T>,
+        T: SchemaRepr<T::Repr>,
+    {
+        fn schema(generator: &mut SchemaGenerator) -> Value {
+            generator.definition::<T>(T::IDENT, <T as SchemaRepr<_>>::schema)
+        }
+    }
+
+    // enum E { A, B(..), C { .. } }, as `{ "oneOf": ["A" or "B", { "B": .. }, { "C": .. }] }`
+    impl<T> SchemaKind<EnumKind> for T
+    where
+        T: Enum,
+        T::Variants: SchemaVariants,
+    {
+        fn schema(generator: &mut SchemaGenerator) -> Value {
+            generator.definition::<T>(T::IDENT, |generator| {
+                let (mut units, mut schemas) = (Vec::new(), Vec::new());
+                T::Variants::schemas(generator, &mut units, &mut schemas);
+                if !units.is_empty() {
+                    schemas.insert(0, json!({ "type": "string", "enum": units }));
+                }
+                match schemas.len() {
+                    // no value can be serialized
+                    0 => Value::Bool(false),
+                    1 => schemas.pop().unwrap(),
+                    _ => json!({ "oneOf": schemas }),
+                }
+            })
+        }
+    }
+
+    pub trait SchemaRepr<Repr> {
+        fn schema(generator: &mut SchemaGenerator) -> Value;
+    }
+
+    impl<T> SchemaRepr<PlainRepr> for T
+    where
+        T: Struct,
+        T: SchemaShape<T::Shape>,
+    {
+        fn schema(generator: &mut SchemaGenerator) -> Value {
+            <T as SchemaShape<_>>::schema(generator)
+        }
+    }
+
+    // #[reflect(transparent)] struct T(A, PhantomData<..>);
+    impl<T> SchemaRepr<TransparentRepr> for T
+    where
+        T: TransparentStruct,
+        T::Inner: SchemaField<<T::Inner as Field>::Repr>,
+    {
+        fn schema(generator: &mut SchemaGenerator) -> Value {
+            T::Inner::schema(generator)
+        }
+    }
+
+    // the payload of a struct or variant
+    pub trait SchemaShape<Shape> {
+        const IS_UNIT: bool = false;
+
+        fn schema(generator: &mut SchemaGenerator) -> Value;
+    }
+
+    // struct S; as `null`, enum E { S, .. } as `"S"`
+    impl<S: Struct> SchemaShape<UnitShape> for S {
+        const IS_UNIT: bool = true;
+
+        fn schema(_: &mut SchemaGenerator) -> Value {
+            json!({ "type": "null" })
+        }
+    }
+
+    // struct S(A); as `A`, and struct S(A, B, ..); as `[A, B, ..]`
+    impl<S> SchemaShape<TupleShape> for S
+    where
+        S: Struct<Fields: SchemaFields>,
+    {
+        fn schema(generator: &mut SchemaGenerator) -> Value {
+            let mut items = Vec::new();
+            S::Fields::items(generator, &mut items);
+            if items.len() == 1 {
+                return items.pop().unwrap();
+            }
+            json!({
+                "type": "array",
+                "prefixItems": items,
+                "items": false,
+                "minItems": items.len(),
+            })
+        }
+    }
+
+    // struct S { .. } as `{ .. }`, with the entries of flattened fields
+    impl<S> SchemaShape<NamedShape> for S
+    where
+        S: Struct<Fields: SchemaFields>,
+    {
+        fn schema(generator: &mut SchemaGenerator) -> Value {
+            let (mut properties, mut required) = (Map::new(), Vec::new());
+            S::Fields::properties(generator, &mut properties, &mut required);
+            json!({
+                "type": "object",
+                "properties": properties,
+                "required": required,
+            })
+        }
+    }
+
+    // helper trait implemented recursively for a list of fields, e.g `(Field0, (Field1, ()))`
+    pub trait SchemaFields {
+        fn items(generator: &mut SchemaGenerator, items: &mut Vec<Value>);
+        fn properties(
+            generator: &mut SchemaGenerator,
+            properties: &mut Map<String, Value>,
+            required: &mut Vec<Value>,
+        );
+    }
+    // end of the recursion
+    impl SchemaFields for () {
+        fn items(_: &mut SchemaGenerator, _: &mut Vec<Value>) {}
+        fn properties(_: &mut SchemaGenerator, _: &mut Map<String, Value>, _: &mut Vec<Value>) {}
+    }
+    impl<Head, Tail> SchemaFields for Cons<Head, Tail>
+    where
+        Head: Field + SchemaField<<Head as Field>::Repr>,
+        Tail: SchemaFields,
+    {
+        // fields marked `#[reflect(removed = ..)]` are no longer written
+        fn items(generator: &mut SchemaGenerator, items: &mut Vec<Value>) {
+            if Head::REMOVED.is_none() {
+                items.push(Head::schema(generator));
+            }
+            Tail::items(generator, items)
+        }
+
+        fn properties(
+            generator: &mut SchemaGenerator,
+            properties: &mut Map<String, Value>,
+            required: &mut Vec<Value>,
+        ) {
+            if Head::REMOVED.is_none() {
+                Head::properties(generator, properties, required);
+            }
+            Tail::properties(generator, properties, required)
+        }
+    }
+
+    // helper trait implemented for every field, depending on its representation
+    pub trait SchemaField<Repr>: Field {
+        fn schema(generator: &mut SchemaGenerator) -> Value;
+
+        fn properties(
+            generator: &mut SchemaGenerator,
+            properties: &mut Map<String, Value>,
+            required: &mut Vec<Value>,
+        ) {
+            let ident = Self::IDENT.unwrap();
+            properties.insert(ident.into(), Self::schema(generator));
+            required.push(ident.into());
+        }
+    }
+
+    impl<F> SchemaField<PlainRepr> for F
+    where
+        F: Field<Type: JsonSchema>,
+    {
+        fn schema(generator: &mut SchemaGenerator) -> Value {
+            generator.subschema::<F::Type>()
+        }
+    }
+
+    impl<F, A> SchemaField<WithRepr<A>> for F
+    where
+        F: Field,
+        A: SchemaAs<F::Type>,
+    {
+        fn schema(generator: &mut SchemaGenerator) -> Value {
+            A::schema_as(generator)
+        }
+    }
+
+    // the fields of the flattened struct become properties of the parent
+    impl<F, Inner> SchemaField<FlattenRepr> for F
+    where
+        F: Field<Type = Inner>,
+        Inner: Struct<Root = Inner, Shape = NamedShape, Fields: SchemaFields>,
+    {
+        fn schema(generator: &mut SchemaGenerator) -> Value {
+            <Inner as SchemaShape<NamedShape>>::schema(generator)
+        }
+
+        fn properties(
+            generator: &mut SchemaGenerator,
+            properties: &mut Map<String, Value>,
+            required: &mut Vec<Value>,
+        ) {
+            Inner::Fields::properties(generator, properties, required)
+        }
+    }
+
+    // helper trait implemented recursively for a list of variants, e.g `(Variant0, (Variant1, ()))`
+    pub trait SchemaVariants {
+        fn schemas(
+            generator: &mut SchemaGenerator,
+            units: &mut Vec<Value>,
+            schemas: &mut Vec<Value>,
+        );
+    }
+    // end of the recursion
+    impl SchemaVariants for () {
+        fn schemas(_: &mut SchemaGenerator, _: &mut Vec<Value>, _: &mut Vec<Value>) {}
+    }
+    impl<Head, Tail> SchemaVariants for Cons<Head, Tail>
+    where
+        Head: Variant + SchemaShape<Head::Shape>,
+        Tail: SchemaVariants,
+    {
+        fn schemas(
+            generator: &mut SchemaGenerator,
+            units: &mut Vec<Value>,
+            schemas: &mut Vec<Value>,
+        ) {
+            if <Head as SchemaShape<_>>::IS_UNIT {
+                units.push(Head::IDENT.into());
+            } else {
+                let payload = <Head as SchemaShape<_>>::schema(generator);
+                schemas.push(json!({
+                    "type": "object",
+                    "properties": { Head::IDENT: payload },
+                    "required": [Head::IDENT],
+                    "additionalProperties": false,
+                }));
+            }
+            Tail::schemas(generator, units, schemas)
+        }
+    }
+}
+
+// the adapters of `reflector_serde::with`
+mod adapters {
+    use reflector_serde::with::{Borrow, DisplayFromStr, Reflect, Same};
+    use serde_json::{Value, json};
+
+    use crate::{JsonSchema, ReflectSchema, SchemaAs, SchemaGenerator};
+
+    impl<T: JsonSchema + ?Sized> SchemaAs<T> for Same {
+        fn schema_as(generator: &mut SchemaGenerator) -> Value {
+            generator.subschema::<T>()
+        }
+    }
+
+    impl<T: ReflectSchema> SchemaAs<T> for Reflect {
+        fn schema_as(generator: &mut SchemaGenerator) -> Value {
+            T::schema(generator)
+        }
+    }
+
+    impl<T: ?Sized> SchemaAs<T> for DisplayFromStr {
+        fn schema_as(_: &mut SchemaGenerator) -> Value {
+            json!({ "type": "string" })
+        }
+    }
+
+    // borrowing doesn't change the representation
+    impl<T: JsonSchema + ?Sized> SchemaAs<T> for Borrow {
+        fn schema_as(generator: &mut SchemaGenerator) -> Value {
+            generator.subschema::<T>()
+        }
+    }
+}
+
+// the representation of std types by serde and serde_json
+mod impls {
+    use std::{
+        borrow::Cow,
+        cell::{Cell, RefCell},
+        collections::{BTreeMap, BTreeSet, HashMap, HashSet, LinkedList, VecDeque},
+        marker::PhantomData,
+        net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6},
+        path::{Path, PathBuf},
+        rc::Rc,
+        sync::Arc,
+        time::Duration,
+    };
+
+    use serde_json::{Value, json};
+
+    use crate::{JsonSchema, SchemaGenerator};
+
+    macro_rules! integers {
+        ($($t:ty),*) => {$(
+            impl JsonSchema for $t {
+                fn schema(_: &mut SchemaGenerator) -> Value {
+                    json!({ "type": "integer", "minimum": <$t>::MIN, "maximum": <$t>::MAX })
+                }
+            }
+        )*};
+    }
+
+    integers!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+    // too large for JSON numbers, so without bounds
+    impl JsonSchema for i128 {
+        fn schema(_: &mut SchemaGenerator) -> Value {
+            json!({ "type": "integer" })
+        }
+    }
+
+    impl JsonSchema for u128 {
+        fn schema(_: &mut SchemaGenerator) -> Value {
+            json!({ "type": "integer", "minimum": 0 })
+        }
+    }
+
+    macro_rules! simple {
+        ($($t:ty => $schema:tt),*) => {$(
+            impl JsonSchema for $t {
+                fn schema(_: &mut SchemaGenerator) -> Value {
+                    json!($schema)
+                }
+            }
+        )*};
+    }
+
+    simple!(
+        f32 => { "type": "number" },
+        f64 => { "type": "number" },
+        bool => { "type": "boolean" },
+        char => { "type": "string", "minLength": 1, "maxLength": 1 },
+        str => { "type": "string" },
+        String => { "type": "string" },
+        Path => { "type": "string" },
+        PathBuf => { "type": "string" },
+        () => { "type": "null" },
+        IpAddr => { "type": "string", "anyOf": [{ "format": "ipv4" }, { "format": "ipv6" }] },
+        Ipv4Addr => { "type": "string", "format": "ipv4" },
+        Ipv6Addr => { "type": "string", "format": "ipv6" },
+        SocketAddr => { "type": "string" },
+        SocketAddrV4 => { "type": "string" },
+        SocketAddrV6 => { "type": "string" }
+    );
+
+    // serde represents `Duration` as a struct
+    impl JsonSchema for Duration {
+        fn schema(generator: &mut SchemaGenerator) -> Value {
+            let (secs, nanos) = (u64::schema(generator), u32::schema(generator));
+            json!({
+                "type": "object",
+                "properties": { "secs": secs, "nanos": nanos },
+                "required": ["secs", "nanos"],
+            })
+        }
+    }
+
+    impl<T: ?Sized> JsonSchema for PhantomData<T> {
+        fn schema(_: &mut SchemaGenerator) -> Value {
+            json!({ "type": "null" })
+        }
+    }
+
+    // containers which are represented by their contents
+    macro_rules! forward {
+        ($($t:ty),*) => {$(
+            impl<T: JsonSchema + ?Sized> JsonSchema for $t {
+                fn schema(generator: &mut SchemaGenerator) -> Value {
+                    generator.subschema::<T>()
+                }
+            }
+        )*};
+    }
+
+    forward!(&T, &mut T, Box<T>, Rc<T>, Arc<T>, RefCell<T>);
+
+    impl<T: JsonSchema + Copy> JsonSchema for Cell<T> {
+        fn schema(generator: &mut SchemaGenerator) -> Value {
+            generator.subschema::<T>()
+        }
+    }
+
+    impl<T: JsonSchema + ToOwned + ?Sized> JsonSchema for Cow<'_, T> {
+        fn schema(generator: &mut SchemaGenerator) -> Value {
+            generator.subschema::<T>()
+        }
+    }
+
+    impl<T: JsonSchema> JsonSchema for Option<T> {
+        fn schema(generator: &mut SchemaGenerator) -> Value {
+            json!({ "anyOf": [generator.subschema::<T>(), { "type": "null" }] })
+        }
+    }
+
+    macro_rules! sequences {
+        ($($t:ty => $unique:literal),*) => {$(
+            impl<T: JsonSchema> JsonSchema for $t {
+                fn schema(generator: &mut SchemaGenerator) -> Value {
+                    let items = generator.subschema::<T>();
+                    match $unique {
+                        true => json!({ "type": "array", "items": items, "uniqueItems": true }),
+                        false => json!({ "type": "array", "items": items }),
+                    }
+                }
+            }
+        )*};
+    }
+
+    sequences!(
+        [T] => false,
+        Vec<T> => false,
+        VecDeque<T> => false,
+        LinkedList<T> => false,
+        BTreeSet<T> => true,
+        HashSet<T> => true
+    );
+
+    impl<T: JsonSchema, const N: usize> JsonSchema for [T; N] {
+        fn schema(generator: &mut SchemaGenerator) -> Value {
+            json!({
+                "type": "array",
+                "items": generator.subschema::<T>(),
+                "minItems": N,
+                "maxItems": N,
+            })
+        }
+    }
+
+    // keys are strings in JSON, whatever their type
+    impl<K, V: JsonSchema> JsonSchema for BTreeMap<K, V> {
+        fn schema(generator: &mut SchemaGenerator) -> Value {
+            json!({ "type": "object", "additionalProperties": generator.subschema::<V>() })
+        }
+    }
+
+    impl<K, V: JsonSchema, S> JsonSchema for HashMap<K, V, S> {
+        fn schema(generator: &mut SchemaGenerator) -> Value {
+            json!({ "type": "object", "additionalProperties": generator.subschema::<V>() })
+        }
+    }
+
+    macro_rules! tuples {
+        ($(($($t:ident),+)),*) => {$(
+            impl<$($t: JsonSchema),+> JsonSchema for ($($t,)+) {
+                fn schema(generator: &mut SchemaGenerator) -> Value {
+                    let items = [$(generator.subschema::<$t>()),+];
+                    json!({
+                        "type": "array",
+                        "prefixItems": items,
+                        "items": false,
+                        "minItems": items.len(),
+                    })
+                }
+            }
+        )*};
+    }
+
+    tuples!(
+        (A),
+        (A, B),
+        (A, B, C),
+        (A, B, C, D),
+        (A, B, C, D, E),
+        (A, B, C, D, E, F),
+        (A, B, C, D, E, F, G),
+        (A, B, C, D, E, F, G, H),
+        (A, B, C, D, E, F, G, H, I),
+        (A, B, C, D, E, F, G, H, I, J),
+        (A, B, C, D, E, F, G, H, I, J, K),
+        (A, B, C, D, E, F, G, H, I, J, K, L)
+    );
+}
@@ -0,0 +1,292 @@
+use std::collections::BTreeMap;
+
+use reflector::Introspect;
+use reflector_schema::schema_for;
+use reflector_serde::with;
+use serde_json::json;
+
+const DRAFT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+#[derive(Introspect)]
+#[reflect(impl(reflector_schema::JsonSchema))]
+pub struct Server {
+    host: String,
+    port: u16,
+}
+
+#[derive(Introspect)]
+#[reflect(impl(reflector_schema::JsonSchema))]
+pub enum Mode {
+    Fast,
+    Slow { delay: u32 },
+    Fixed(u8, u8),
+    Named(String),
+    Other,
+}
+
+#[derive(Introspect)]
+pub struct Settings {
+    server: Server,
+    mode: Mode,
+    workers: Option<usize>,
+    routes: Vec<String>,
+    limits: BTreeMap<String, f64>,
+}
+
+#[test]
+fn structs_and_enums() {
+    let u16 = json!({ "type": "integer", "minimum": 0, "maximum": 65535 });
+    let u8 = json!({ "type": "integer", "minimum": 0, "maximum": 255 });
+    assert_eq!(
+        schema_for::<Settings>(),
+        json!({
+            "$schema": DRAFT,
+            "$ref": "#/$defs/Settings",
+            "$defs": {
+                "Settings": {
+                    "type": "object",
+                    "properties": {
+                        "server": { "$ref": "#/$defs/Server" },
+                        "mode": { "$ref": "#/$defs/Mode" },
+                        "workers": {
+                            "anyOf": [
+                                { "type": "integer", "minimum": 0, "maximum": usize::MAX },
+                                { "type": "null" },
+                            ],
+                        },
+                        "routes": { "type": "array", "items": { "type": "string" } },
+                        "limits": {
+                            "type": "object",
+                            "additionalProperties": { "type": "number" },
+                        },
+                    },
+                    "required": ["server", "mode", "workers", "routes", "limits"],
+                },
+                "Server": {
+                    "type": "object",
+                    "properties": { "host": { "type": "string" }, "port": u16 },
+                    "required": ["host", "port"],
+                },
+                "Mode": {
+                    "oneOf": [
+                        { "type": "string", "enum": ["Fast", "Other"] },
+                        {
+                            "type": "object",
+                            "properties": {
+                                "Slow": {
+                                    "type": "object",
+                                    "properties": {
+                                        "delay": { "type": "integer", "minimum": 0, "maximum": u32::MAX },
+                                    },
+                                    "required": ["delay"],
+                                },
+                            },
+                            "required": ["Slow"],
+                            "additionalProperties": false,
+                        },
+                        {
+                            "type": "object",
+                            "properties": {
+                                "Fixed": {
+                                    "type": "array",
+                                    "prefixItems": [u8, u8],
+                                    "items": false,
+                                    "minItems": 2,
+                                },
+                            },
+                            "required": ["Fixed"],
+                            "additionalProperties": false,
+                        },
+                        {
+                            "type": "object",
+                            "properties": { "Named": { "type": "string" } },
+                            "required": ["Named"],
+                            "additionalProperties": false,
+                        },
+                    ],
+                },
+            },
+        })
+    );
+}
+
+#[derive(Introspect)]
+pub struct Unit;
+
+#[derive(Introspect)]
+pub struct Empty();
+
+#[derive(Introspect)]
+pub struct Newtype(bool);
+
+#[derive(Introspect)]
+pub enum Only {
+    A,
+    B,
+}
+
+#[test]
+fn shapes() {
+    assert_eq!(
+        schema_for::<Unit>()["$defs"]["Unit"],
+        json!({ "type": "null" })
+    );
+    assert_eq!(
+        schema_for::<Empty>()["$defs"]["Empty"],
+        json!({ "type": "array", "prefixItems": [], "items": false, "minItems": 0 })
+    );
+    assert_eq!(
+        schema_for::<Newtype>()["$defs"]["Newtype"],
+        json!({ "type": "boolean" })
+    );
+    assert_eq!(
+        schema_for::<Only>()["$defs"]["Only"],
+        json!({ "type": "string", "enum": ["A", "B"] })
+    );
+}
+
+#[derive(Introspect)]
+pub struct Page {
+    title: String,
+    #[reflect(flatten)]
+    meta: Meta,
+}
+
+#[derive(Introspect)]
+pub struct Meta {
+    author: String,
+    tags: Vec<String>,
+}
+
+#[derive(Introspect)]
+#[reflect(transparent)]
+pub struct Id(u32);
+
+#[derive(Introspect)]
+pub struct Record {
+    #[reflect(adapter = with::Reflect)]
+    id: Id,
+    #[reflect(adapter = with::DisplayFromStr)]
+    count: u64,
+    #[reflect(adapter = with::Reflect)]
+    page: Page,
+}
+
+#[test]
+fn flatten_transparent_and_adapters() {
+    assert_eq!(
+        schema_for::<Record>(),
+        json!({
+            "$schema": DRAFT,
+            "$ref": "#/$defs/Record",
+            "$defs": {
+                "Record": {
+                    "type": "object",
+                    "properties": {
+                        "id": { "$ref": "#/$defs/Id" },
+                        "count": { "type": "string" },
+                        "page": { "$ref": "#/$defs/Page" },
+                    },
+                    "required": ["id", "count", "page"],
+                },
+                "Id": { "type": "integer", "minimum": 0, "maximum": u32::MAX },
+                "Page": {
+                    "type": "object",
+                    "properties": {
+                        "title": { "type": "string" },
+                        "author": { "type": "string" },
+                        "tags": { "type": "array", "items": { "type": "string" } },
+                    },
+                    "required": ["title", "author", "tags"],
+                },
+            },
+        })
+    );
+}
+
+#[derive(Introspect)]
+#[reflect(impl(reflector_schema::JsonSchema))]
+pub struct Tree {
+    value: i8,
+    children: Vec<Tree>,
+}
+
+#[derive(Introspect)]
+#[reflect(impl(reflector_schema::JsonSchema))]
+pub struct Wrapper<T> {
+    value: T,
+}
+
+#[derive(Introspect)]
+pub struct Both {
+    a: Wrapper<bool>,
+    b: Wrapper<String>,
+}
+
+#[test]
+fn recursion_and_generics() {
+    assert_eq!(
+        schema_for::<Tree>(),
+        json!({
+            "$schema": DRAFT,
+            "$ref": "#/$defs/Tree",
+            "$defs": {
+                "Tree": {
+                    "type": "object",
+                    "properties": {
+                        "value": { "type": "integer", "minimum": -128, "maximum": 127 },
+                        "children": { "type": "array", "items": { "$ref": "#/$defs/Tree" } },
+                    },
+                    "required": ["value", "children"],
+                },
+            },
+        })
+    );
+
+    let schema = schema_for::<Both>();
+    assert_eq!(
+        schema["$defs"]["Both"]["properties"],
+        json!({
+            "a": { "$ref": "#/$defs/Wrapper" },
+            "b": { "$ref": "#/$defs/Wrapper2" },
+        })
+    );
+    assert_eq!(
+        schema["$defs"]["Wrapper2"]["properties"]["value"],
+        json!({ "type": "string" })
+    );
+}
+
+#[derive(Introspect)]
+#[reflect(version = 3)]
+pub struct Versioned {
+    a: u32,
+    #[reflect(since = 2, default)]
+    b: u32,
+    #[reflect(removed = 3)]
+    old: u32,
+}
+
+#[test]
+fn removed_fields() {
+    let value = Versioned { a: 1, b: 2, old: 3 };
+    assert_eq!(
+        serde_json::to_value(reflector_serde::ser::Reflect(&value)).unwrap(),
+        json!({ "a": 1, "b": 2 })
+    );
+    let u32 = json!({ "type": "integer", "minimum": 0, "maximum": u32::MAX });
+    assert_eq!(
+        schema_for::<Versioned>(),
+        json!({
+            "$schema": DRAFT,
+            "$ref": "#/$defs/Versioned",
+            "$defs": {
+                "Versioned": {
+                    "type": "object",
+                    "properties": { "a": u32, "b": u32 },
+                    "required": ["a", "b"],
+                },
+            },
+        })
+    );
+}
@@ -9,6 +9,11 @@ use serde::{
     de::{EnumAccess, Error, VariantAccess, Visitor},
 };
 
+use super::{
+    fields::{DeserializeField, DeserializeFields, FieldSeed},
+    named::MapSeed,
+};
+
 struct Discriminant<T>(usize, PhantomData<T>);
 
 struct VisitDiscriminant<T>(PhantomData<T>);
@@ -114,12 +119,12 @@ where
 // newtype variant
 impl<'de, T, V, InnerField> DeserializeVariant<'de, T, TupleShape, Cons<InnerField, ()>> for V
 where
-    InnerField: Field<Type: Sized + Deserialize<'de>>,
+    InnerField: Field + DeserializeField<'de, <InnerField as Field>::Repr>,
     V: Variant<Shape = TupleShape, Root = T> + SizedStruct<FieldTypes = Cons<InnerField::Type, ()>>,
     super::tuple::Visit<'de, V>: Visitor<'de, Value = T>,
 {
     fn deserialize<A: VariantAccess<'de>>(v: A) -> Result<T, A::Error> {
-        v.newtype_variant::<InnerField::Type>()
+        v.newtype_variant_seed(FieldSeed::<InnerField, InnerField::Repr>(PhantomData))
             .map(|inner| V::from_values(Cons(inner, ())))
     }
 }
@@ -136,11 +141,17 @@ where
 // struct variant
 impl<'de, T, V, Fields> DeserializeVariant<'de, T, NamedShape, Fields> for V
 where
-    V: Variant<Shape = NamedShape, Root = T, Fields: NamedFieldList> + SizedStruct,
+    V: Variant<Shape = NamedShape, Root = T, Fields: NamedFieldList + DeserializeFields<'de>>
+        + SizedStruct,
     super::named::Visit<'de, V>: Visitor<'de, Value = T>,
 {
     fn deserialize<A: VariantAccess<'de>>(v: A) -> Result<T, A::Error> {
-        v.struct_variant(V::Fields::NAMES, super::named::Visit::<V>(PhantomData))
+        let visit = super::named::Visit::<V>(PhantomData);
+        if <V::Fields as DeserializeFields>::FLATTEN {
+            v.newtype_variant_seed(MapSeed(visit))
+        } else {
+            v.struct_variant(V::Fields::NAMES, visit)
+        }
     }
 }
 
@@ -0,0 +1,234 @@
+use std::marker::PhantomData;
+
+use reflector::{Cons, Field, FlattenRepr, NamedShape, PlainRepr, SizedStruct, WithRepr};
+use serde::{
+    Deserialize, Deserializer,
+    de::{DeserializeSeed, Error, IgnoredAny, MapAccess, SeqAccess},
+};
+
+use crate::with::{DeserializeAs, Same};
+
+// helper trait implemented recursively for a list of fields, e.g `(Field0, (Field1, ()))`.
+// fields are addressed by an index, where the fields of a flattened field are counted
+// individually.
+pub trait DeserializeFields<'de> {
+    /// Partially deserialized fields
+    type State: Default;
+    type Values;
+
+    const LEN: usize;
+    const FLATTEN: bool;
+
+    fn position(name: &[u8]) -> Option<usize>;
+    fn deserialize<A: MapAccess<'de>>(
+        state: &mut Self::State,
+        idx: usize,
+        map: &mut A,
+    ) -> Result<(), A::Error>;
+    fn from_seq<A: SeqAccess<'de>>(seq: &mut A) -> Result<Self::Values, A::Error>;
+    fn finish<E: Error>(state: Self::State) -> Result<Self::Values, E>;
+}
+
+// end of the recursion, ignoring unknown fields
+impl<'de> DeserializeFields<'de> for () {
+    type State = ();
+    type Values = ();
+
+    const LEN: usize = 0;
+    const FLATTEN: bool = false;
+
+    fn position(_: &[u8]) -> Option<usize> {
+        None
+    }
+    fn deserialize<A: MapAccess<'de>>(_: &mut (), _: usize, map: &mut A) -> Result<(), A::Error> {
+        map.next_value::<IgnoredAny>()?;
+        Ok(())
+    }
+    fn from_seq<A: SeqAccess<'de>>(_: &mut A) -> Result<(), A::Error> {
+        Ok(())
+    }
+    fn finish<E: Error>(_: ()) -> Result<(), E> {
+        Ok(())
+    }
+}
+
+impl<'de, Head, Tail> DeserializeFields<'de> for Cons<Head, Tail>
+where
+    Head: Field + DeserializeField<'de, <Head as Field>::Repr>,
+    Tail: DeserializeFields<'de>,
+{
+    type State = Cons<Head::State, Tail::State>;
+    type Values = Cons<Head::Type, Tail::Values>;
+
+    const LEN: usize = Head::LEN + Tail::LEN;
+    const FLATTEN: bool = Head::FLATTEN || Tail::FLATTEN;
+
+    fn position(name: &[u8]) -> Option<usize> {
+        Head::position(name).or_else(|| Some(Head::LEN + Tail::position(name)?))
+    }
+    fn deserialize<A: MapAccess<'de>>(
+        state: &mut Self::State,
+        idx: usize,
+        map: &mut A,
+    ) -> Result<(), A::Error> {
+        match idx.checked_sub(Head::LEN) {
+            None => Head::deserialize(&mut state.0, idx, map),
+            Some(idx) => Tail::deserialize(&mut state.1, idx, map),
+        }
+    }
+    fn from_seq<A: SeqAccess<'de>>(seq: &mut A) -> Result<Self::Values, A::Error> {
+        Ok(Cons(Head::from_seq(seq)?, Tail::from_seq(seq)?))
+    }
+    fn finish<E: Error>(state: Self::State) -> Result<Self::Values, E> {
+        Ok(Cons(Head::finish(state.0)?, Tail::finish(state.1)?))
+    }
+}
+
+// helper trait implemented for every field, depending on its representation
+pub trait DeserializeField<'de, Repr>: Field<Type: Sized> {
+    type State: Default;
+
+    const LEN: usize;
+    const FLATTEN: bool;
+
+    fn position(name: &[u8]) -> Option<usize>;
+    fn deserialize<A: MapAccess<'de>>(
+        state: &mut Self::State,
+        idx: usize,
+        map: &mut A,
+    ) -> Result<(), A::Error>;
+    fn from_seq<A: SeqAccess<'de>>(seq: &mut A) -> Result<Self::Type, A::Error>;
+    fn finish<E: Error>(state: Self::State) -> Result<Self::Type, E>;
+    /// Deserializes the value of the field on its own
+    fn deserialize_value<D: Deserializer<'de>>(de: D) -> Result<Self::Type, D::Error>;
+}
+
+// a plain field is deserialized just like a field with the `Same` adapter
+impl<'de, F> DeserializeField<'de, PlainRepr> for F
+where
+    F: Field<Type: Deserialize<'de>>,
+{
+    type State = Option<F::Type>;
+
+    const LEN: usize = 1;
+    const FLATTEN: bool = false;
+
+    fn position(name: &[u8]) -> Option<usize> {
+        <F as DeserializeField<'de, WithRepr<Same>>>::position(name)
+    }
+    fn deserialize<A: MapAccess<'de>>(
+        state: &mut Self::State,
+        idx: usize,
+        map: &mut A,
+    ) -> Result<(), A::Error> {
+        <F as DeserializeField<'de, WithRepr<Same>>>::deserialize(state, idx, map)
+    }
+    fn from_seq<A: SeqAccess<'de>>(seq: &mut A) -> Result<Self::Type, A::Error> {
+        <F as DeserializeField<'de, WithRepr<Same>>>::from_seq(seq)
+    }
+    fn finish<E: Error>(state: Self::State) -> Result<Self::Type, E> {
+        <F as DeserializeField<'de, WithRepr<Same>>>::finish(state)
+    }
+    fn deserialize_value<D: Deserializer<'de>>(de: D) -> Result<Self::Type, D::Error> {
+        F::Type::deserialize(de)
+    }
+}
+
+impl<'de, F, A> DeserializeField<'de, WithRepr<A>> for F
+where
+    F: Field<Type: Sized>,
+    A: DeserializeAs<'de, F::Type>,
+{
+    type State = Option<F::Type>;
+
+    const LEN: usize = 1;
+    const FLATTEN: bool = false;
+
+    fn position(name: &[u8]) -> Option<usize> {
+        let matches = |ident: &str| ident.as_bytes() == name;
+        (matches(F::IDENT?) || F::ALIASES.iter().copied().any(matches)).then_some(0)
+    }
+    fn deserialize<M: MapAccess<'de>>(
+        state: &mut Self::State,
+        _: usize,
+        map: &mut M,
+    ) -> Result<(), M::Error> {
+        if state.is_some() {
+            return Err(M::Error::duplicate_field(F::IDENT.unwrap_or_default()));
+        }
+
+        *state = Some(map.next_value_seed(FieldSeed::<F, WithRepr<A>>(PhantomData))?);
+        Ok(())
+    }
+    fn from_seq<S: SeqAccess<'de>>(seq: &mut S) -> Result<Self::Type, S::Error> {
+        // unlike maps, sequences don't tell which fields of which version they contain
+        if F::SINCE > 0 || F::REMOVED.is_some() {
+            return Err(match F::IDENT {
+                Some(ident) => S::Error::custom(format_args!(
+                    "versioned field `{ident}` can't be deserialized from a sequence"
+                )),
+                None => S::Error::custom(format_args!(
+                    "versioned field {} can't be deserialized from a sequence",
+                    F::INDEX
+                )),
+            });
+        }
+        seq.next_element_seed(FieldSeed::<F, WithRepr<A>>(PhantomData))?
+            .or_else(F::default_value)
+            .ok_or_else(|| S::Error::custom("not enough items in sequence"))
+    }
+    fn finish<E: Error>(state: Self::State) -> Result<Self::Type, E> {
+        state
+            .or_else(F::default_value)
+            .ok_or_else(|| E::missing_field(F::IDENT.unwrap_or_default()))
+    }
+    fn deserialize_value<D: Deserializer<'de>>(de: D) -> Result<Self::Type, D::Error> {
+        A::deserialize_as(de)
+    }
+}
+
+impl<'de, F, Inner> DeserializeField<'de, FlattenRepr> for F
+where
+    F: Field<Type = Inner>,
+    Inner: SizedStruct<Root = Inner, Shape = NamedShape>,
+    Inner::Fields: DeserializeFields<'de, Values = Inner::FieldTypes>,
+{
+    type State = <Inner::Fields as DeserializeFields<'de>>::State;
+
+    const LEN: usize = Inner::Fields::LEN;
+    const FLATTEN: bool = true;
+
+    fn position(name: &[u8]) -> Option<usize> {
+        Inner::Fields::position(name)
+    }
+    fn deserialize<A: MapAccess<'de>>(
+        state: &mut Self::State,
+        idx: usize,
+        map: &mut A,
+    ) -> Result<(), A::Error> {
+        Inner::Fields::deserialize(state, idx, map)
+    }
+    fn from_seq<A: SeqAccess<'de>>(seq: &mut A) -> Result<Inner, A::Error> {
+        Inner::Fields::from_seq(seq).map(Inner::from_values)
+    }
+    fn finish<E: Error>(state: Self::State) -> Result<Inner, E> {
+        Inner::Fields::finish(state).map(Inner::from_values)
+    }
+    fn deserialize_value<D: Deserializer<'de>>(de: D) -> Result<Inner, D::Error> {
+        de.deserialize_map(super::named::Visit::<Inner>(PhantomData))
+    }
+}
+
+/// Deserializes a single field on its own, according to its representation
+pub struct FieldSeed<F, Repr>(pub PhantomData<(F, Repr)>);
+
+impl<'de, F, Repr> DeserializeSeed<'de> for FieldSeed<F, Repr>
+where
+    F: DeserializeField<'de, Repr>,
+{
+    type Value = F::Type;
+
+    fn deserialize<D: Deserializer<'de>>(self, de: D) -> Result<F::Type, D::Error> {
+        F::deserialize_value(de)
+    }
+}
@@ -1,4 +1,5 @@
 mod r#enum;
+mod fields;
 mod named;
 mod newtype;
 mod tuple;
@@ -7,14 +8,20 @@ mod unit;
 use std::marker::PhantomData;
 
 use reflector::{
-    Cons, Enum, EnumKind, Introspect, List, NamedFieldList, NamedShape, NamedStruct, Struct,
-    StructKind, TupleShape, UnitShape, VariantList,
+    Cons, Enum, EnumKind, Field, Introspect, List, NamedFieldList, NamedShape, NamedStruct,
+    PlainRepr, Struct, StructKind, TransparentRepr, TransparentStruct, TupleShape, UnitShape,
+    VariantList,
 };
 use serde::{Deserialize, Deserializer, de::Visitor};
 
+use self::fields::DeserializeField;
+
 trait DeserializeKind<'de, T, Kind>: Sized {
     fn deserialize<D: Deserializer<'de>>(de: D) -> Result<Self, D::Error>;
 }
+trait DeserializeRepr<'de, T, Repr>: Sized {
+    fn deserialize<D: Deserializer<'de>>(de: D) -> Result<Self, D::Error>;
+}
 trait DeserializeStruct<'de, T, Shape>: Sized {
     fn deserialize<D: Deserializer<'de>>(de: D) -> Result<Self, D::Error>;
 }
@@ -24,10 +31,10 @@ trait DeserializeTuple<'de, T, Fields>: Sized {
 
 impl<'de, T> DeserializeKind<'de, T, StructKind> for T
 where
-    T: Struct + DeserializeStruct<'de, T, T::Shape>,
+    T: Struct + DeserializeRepr<'de, T, T::Repr>,
 {
     fn deserialize<D: Deserializer<'de>>(de: D) -> Result<Self, D::Error> {
-        DeserializeStruct::deserialize(de)
+        DeserializeRepr::deserialize(de)
     }
 }
 impl<'de, T> DeserializeKind<'de, T, EnumKind> for T
@@ -44,13 +51,36 @@ where
     }
 }
 
+impl<'de, T> DeserializeRepr<'de, T, PlainRepr> for T
+where
+    T: Struct + DeserializeStruct<'de, T, T::Shape>,
+{
+    fn deserialize<D: Deserializer<'de>>(de: D) -> Result<Self, D::Error> {
+        DeserializeStruct::deserialize(de)
+    }
+}
+impl<'de, T> DeserializeRepr<'de, T, TransparentRepr> for T
+where
+    T: TransparentStruct<Root = T>,
+    T::Inner: DeserializeField<'de, <T::Inner as Field>::Repr>,
+{
+    fn deserialize<D: Deserializer<'de>>(de: D) -> Result<Self, D::Error> {
+        T::Inner::deserialize_value(de).map(T::from_inner)
+    }
+}
+
 impl<'de, T> DeserializeStruct<'de, T, NamedShape> for T
 where
-    T: NamedStruct,
+    T: NamedStruct<Fields: fields::DeserializeFields<'de>>,
     named::Visit<'de, T>: Visitor<'de, Value = T>,
 {
     fn deserialize<D: Deserializer<'de>>(de: D) -> Result<Self, D::Error> {
-        de.deserialize_struct(T::IDENT, T::Fields::NAMES, named::Visit::<T>(PhantomData))
+        let visit = named::Visit::<T>(PhantomData);
+        if <T::Fields as fields::DeserializeFields>::FLATTEN {
+            de.deserialize_map(visit)
+        } else {
+            de.deserialize_struct(T::IDENT, T::Fields::NAMES, visit)
+        }
     }
 }
 impl<'de, T> DeserializeStruct<'de, T, TupleShape> for T
@@ -99,6 +129,12 @@ where
     }
 }
 
+/// Deserializes `T` through reflection.
+///
+/// Fields marked `#[reflect(since = ..)]` or `#[reflect(removed = ..)]` can only be deserialized
+/// from maps, like JSON objects. Structs written as sequences, e.g. by bincode or postcard, don't
+/// record the version of the data, so which fields they contain is unknown and deserializing
+/// them fails. `reflector_binary` records the version instead.
 pub struct Reflect<T>(pub T);
 
 impl<'de, T> Deserialize<'de> for Reflect<T>
@@ -1,11 +1,13 @@
 use std::{fmt::Formatter, marker::PhantomData};
 
-use reflector::{Cons, NamedFieldList, NamedStruct, SizedStruct, Struct};
+use reflector::{SizedStruct, Struct};
 use serde::{
     Deserialize, Deserializer,
-    de::{Error, IgnoredAny, MapAccess, SeqAccess, Visitor},
+    de::{DeserializeSeed, Error, MapAccess, SeqAccess, Visitor},
 };
 
+use super::fields::DeserializeFields;
+
 struct FieldIndex<T> {
     idx: usize,
     _marker: PhantomData<T>,
@@ -15,7 +17,7 @@ struct VisitFieldIndex<T>(PhantomData<T>);
 impl<'de, T> Visitor<'de> for VisitFieldIndex<T>
 where
     T: Struct,
-    T::Fields: NamedFieldList,
+    T::Fields: DeserializeFields<'de>,
 {
     type Value = FieldIndex<T>;
 
@@ -46,10 +48,7 @@ where
         E: Error,
     {
         Ok(FieldIndex {
-            idx: T::Fields::NAMES
-                .iter()
-                .position(|name| name.as_bytes() == v)
-                .unwrap_or(usize::MAX),
+            idx: T::Fields::position(v).unwrap_or(usize::MAX),
             _marker: PhantomData,
         })
     }
@@ -58,7 +57,7 @@ where
 impl<'de, T> Deserialize<'de> for FieldIndex<T>
 where
     T: Struct,
-    T::Fields: NamedFieldList,
+    T::Fields: DeserializeFields<'de>,
 {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -74,9 +73,7 @@ pub struct Visit<'de, T>(pub PhantomData<(&'de (), T)>);
 
 impl<'de, T> Visitor<'de> for Visit<'de, T>
 where
-    T: NamedStruct + SizedStruct,
-    super::tuple::Visit<'de, T>: Visitor<'de, Value = T::Root>,
-    T::FieldTypes: Wrap<List: DeserializeFields<'de>>,
+    T: SizedStruct<Fields: DeserializeFields<'de, Values = T::FieldTypes>>,
 {
     type Value = T::Root;
 
@@ -85,89 +82,31 @@ where
         formatter.write_str(T::IDENT)
     }
 
-    fn visit_seq<A>(self, seq: A) -> Result<Self::Value, A::Error>
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
     where
         A: SeqAccess<'de>,
     {
-        super::tuple::Visit(PhantomData).visit_seq(seq)
+        T::Fields::from_seq(&mut seq).map(T::from_values)
     }
     fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
     where
         A: MapAccess<'de>,
     {
-        let mut fields = <T::FieldTypes as Wrap>::List::default();
+        let mut state = <T::Fields as DeserializeFields>::State::default();
         while let Some(key) = map.next_key::<FieldIndex<T>>()? {
-            fields.deserialize(key.idx, &mut map)?;
-        }
-        let fields = fields.unwrap_all()?;
-        Ok(T::from_values(fields))
-    }
-}
-
-trait DeserializeFields<'de> {
-    fn deserialize<A: MapAccess<'de>>(&mut self, idx: usize, map: &mut A) -> Result<(), A::Error>;
-}
-
-impl<'de> DeserializeFields<'de> for () {
-    fn deserialize<A: MapAccess<'de>>(&mut self, _: usize, map: &mut A) -> Result<(), A::Error> {
-        map.next_value::<IgnoredAny>()?;
-        Ok(())
-    }
-}
-
-impl<'de, Head, Tail> DeserializeFields<'de> for Cons<Option<Head>, Tail>
-where
-    Head: Deserialize<'de>,
-    Tail: DeserializeFields<'de>,
-{
-    fn deserialize<A: MapAccess<'de>>(&mut self, idx: usize, map: &mut A) -> Result<(), A::Error> {
-        if idx > 0 {
-            return Tail::deserialize(&mut self.1, idx - 1, map);
-        }
-        if self.0.is_some() {
-            return Err(A::Error::custom("duplicate field"));
+            T::Fields::deserialize(&mut state, key.idx, &mut map)?;
         }
-
-        self.0 = Some(map.next_value()?);
-        Ok(())
+        T::Fields::finish(state).map(T::from_values)
     }
 }
 
-trait Wrap {
-    type List: Unwrap<List = Self> + Default;
-}
-impl Wrap for () {
-    type List = ();
-}
-impl<Head, Tail> Wrap for Cons<Head, Tail>
-where
-    Tail: Wrap,
-{
-    type List = Cons<Option<Head>, Tail::List>;
-}
-
-trait Unwrap {
-    type List;
+// structs with flattened fields are deserialized from maps, like serde_derive does.
+pub struct MapSeed<V>(pub V);
 
-    fn unwrap_all<Error: serde::de::Error>(self) -> Result<Self::List, Error>;
-}
-impl Unwrap for () {
-    type List = ();
-
-    fn unwrap_all<Error>(self) -> Result<Self::List, Error> {
-        Ok(())
-    }
-}
-impl<Head, Tail> Unwrap for Cons<Option<Head>, Tail>
-where
-    Tail: Unwrap,
-{
-    type List = Cons<Head, Tail::List>;
+impl<'de, V: Visitor<'de>> DeserializeSeed<'de> for MapSeed<V> {
+    type Value = V::Value;
 
-    fn unwrap_all<Error: serde::de::Error>(self) -> Result<Self::List, Error> {
-        Ok(Cons(
-            self.0.ok_or_else(|| Error::custom("missing field"))?,
-            self.1.unwrap_all()?,
-        ))
+    fn deserialize<D: Deserializer<'de>>(self, de: D) -> Result<Self::Value, D::Error> {
+        de.deserialize_map(self.0)
     }
 }
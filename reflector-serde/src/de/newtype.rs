@@ -1,17 +1,19 @@
 use std::{fmt::Formatter, marker::PhantomData};
 
-use reflector::{Cons, SizedStruct};
+use reflector::{Cons, Field, SizedStruct};
 use serde::{
-    Deserialize, Deserializer,
+    Deserializer,
     de::{SeqAccess, Visitor},
 };
 
+use super::fields::DeserializeField;
+
 pub struct Visit<'de, T>(pub PhantomData<(&'de (), T)>);
 
 impl<'de, T, Inner> Visitor<'de> for Visit<'de, T>
 where
-    T: SizedStruct<FieldTypes = Cons<Inner, ()>>,
-    Inner: Deserialize<'de>,
+    T: SizedStruct<Fields = Cons<Inner, ()>, FieldTypes = Cons<Inner::Type, ()>>,
+    Inner: Field + DeserializeField<'de, <Inner as Field>::Repr>,
     super::tuple::Visit<'de, T>: Visitor<'de, Value = T::Root>,
 {
     type Value = T::Root;
@@ -22,7 +24,7 @@ where
     }
 
     fn visit_newtype_struct<D: Deserializer<'de>>(self, de: D) -> Result<Self::Value, D::Error> {
-        Ok(T::from_values(Cons(Inner::deserialize(de)?, ())))
+        Ok(T::from_values(Cons(Inner::deserialize_value(de)?, ())))
     }
 
     fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
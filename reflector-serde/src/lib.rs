@@ -1,6 +1,7 @@
 pub mod de;
 pub mod ser;
 mod visit;
+pub mod with;
 
 #[test]
 fn works() {
@@ -26,6 +27,7 @@ fn works() {
     struct B<'a>(i32, &'a str);
     roundtrip!(B(3, "hey"));
 
+    #[allow(clippy::enum_variant_names)]
     #[derive(PartialEq, Debug, Introspect)]
     enum C<'a> {
         A,
@@ -38,3 +40,515 @@ fn works() {
     roundtrip!(C::C(42, "hey"));
     roundtrip!(C::D { x: "hey" });
 }
+
+#[test]
+fn flatten() {
+    use reflector::Introspect;
+
+    macro_rules! roundtrip {
+        ($e:expr, $json:expr) => {{
+            let json = serde_json::to_string(&ser::Reflect(&$e)).unwrap();
+            assert_eq!(json, $json);
+            let back = serde_json::from_str::<de::Reflect<_>>(&json).unwrap().0;
+            assert_eq!($e, back);
+        }};
+    }
+
+    #[derive(PartialEq, Debug, Introspect)]
+    struct Meta {
+        id: u32,
+    }
+    #[derive(PartialEq, Debug, Introspect)]
+    struct Pagination {
+        page: u32,
+        #[reflect(flatten)]
+        meta: Meta,
+    }
+    #[derive(PartialEq, Debug, Introspect)]
+    struct Response {
+        #[reflect(flatten)]
+        pagination: Pagination,
+        items: Vec<u8>,
+    }
+    #[derive(PartialEq, Debug, Introspect)]
+    enum Event {
+        Created {
+            name: String,
+            #[reflect(flatten)]
+            meta: Meta,
+        },
+    }
+
+    let response = Response {
+        pagination: Pagination {
+            page: 1,
+            meta: Meta { id: 7 },
+        },
+        items: vec![1, 2],
+    };
+    roundtrip!(response, r#"{"page":1,"id":7,"items":[1,2]}"#);
+
+    let event = Event::Created {
+        name: "x".to_owned(),
+        meta: Meta { id: 3 },
+    };
+    roundtrip!(event, r#"{"Created":{"name":"x","id":3}}"#);
+
+    // unknown keys are ignored, the fields may come in any order
+    let json = r#"{"items":[],"extra":null,"id":2,"page":0}"#;
+    let back = serde_json::from_str::<de::Reflect<Response>>(json)
+        .unwrap()
+        .0;
+    assert_eq!(back.pagination.meta.id, 2);
+
+    let json = r#"{"page":0,"items":[]}"#;
+    let err = serde_json::from_str::<de::Reflect<Response>>(json)
+        .err()
+        .unwrap();
+    assert!(err.to_string().contains("missing field `id`"));
+}
+
+#[test]
+fn with() {
+    use std::{fmt, str::FromStr};
+
+    use reflector::Introspect;
+
+    // a type without serde impls
+    #[derive(PartialEq, Debug)]
+    struct Timestamp(u64);
+    impl fmt::Display for Timestamp {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "@{}", self.0)
+        }
+    }
+    impl FromStr for Timestamp {
+        type Err = &'static str;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let s = s.strip_prefix('@').ok_or("expected '@'")?;
+            s.parse().map(Timestamp).map_err(|_| "invalid timestamp")
+        }
+    }
+
+    mod hex {
+        use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+        pub fn serialize<S: Serializer>(value: &u32, s: S) -> Result<S::Ok, S::Error> {
+            s.serialize_str(&format!("{value:x}"))
+        }
+        pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<u32, D::Error> {
+            let s = <&str>::deserialize(d)?;
+            u32::from_str_radix(s, 16).map_err(D::Error::custom)
+        }
+    }
+
+    fn negated<S: serde::Serializer>(value: &i32, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_i32(-value)
+    }
+
+    #[derive(PartialEq, Debug, Introspect)]
+    struct Inner {
+        x: u8,
+    }
+    #[derive(PartialEq, Debug, Introspect)]
+    struct Id(#[reflect(adapter = with::DisplayFromStr)] Timestamp);
+    #[derive(PartialEq, Debug, Introspect)]
+    struct A<'a> {
+        #[reflect(adapter = with::DisplayFromStr)]
+        created: Timestamp,
+        #[reflect(with = hex)]
+        flags: u32,
+        #[reflect(serialize_with = "negated")]
+        neg: i32,
+        #[reflect(adapter = with::Reflect)]
+        inner: Inner,
+        #[reflect(adapter = with::Same)]
+        name: &'a str,
+        #[reflect(adapter = with::Reflect)]
+        id: Id,
+    }
+    #[derive(PartialEq, Debug, Introspect)]
+    enum E {
+        Tuple(#[reflect(adapter = with::DisplayFromStr)] Timestamp, u8),
+        Struct {
+            #[reflect(with = hex)]
+            flags: u32,
+        },
+    }
+
+    let a = A {
+        created: Timestamp(42),
+        flags: 255,
+        neg: 3,
+        inner: Inner { x: 1 },
+        name: "a",
+        id: Id(Timestamp(7)),
+    };
+    let json = serde_json::to_string(&ser::Reflect(&a)).unwrap();
+    assert_eq!(
+        json,
+        r#"{"created":"@42","flags":"ff","neg":-3,"inner":{"x":1},"name":"a","id":"@7"}"#
+    );
+    let back = serde_json::from_str::<de::Reflect<A>>(&json).unwrap().0;
+    assert_eq!(back, A { neg: -3, ..a });
+
+    let e = E::Tuple(Timestamp(1), 2);
+    let json = serde_json::to_string(&ser::Reflect(&e)).unwrap();
+    assert_eq!(json, r#"{"Tuple":["@1",2]}"#);
+    assert_eq!(serde_json::from_str::<de::Reflect<E>>(&json).unwrap().0, e);
+
+    let e = E::Struct { flags: 16 };
+    let json = serde_json::to_string(&ser::Reflect(&e)).unwrap();
+    assert_eq!(json, r#"{"Struct":{"flags":"10"}}"#);
+    assert_eq!(serde_json::from_str::<de::Reflect<E>>(&json).unwrap().0, e);
+
+    let json = r#"{"created":"42","flags":"0","neg":0,"inner":{"x":0},"name":"","id":"@0"}"#;
+    let err = serde_json::from_str::<de::Reflect<A>>(json).err().unwrap();
+    assert!(err.to_string().contains("expected '@'"));
+}
+
+#[test]
+fn local_aliases() {
+    use reflector::Introspect;
+
+    // the derive refers to `Result` and `Option` by their full paths
+    #[allow(dead_code)]
+    type Result<T> = std::result::Result<T, String>;
+    #[allow(dead_code)]
+    type Option = ();
+
+    mod octal {
+        use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+        pub fn serialize<S: Serializer>(value: &u32, s: S) -> Result<S::Ok, S::Error> {
+            s.serialize_str(&format!("{value:o}"))
+        }
+        pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<u32, D::Error> {
+            let s = <&str>::deserialize(d)?;
+            u32::from_str_radix(s, 8).map_err(D::Error::custom)
+        }
+    }
+
+    #[derive(PartialEq, Debug, Introspect)]
+    struct Mode {
+        #[reflect(with = octal)]
+        bits: u32,
+        #[reflect(default)]
+        name: String,
+    }
+
+    let mode = Mode {
+        bits: 0o644,
+        name: "file".into(),
+    };
+    let json = serde_json::to_string(&ser::Reflect(&mode)).unwrap();
+    assert_eq!(json, r#"{"bits":"644","name":"file"}"#);
+    let back = serde_json::from_str::<de::Reflect<Mode>>(r#"{"bits":"755"}"#).unwrap();
+    assert_eq!(
+        back.0,
+        Mode {
+            bits: 0o755,
+            name: String::new(),
+        }
+    );
+}
+
+#[test]
+fn borrow() {
+    use std::borrow::Cow;
+
+    use reflector::Introspect;
+    use serde::{
+        Deserialize,
+        de::value::{Error, MapDeserializer},
+    };
+
+    #[derive(PartialEq, Debug, Introspect)]
+    struct Line<'a> {
+        #[reflect(borrow)]
+        host: Cow<'a, str>,
+        #[reflect(borrow)]
+        message: Option<Cow<'a, str>>,
+        #[reflect(borrow)]
+        raw: &'a [u8],
+    }
+
+    let json = r#"{"host":"a","message":"hello","raw":"xyz"}"#;
+    let line = serde_json::from_str::<de::Reflect<Line>>(json).unwrap().0;
+    assert!(matches!(line.host, Cow::Borrowed("a")));
+    assert!(matches!(line.message, Some(Cow::Borrowed("hello"))));
+    assert_eq!(line.raw, b"xyz");
+
+    // serde_json can't borrow strings containing escape sequences
+    let json = r#"{"host":"a\"b","message":null,"raw":""}"#;
+    let line = serde_json::from_str::<de::Reflect<Line>>(json).unwrap().0;
+    assert!(matches!(line.host, Cow::Owned(ref host) if host == "a\"b"));
+    assert_eq!(line.message, None);
+
+    #[derive(PartialEq, Debug, Introspect)]
+    struct Owned<'a> {
+        #[reflect(borrow)]
+        host: Cow<'a, str>,
+        #[reflect(borrow)]
+        bytes: Cow<'a, [u8]>,
+    }
+
+    let owned = Owned {
+        host: "a".into(),
+        bytes: Cow::Borrowed(&[1, 2]),
+    };
+    let json = serde_json::to_string(&ser::Reflect(&owned)).unwrap();
+    assert_eq!(json, r#"{"host":"a","bytes":[1,2]}"#);
+    assert_eq!(
+        serde_json::from_str::<de::Reflect<Owned>>(&json).unwrap().0,
+        owned
+    );
+
+    // a format which never borrows
+    let entries = [("host", "a".to_owned()), ("bytes", "b".to_owned())];
+    let de = MapDeserializer::<_, Error>::new(entries.into_iter());
+    let owned = de::Reflect::<Owned>::deserialize(de).unwrap().0;
+    assert!(matches!(owned.host, Cow::Owned(ref host) if host == "a"));
+    assert!(matches!(owned.bytes, Cow::Owned(ref bytes) if bytes == b"b"));
+}
+
+#[test]
+fn transparent() {
+    use std::marker::PhantomData;
+
+    use reflector::Introspect;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(PartialEq, Debug, Introspect)]
+    #[reflect(transparent)]
+    struct Id(u32);
+    #[derive(PartialEq, Debug, Introspect)]
+    #[reflect(transparent)]
+    struct Name<T> {
+        marker: PhantomData<T>,
+        #[reflect(adapter = with::DisplayFromStr)]
+        value: u8,
+        unit: (),
+    }
+    #[derive(PartialEq, Debug, Introspect)]
+    struct Outer {
+        #[reflect(adapter = with::Reflect)]
+        id: Id,
+        #[reflect(adapter = with::Reflect)]
+        name: Name<bool>,
+    }
+
+    // serde_derive's equivalent
+    #[derive(Serialize, Deserialize)]
+    #[serde(transparent)]
+    struct SerdeId(u32);
+    #[derive(Serialize, Deserialize)]
+    struct SerdeOuter {
+        id: SerdeId,
+        name: String,
+    }
+
+    let outer = Outer {
+        id: Id(3),
+        name: Name {
+            marker: PhantomData,
+            value: 42,
+            unit: (),
+        },
+    };
+    let expected = SerdeOuter {
+        id: SerdeId(3),
+        name: "42".to_owned(),
+    };
+    let json = serde_json::to_string(&ser::Reflect(&outer)).unwrap();
+    assert_eq!(json, serde_json::to_string(&expected).unwrap());
+    assert_eq!(
+        serde_json::from_str::<de::Reflect<Outer>>(&json).unwrap().0,
+        outer
+    );
+
+    assert_eq!(serde_json::to_string(&ser::Reflect(&Id(7))).unwrap(), "7");
+    assert_eq!(
+        serde_json::from_str::<de::Reflect<Id>>("7").unwrap().0,
+        Id(7)
+    );
+}
+
+#[test]
+fn standalone() {
+    use std::rc::Rc;
+
+    use reflector::{Cons, Enum, Introspect, Variant};
+    use serde::Serialize;
+
+    #[derive(Introspect)]
+    enum Event {
+        Started { id: u32, name: &'static str },
+        Progress(u8),
+        Stopped,
+    }
+
+    // serializes the payload of the active variant, without knowing `Event`
+    trait Payload<Root> {
+        fn payload(root: &Root) -> Option<String>;
+    }
+    impl<Root> Payload<Root> for () {
+        fn payload(_: &Root) -> Option<String> {
+            None
+        }
+    }
+    impl<Root, Head, Tail> Payload<Root> for Cons<Head, Tail>
+    where
+        Head: Variant<Root = Root>,
+        for<'a> ser::ReflectFields<'a, Head>: Serialize,
+        Tail: Payload<Root>,
+    {
+        fn payload(root: &Root) -> Option<String> {
+            match ser::ReflectFields::<Head>::variant(root) {
+                Some(fields) => Some(serde_json::to_string(&fields).unwrap()),
+                None => Tail::payload(root),
+            }
+        }
+    }
+    fn payload<E: Enum<Variants: Payload<E>>>(e: &E) -> String {
+        E::Variants::payload(e).unwrap()
+    }
+
+    let started = Event::Started { id: 1, name: "a" };
+    assert_eq!(payload(&started), r#"{"id":1,"name":"a"}"#);
+    assert_eq!(payload(&Event::Progress(50)), "50");
+    assert_eq!(payload(&Event::Stopped), "null");
+
+    #[derive(Introspect)]
+    struct Config {
+        host: &'static str,
+        port: u16,
+        secret: &'static str,
+    }
+    let config = Config {
+        host: "localhost",
+        port: 80,
+        secret: "hunter2",
+    };
+    let projection = ser::ReflectFields::new(&config).only(&["port", "host"]);
+    assert_eq!(
+        serde_json::to_string(&projection).unwrap(),
+        r#"{"host":"localhost","port":80}"#
+    );
+
+    let json = serde_json::to_string(&ser::Reflect(&started)).unwrap();
+    assert_eq!(json, r#"{"Started":{"id":1,"name":"a"}}"#);
+    let owned = serde_json::to_string(&ser::ReflectOwned(started)).unwrap();
+    assert_eq!(owned, json);
+    let boxed = ser::ReflectDeref(Box::new(Event::Stopped));
+    assert_eq!(serde_json::to_string(&boxed).unwrap(), r#""Stopped""#);
+    let rc = ser::ReflectDeref(Rc::new(Event::Progress(1)));
+    assert_eq!(serde_json::to_string(&rc).unwrap(), r#"{"Progress":1}"#);
+    let mut config = config;
+    let mutable = ser::ReflectDeref(&mut config);
+    assert_eq!(
+        serde_json::to_string(&mutable).unwrap(),
+        r#"{"host":"localhost","port":80,"secret":"hunter2"}"#
+    );
+}
+
+#[test]
+fn versioning() {
+    use reflector::Introspect;
+
+    fn default_port() -> u16 {
+        80
+    }
+
+    #[derive(PartialEq, Debug, Introspect)]
+    #[reflect(version = 3)]
+    struct Server {
+        #[reflect(renamed_from = "hostname", renamed_from = "addr")]
+        host: String,
+        #[reflect(since = 2, default = default_port)]
+        port: u16,
+        #[reflect(removed = 3)]
+        legacy: bool,
+        #[reflect(default)]
+        tags: Vec<String>,
+    }
+
+    let server = Server {
+        host: "a".into(),
+        port: 8080,
+        legacy: false,
+        tags: vec![],
+    };
+    let json = serde_json::to_string(&ser::Reflect(&server)).unwrap();
+    assert_eq!(json, r#"{"host":"a","port":8080,"tags":[]}"#);
+    assert_eq!(
+        serde_json::from_str::<de::Reflect<Server>>(&json)
+            .unwrap()
+            .0,
+        server
+    );
+
+    // version 1 data, with a former name and the removed field
+    let old = serde_json::from_str::<de::Reflect<Server>>(r#"{"hostname":"b","legacy":true}"#)
+        .unwrap()
+        .0;
+    assert_eq!(
+        old,
+        Server {
+            host: "b".into(),
+            port: 80,
+            legacy: true,
+            tags: vec![],
+        }
+    );
+    assert!(serde_json::from_str::<de::Reflect<Server>>(r#"{"port":1}"#).is_err());
+
+    // sequences don't record the version, neither for old nor for current data
+    for json in [r#"["b",true]"#, r#"["a",8080,[]]"#] {
+        let err = serde_json::from_str::<de::Reflect<Server>>(json)
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("versioned field `port`"), "{err}");
+    }
+    #[derive(PartialEq, Debug, Introspect)]
+    #[reflect(version = 2)]
+    struct Point(i32, #[reflect(removed = 2)] i32);
+    let err = serde_json::from_str::<de::Reflect<Point>>("[1,2]")
+        .err()
+        .unwrap();
+    assert!(err.to_string().contains("versioned field 1"), "{err}");
+
+    // the fingerprint only depends on the layout
+    mod same {
+        fn default_port() -> u16 {
+            80
+        }
+        #[derive(reflector::Introspect)]
+        #[reflect(version = 3)]
+        pub struct Server {
+            #[reflect(renamed_from = "hostname", renamed_from = "addr")]
+            host: String,
+            #[reflect(since = 2, default = default_port)]
+            port: u16,
+            #[reflect(removed = 3)]
+            legacy: bool,
+            #[reflect(default)]
+            tags: Vec<String>,
+        }
+    }
+    mod changed {
+        #[derive(reflector::Introspect)]
+        #[reflect(version = 3)]
+        pub struct Server {
+            host: String,
+            port: u32,
+            #[reflect(removed = 3)]
+            legacy: bool,
+            tags: Vec<String>,
+        }
+    }
+    assert_eq!(Server::VERSION, 3);
+    assert_eq!(Server::FINGERPRINT, same::Server::FINGERPRINT);
+    assert_ne!(Server::FINGERPRINT, changed::Server::FINGERPRINT);
+}
@@ -1,13 +1,17 @@
+use std::{convert::Infallible, marker::PhantomData, ops::Deref};
+
 use reflector::{
-    Cons, Enum, EnumKind, Field, Introspect, NamedShape, Struct, StructKind, TupleShape, UnitShape,
-    Variant,
+    Cons, Enum, EnumKind, Field, Introspect, NamedShape, NamedStruct, PlainRepr, Struct,
+    StructKind, TransparentRepr, TransparentStruct, TupleShape, UnitShape, Variant,
 };
 use serde::ser::{
-    Serialize, SerializeStruct, SerializeStructVariant, SerializeTupleStruct,
+    Serialize, SerializeMap, SerializeStruct, SerializeStructVariant, SerializeTupleStruct,
     SerializeTupleVariant, Serializer,
 };
 
-use crate::visit::{FieldVisitor, Fields, VariantVisitor, Variants};
+use crate::visit::{
+    FieldValue, FieldVisitor, Fields, MapEntries, Only, VariantVisitor, Variants, VisitField,
+};
 
 pub struct Reflect<'a, T>(pub &'a T);
 
@@ -20,6 +24,122 @@ where
     }
 }
 
+/// Like [`Reflect`], but owning the value.
+pub struct ReflectOwned<T>(pub T);
+
+impl<T> Serialize for ReflectOwned<T>
+where
+    T: Introspect<Root = T> + Impl,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        T::serialize(&self.0, serializer)
+    }
+}
+
+/// Like [`Reflect`], but for any pointer to the value, e.g. `Box<T>`, `Rc<T>` or `&mut T`.
+pub struct ReflectDeref<P>(pub P);
+
+impl<P> Serialize for ReflectDeref<P>
+where
+    P: Deref<Target: Sized + Introspect<Root = P::Target> + Impl>,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        P::Target::serialize(&*self.0, serializer)
+    }
+}
+
+/// Serializes just the fields of `T` as a struct named `T::IDENT`, where `T` may also be an enum
+/// variant. With [`ReflectFields::only`], just a subset of the fields is serialized.
+pub struct ReflectFields<'a, T: Struct> {
+    root: &'a T::Root,
+    only: Option<&'a [&'a str]>,
+    _marker: PhantomData<T>,
+}
+
+impl<'a, T: Struct<Root = T>> ReflectFields<'a, T> {
+    pub fn new(root: &'a T) -> Self {
+        Self {
+            root,
+            only: None,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, T: Variant> ReflectFields<'a, T> {
+    /// Returns `None` if the variant `T` is not active
+    pub fn variant(root: &'a T::Root) -> Option<Self> {
+        T::is_active(root).then_some(Self {
+            root,
+            only: None,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<'a, T: NamedStruct> ReflectFields<'a, T> {
+    /// Only serializes the fields with the given names, skipping all others.
+    pub fn only(self, names: &'a [&'a str]) -> Self {
+        Self {
+            only: Some(names),
+            ..self
+        }
+    }
+}
+
+impl<T: Struct> Serialize for ReflectFields<'_, T>
+where
+    T: ImplStruct<T::Root, StructKind, T::Shape>,
+    T::Fields: Fields<T::Root>,
+{
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        let Some(names) = self.only else {
+            return <T as ImplStruct<_, _, _>>::serialize(self.root, s);
+        };
+        let selected = |ident: Option<&str>| ident.is_some_and(|ident| names.contains(&ident));
+
+        struct Count(usize);
+        impl FieldVisitor for Count {
+            type Error = Infallible;
+
+            fn visit<F, V>(self, _: &V) -> Result<Self, Self::Error>
+            where
+                F: Field,
+                V: Serialize + ?Sized,
+            {
+                Ok(Count(self.0 + 1))
+            }
+        }
+
+        struct Visit<S>(S);
+        impl<S: SerializeStruct> FieldVisitor for Visit<S> {
+            type Error = S::Error;
+
+            fn visit<F, V>(mut self, value: &V) -> Result<Self, Self::Error>
+            where
+                F: Field,
+                V: Serialize + ?Sized,
+            {
+                self.0.serialize_field(F::IDENT.unwrap(), value)?;
+                Ok(self)
+            }
+
+            fn skip<F: Field>(mut self) -> Result<Self, Self::Error> {
+                self.0.skip_field(F::IDENT.unwrap())?;
+                Ok(self)
+            }
+        }
+
+        let Ok(Only(_, Count(len))) = T::Fields::for_each(self.root, Only(selected, Count(0)));
+        if T::Fields::FLATTEN {
+            let visit = Only(selected, MapEntries(s.serialize_map(Some(len))?));
+            return T::Fields::for_each(self.root, visit)?.1.0.end();
+        }
+        let visit = Only(selected, Visit(s.serialize_struct(T::IDENT, len)?));
+        T::Fields::for_each(self.root, visit)?.1.0.end()
+    }
+}
+
 pub trait Impl: Introspect + ImplKind<Self::Root, Self::Kind> {}
 
 impl<T> Impl for T where T: Introspect + ImplKind<T::Root, T::Kind> {}
@@ -28,7 +148,11 @@ pub trait ImplKind<Root, Kind> {
     fn serialize<S: Serializer>(root: &Root, s: S) -> Result<S::Ok, S::Error>;
 }
 
-trait ImplStruct<Root, RootKind, Shape> {
+trait ImplRepr<Root, Repr> {
+    fn serialize<S: Serializer>(root: &Root, s: S) -> Result<S::Ok, S::Error>;
+}
+
+pub trait ImplStruct<Root, RootKind, Shape> {
     fn serialize<S: Serializer>(root: &Root, s: S) -> Result<S::Ok, S::Error>;
 }
 
@@ -37,6 +161,15 @@ trait ImplTuple<Root, RootKind, Fields> {
 }
 
 impl<I: Struct> ImplKind<I::Root, StructKind> for I
+where
+    I: ImplRepr<I::Root, I::Repr>,
+{
+    fn serialize<S: Serializer>(root: &I::Root, s: S) -> Result<S::Ok, S::Error> {
+        <I as ImplRepr<_, _>>::serialize(root, s)
+    }
+}
+
+impl<I: Struct> ImplRepr<I::Root, PlainRepr> for I
 where
     I: ImplStruct<I::Root, <I::Root as Introspect>::Kind, I::Shape>,
 {
@@ -45,6 +178,16 @@ where
     }
 }
 
+// #[reflect(transparent)] struct I(A, PhantomData<..>);
+impl<I: TransparentStruct> ImplRepr<I::Root, TransparentRepr> for I
+where
+    I::Inner: VisitField<<I::Inner as Field>::Repr>,
+{
+    fn serialize<S: Serializer>(root: &I::Root, s: S) -> Result<S::Ok, S::Error> {
+        I::Inner::serialize(I::Inner::try_get_ref(root).unwrap(), s)
+    }
+}
+
 // struct I;
 impl<I: Struct> ImplStruct<I::Root, StructKind, UnitShape> for I {
     fn serialize<S: Serializer>(_: &I::Root, s: S) -> Result<S::Ok, S::Error> {
@@ -65,13 +208,18 @@ where
     I::Fields: Fields<I::Root>,
 {
     fn serialize<S: Serializer>(root: &I::Root, s: S) -> Result<S::Ok, S::Error> {
+        if I::Fields::FLATTEN {
+            return FlatMap::<I>(root, PhantomData).serialize(s);
+        }
+
         struct Visit<S>(S);
-        impl<Root, S: SerializeStruct> FieldVisitor<Root> for Visit<S> {
+        impl<S: SerializeStruct> FieldVisitor for Visit<S> {
             type Error = S::Error;
 
-            fn visit<F>(mut self, value: &F::Type) -> Result<Self, Self::Error>
+            fn visit<F, T>(mut self, value: &T) -> Result<Self, Self::Error>
             where
-                F: Field<Root = Root, Type: Serialize>,
+                F: Field,
+                T: Serialize + ?Sized,
             {
                 self.0.serialize_field(F::IDENT.unwrap(), value)?;
                 Ok(self)
@@ -89,13 +237,19 @@ where
     I::Fields: Fields<I::Root>,
 {
     fn serialize<S: Serializer>(root: &I::Root, s: S) -> Result<S::Ok, S::Error> {
+        if I::Fields::FLATTEN {
+            let map = FlatMap::<I>(root, PhantomData);
+            return s.serialize_newtype_variant(I::Root::IDENT, I::INDEX, I::IDENT, &map);
+        }
+
         struct Visit<S>(S);
-        impl<Root, S: SerializeStructVariant> FieldVisitor<Root> for Visit<S> {
+        impl<S: SerializeStructVariant> FieldVisitor for Visit<S> {
             type Error = S::Error;
 
-            fn visit<F>(mut self, value: &F::Type) -> Result<Self, Self::Error>
+            fn visit<F, T>(mut self, value: &T) -> Result<Self, Self::Error>
             where
-                F: Field<Root = Root, Type: Serialize>,
+                F: Field,
+                T: Serialize + ?Sized,
             {
                 self.0.serialize_field(F::IDENT.unwrap(), value)?;
                 Ok(self)
@@ -112,6 +266,21 @@ where
     }
 }
 
+// struct I { .., #[reflect(flatten)] .. }
+// enum Root { I { .., #[reflect(flatten)] .. }, .. }
+// structs with flattened fields are serialized as maps, like serde_derive does.
+struct FlatMap<'a, I: Struct>(&'a I::Root, PhantomData<I>);
+
+impl<I: Struct> Serialize for FlatMap<'_, I>
+where
+    I::Fields: Fields<I::Root>,
+{
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        let visit = MapEntries(s.serialize_map(Some(I::Fields::LEN))?);
+        I::Fields::for_each(self.0, visit)?.0.end()
+    }
+}
+
 // enum I { .. }
 impl<I: Enum> ImplKind<I::Root, EnumKind> for I
 where
@@ -139,9 +308,9 @@ where
 }
 
 // struct I(..);
-impl<I: Struct> ImplStruct<I::Root, <I::Root as Introspect>::Kind, TupleShape> for I
+impl<I: Struct, K> ImplStruct<I::Root, K, TupleShape> for I
 where
-    I: ImplTuple<I::Root, <I::Root as Introspect>::Kind, I::Fields>,
+    I: ImplTuple<I::Root, K, I::Fields>,
 {
     fn serialize<S: Serializer>(root: &I::Root, s: S) -> Result<S::Ok, S::Error> {
         <I as ImplTuple<_, _, _>>::serialize(root, s)
@@ -164,25 +333,24 @@ impl<I: Variant> ImplTuple<I::Root, EnumKind, ()> for I {
 }
 
 // struct I(A);
-impl<I: Struct, A: Field<Root = I::Root, Type: Serialize>>
-    ImplTuple<I::Root, StructKind, Cons<A, ()>> for I
+impl<I: Struct, A> ImplTuple<I::Root, StructKind, Cons<A, ()>> for I
+where
+    A: Field<Root = I::Root> + VisitField<<A as Field>::Repr>,
 {
     fn serialize<S: Serializer>(root: &I::Root, s: S) -> Result<S::Ok, S::Error> {
-        s.serialize_newtype_struct(I::IDENT, A::try_get_ref(root).unwrap())
+        let value = FieldValue::<A>(A::try_get_ref(root).unwrap());
+        s.serialize_newtype_struct(I::IDENT, &value)
     }
 }
 
 // enum Root { I(A), .. };
-impl<I: Variant, A: Field<Root = I::Root, Type: Serialize>>
-    ImplTuple<I::Root, EnumKind, Cons<A, ()>> for I
+impl<I: Variant, A> ImplTuple<I::Root, EnumKind, Cons<A, ()>> for I
+where
+    A: Field<Root = I::Root> + VisitField<<A as Field>::Repr>,
 {
     fn serialize<S: Serializer>(root: &I::Root, s: S) -> Result<S::Ok, S::Error> {
-        s.serialize_newtype_variant(
-            I::Root::IDENT,
-            I::INDEX,
-            I::IDENT,
-            A::try_get_ref(root).unwrap(),
-        )
+        let value = FieldValue::<A>(A::try_get_ref(root).unwrap());
+        s.serialize_newtype_variant(I::Root::IDENT, I::INDEX, I::IDENT, &value)
     }
 }
 
@@ -194,12 +362,13 @@ where
     fn serialize<S: Serializer>(root: &I::Root, s: S) -> Result<S::Ok, S::Error> {
         struct Visit<S>(S);
 
-        impl<Root, S: SerializeTupleStruct> FieldVisitor<Root> for Visit<S> {
+        impl<S: SerializeTupleStruct> FieldVisitor for Visit<S> {
             type Error = S::Error;
 
-            fn visit<F>(mut self, value: &F::Type) -> Result<Self, Self::Error>
+            fn visit<F, T>(mut self, value: &T) -> Result<Self, Self::Error>
             where
-                F: Field<Root = Root, Type: Serialize>,
+                F: Field,
+                T: Serialize + ?Sized,
             {
                 self.0.serialize_field(value)?;
                 Ok(self)
@@ -219,12 +388,13 @@ where
     fn serialize<S: Serializer>(root: &I::Root, s: S) -> Result<S::Ok, S::Error> {
         struct Visit<S>(S);
 
-        impl<Root, S: SerializeTupleVariant> FieldVisitor<Root> for Visit<S> {
+        impl<S: SerializeTupleVariant> FieldVisitor for Visit<S> {
             type Error = S::Error;
 
-            fn visit<F>(mut self, value: &F::Type) -> Result<Self, Self::Error>
+            fn visit<F, T>(mut self, value: &T) -> Result<Self, Self::Error>
             where
-                F: Field<Root = Root, Type: Serialize>,
+                F: Field,
+                T: Serialize + ?Sized,
             {
                 self.0.serialize_field(value)?;
                 Ok(self)
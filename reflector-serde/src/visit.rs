@@ -1,20 +1,34 @@
-use reflector::{Cons, Field, Variant};
-use serde::Serialize;
+use std::marker::PhantomData;
 
-use crate::ser::Impl;
+use reflector::{Cons, Field, FlattenRepr, NamedShape, PlainRepr, Struct, Variant, WithRepr};
+use serde::{Serialize, Serializer, ser::SerializeMap};
 
-pub trait FieldVisitor<Root>: Sized {
+use crate::{
+    ser::Impl,
+    with::{SerializeAs, SerializeWith},
+};
+
+pub trait FieldVisitor: Sized {
     type Error;
 
-    fn visit<F>(self, value: &F::Type) -> Result<Self, Self::Error>
+    fn visit<F, T>(self, value: &T) -> Result<Self, Self::Error>
     where
-        F: Field<Root = Root, Type: Serialize>;
+        F: Field,
+        T: Serialize + ?Sized;
+
+    /// Called instead of `visit` for fields which are not serialized
+    fn skip<F: Field>(self) -> Result<Self, Self::Error> {
+        Ok(self)
+    }
 }
 
 pub trait Fields<Root> {
+    /// Number of fields, where the fields of a flattened field are counted individually
     const LEN: usize = 0;
+    /// Whether any of the fields is flattened
+    const FLATTEN: bool = false;
 
-    fn for_each<V: FieldVisitor<Root>>(_root: &Root, visit: V) -> Result<V, V::Error> {
+    fn for_each<V: FieldVisitor>(_root: &Root, visit: V) -> Result<V, V::Error> {
         Ok(visit)
     }
 }
@@ -23,17 +37,137 @@ impl<Root> Fields<Root> for () {}
 
 impl<Root, Head, Tail> Fields<Root> for Cons<Head, Tail>
 where
-    Head: Field<Root = Root, Type: Serialize>,
+    Head: Field<Root = Root> + VisitField<<Head as Field>::Repr>,
     Tail: Fields<Root>,
 {
-    const LEN: usize = 1 + Tail::LEN;
+    // fields marked `#[reflect(removed = ..)]` are no longer written
+    const LEN: usize = match Head::REMOVED {
+        None => Head::LEN + Tail::LEN,
+        Some(_) => Tail::LEN,
+    };
+    const FLATTEN: bool = Head::FLATTEN || Tail::FLATTEN;
 
-    fn for_each<V: FieldVisitor<Root>>(root: &Root, visit: V) -> Result<V, V::Error> {
-        let visit = visit.visit::<Head>(Head::try_get_ref(root).unwrap())?;
+    fn for_each<V: FieldVisitor>(root: &Root, visit: V) -> Result<V, V::Error> {
+        let visit = match Head::REMOVED {
+            None => Head::visit(Head::try_get_ref(root).unwrap(), visit)?,
+            Some(_) => visit.skip::<Head>()?,
+        };
         Tail::for_each(root, visit)
     }
 }
 
+// helper trait implemented for every field, depending on its representation
+pub trait VisitField<Repr>: Field {
+    const LEN: usize;
+    const FLATTEN: bool;
+
+    fn visit<V: FieldVisitor>(value: &Self::Type, visit: V) -> Result<V, V::Error>;
+    /// Serializes the value of the field on its own
+    fn serialize<S: Serializer>(value: &Self::Type, s: S) -> Result<S::Ok, S::Error>;
+}
+
+impl<F> VisitField<PlainRepr> for F
+where
+    F: Field<Type: Serialize>,
+{
+    const LEN: usize = 1;
+    const FLATTEN: bool = false;
+
+    fn visit<V: FieldVisitor>(value: &Self::Type, visit: V) -> Result<V, V::Error> {
+        visit.visit::<F, _>(value)
+    }
+    fn serialize<S: Serializer>(value: &Self::Type, s: S) -> Result<S::Ok, S::Error> {
+        value.serialize(s)
+    }
+}
+
+impl<F, A> VisitField<WithRepr<A>> for F
+where
+    F: Field,
+    A: SerializeAs<F::Type>,
+{
+    const LEN: usize = 1;
+    const FLATTEN: bool = false;
+
+    fn visit<V: FieldVisitor>(value: &Self::Type, visit: V) -> Result<V, V::Error> {
+        visit.visit::<F, _>(&SerializeWith::<A, _>(value, PhantomData))
+    }
+    fn serialize<S: Serializer>(value: &Self::Type, s: S) -> Result<S::Ok, S::Error> {
+        A::serialize_as(value, s)
+    }
+}
+
+impl<F, Inner> VisitField<FlattenRepr> for F
+where
+    F: Field<Type = Inner>,
+    Inner: Struct<Root = Inner, Shape = NamedShape, Fields: Fields<Inner>>,
+{
+    const LEN: usize = Inner::Fields::LEN;
+    const FLATTEN: bool = true;
+
+    fn visit<V: FieldVisitor>(value: &Inner, visit: V) -> Result<V, V::Error> {
+        Inner::Fields::for_each(value, visit)
+    }
+    fn serialize<S: Serializer>(value: &Inner, s: S) -> Result<S::Ok, S::Error> {
+        let visit = MapEntries(s.serialize_map(Some(Inner::Fields::LEN))?);
+        Inner::Fields::for_each(value, visit)?.0.end()
+    }
+}
+
+/// Serializes a single field on its own, according to its representation
+pub struct FieldValue<'a, F: Field>(pub &'a F::Type);
+
+impl<F> Serialize for FieldValue<'_, F>
+where
+    F: Field + VisitField<<F as Field>::Repr>,
+{
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        F::serialize(self.0, s)
+    }
+}
+
+// visits fields as the entries of a map
+pub struct MapEntries<S>(pub S);
+
+impl<S: SerializeMap> FieldVisitor for MapEntries<S> {
+    type Error = S::Error;
+
+    fn visit<F, T>(mut self, value: &T) -> Result<Self, Self::Error>
+    where
+        F: Field,
+        T: Serialize + ?Sized,
+    {
+        self.0.serialize_entry(F::IDENT.unwrap(), value)?;
+        Ok(self)
+    }
+}
+
+// only visits the fields whose identifier matches the predicate, skipping the others
+pub struct Only<P, V>(pub P, pub V);
+
+impl<P, V> FieldVisitor for Only<P, V>
+where
+    P: Fn(Option<&str>) -> bool,
+    V: FieldVisitor,
+{
+    type Error = V::Error;
+
+    fn visit<F, T>(self, value: &T) -> Result<Self, Self::Error>
+    where
+        F: Field,
+        T: Serialize + ?Sized,
+    {
+        match (self.0)(F::IDENT) {
+            true => Ok(Only(self.0, self.1.visit::<F, T>(value)?)),
+            false => Ok(Only(self.0, self.1.skip::<F>()?)),
+        }
+    }
+
+    fn skip<F: Field>(self) -> Result<Self, Self::Error> {
+        Ok(Only(self.0, self.1.skip::<F>()?))
+    }
+}
+
 pub trait VariantVisitor<Root>: Sized {
     type Error;
 
@@ -0,0 +1,108 @@
+//! Adapters for (de)serializing fields on behalf of their type.
+//!
+//! A field marked with `#[reflect(adapter = A)]` is serialized through `A: SerializeAs<T>` and
+//! deserialized through `A: DeserializeAs<'de, T>` instead of the `Serialize` and `Deserialize`
+//! impls of its type `T`, which therefore don't need to exist. Since adapters are types, a single
+//! adapter can handle a whole category of types, like [`DisplayFromStr`] does.
+//!
+//! Like with serde_derive, `#[reflect(with = module)]` uses `module::serialize` and
+//! `module::deserialize`, while `#[reflect(serialize_with = function)]` and
+//! `#[reflect(deserialize_with = function)]` replace only one direction.
+
+use std::{
+    fmt::{Display, Formatter},
+    marker::PhantomData,
+    str::FromStr,
+};
+
+pub use reflector::with::{Borrow, DeserializeAs, Deserializer, SerializeAs, Serializer};
+use serde::{
+    Deserialize, Serialize,
+    de::{Error, Visitor},
+};
+
+use crate::{de, ser};
+
+/// Uses the `Serialize` and `Deserialize` impls of the type itself.
+pub struct Same;
+
+impl<T: Serialize + ?Sized> SerializeAs<T> for Same {
+    fn serialize_as<S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+        value.serialize(serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> DeserializeAs<'de, T> for Same {
+    fn deserialize_as<D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error> {
+        T::deserialize(deserializer)
+    }
+}
+
+/// Uses reflection, for fields of types which derive `Introspect`, but don't implement
+/// `Serialize` and `Deserialize` themselves.
+pub struct Reflect;
+
+impl<T> SerializeAs<T> for Reflect
+where
+    for<'a> ser::Reflect<'a, T>: Serialize,
+{
+    fn serialize_as<S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+        ser::Reflect(value).serialize(serializer)
+    }
+}
+
+impl<'de, T> DeserializeAs<'de, T> for Reflect
+where
+    de::Reflect<T>: Deserialize<'de>,
+{
+    fn deserialize_as<D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error> {
+        de::Reflect::deserialize(deserializer).map(|de::Reflect(value)| value)
+    }
+}
+
+/// Uses the `Display` and `FromStr` impls of the type, representing it as a string.
+pub struct DisplayFromStr;
+
+impl<T: Display + ?Sized> SerializeAs<T> for DisplayFromStr {
+    fn serialize_as<S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(value)
+    }
+}
+
+impl<'de, T> DeserializeAs<'de, T> for DisplayFromStr
+where
+    T: FromStr<Err: Display>,
+{
+    fn deserialize_as<D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error> {
+        struct Visit<T>(PhantomData<T>);
+        impl<T> Visitor<'_> for Visit<T>
+        where
+            T: FromStr<Err: Display>,
+        {
+            type Value = T;
+
+            fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+                formatter.write_str("string")
+            }
+
+            fn visit_str<E: Error>(self, v: &str) -> Result<T, E> {
+                v.parse().map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_str(Visit(PhantomData))
+    }
+}
+
+// wrappers applying an adapter to a value
+pub(crate) struct SerializeWith<'a, A, T: ?Sized>(pub &'a T, pub PhantomData<A>);
+
+impl<A, T> Serialize for SerializeWith<'_, A, T>
+where
+    A: SerializeAs<T>,
+    T: ?Sized,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        A::serialize_as(self.0, serializer)
+    }
+}
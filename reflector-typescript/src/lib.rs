@@ -0,0 +1,583 @@
+//! TypeScript declarations for reflected types.
+//!
+//! The declarations describe exactly what `reflector-serde` produces: named structs are
+//! interfaces, newtype structs are their only field, other tuple structs are tuples, and enums
+//! are unions of string literals for unit variants and `{ Name: payload }` objects for the
+//! others. Flattened fields are inlined into their parent, transparent structs are their inner
+//! field, and fields with an adapter are described by [`TypeScriptAs`].
+//!
+//! Every struct and enum is declared once, under its identifier, along with the types it refers
+//! to. Nested types are described through [`TypeScript`], which is implemented for std types and
+//! for reflected types marked `#[reflect(impl(reflector_typescript::TypeScript))]`.
+//!
+//! ```ignore
+//! #[test]
+//! fn bindings() {
+//!     Declarations::default()
+//!         .add::<Request>()
+//!         .add::<Response>()
+//!         .write("frontend/src/api.d.ts")
+//!         .unwrap();
+//! }
+//! ```
+
+pub use declarations::Declarations;
+pub use reflect::ReflectTypeScript;
+
+/// The declarations of `T` and of the types it refers to.
+pub fn declarations<T: ReflectTypeScript>() -> Declarations {
+    let mut declarations = Declarations::default();
+    declarations.add::<T>();
+    declarations
+}
+
+/// A type with a JSON representation, described as a TypeScript type.
+pub trait TypeScript {
+    /// The type, as used in other declarations, after adding the declarations it refers to
+    fn typescript(declarations: &mut Declarations) -> String;
+}
+
+/// Implements [`TypeScript`] for a reflected type through [`ReflectTypeScript`], with
+/// `#[reflect(impl(reflector_typescript::TypeScript))]`. Type parameters have to implement
+/// `TypeScript`.
+#[macro_export]
+macro_rules! TypeScript {
+    (
+        lifetimes [$($lifetime:lifetime),*]
+        types [$($param:ident),*]
+        impl [$($params:tt)*] $ty:ty
+        where [$($where:tt)*]
+    ) => {
+        impl<$($params)*> $crate::TypeScript for $ty
+        where
+            $($where)*
+            $($param: $crate::TypeScript,)*
+        {
+            fn typescript(declarations: &mut $crate::Declarations) -> ::std::string::String {
+                <Self as $crate::ReflectTypeScript>::typescript(declarations)
+            }
+        }
+    };
+}
+
+/// Describes the representation of `T` used by an adapter of `reflector_serde::with`.
+pub trait TypeScriptAs<T: ?Sized> {
+    fn typescript_as(declarations: &mut Declarations) -> String;
+}
+
+mod declarations {
+    use std::{any::type_name, collections::HashMap, fmt, fs, io, path::Path};
+
+    use crate::{ReflectTypeScript, TypeScript};
+
+    /// Declarations of structs and enums, in the order they were first referred to.
+    #[derive(Default)]
+    pub struct Declarations {
+        // (name, declaration), empty while the declaration is generated
+        declarations: Vec<(String, String)>,
+        // names by type, since e.g. `A<u8>` and `A<String>` share their identifier
+        names: HashMap<&'static str, String>,
+    }
+
+    impl Declarations {
+        /// Declares `T` and the types it refers to
+        pub fn add<T: ReflectTypeScript>(&mut self) -> &mut Self {
+            T::typescript(self);
+            self
+        }
+
+        pub fn typescript<T: TypeScript + ?Sized>(&mut self) -> String {
+            T::typescript(self)
+        }
+
+        /// Declares `T` as `name`, or as `name2`, `name3` and so on if the name is taken by
+        /// another type, and returns that name. `declare` is given the name and returns the
+        /// declaration, e.g. `export type Name = ..;`
+        pub fn declare<T: ?Sized>(
+            &mut self,
+            name: &str,
+            declare: impl FnOnce(&mut Self, &str) -> String,
+        ) -> String {
+            if let Some(name) = self.names.get(type_name::<T>()) {
+                return name.clone();
+            }
+
+            let mut unique = name.to_owned();
+            for i in 2.. {
+                if !self.declarations.iter().any(|(name, _)| *name == unique) {
+                    break;
+                }
+                unique = format!("{name}{i}");
+            }
+            self.names.insert(type_name::<T>(), unique.clone());
+            // reserved while the declaration is generated, which may refer to itself
+            let index = self.declarations.len();
+            self.declarations.push((unique.clone(), String::new()));
+            self.declarations[index].1 = declare(self, &unique);
+            unique
+        }
+
+        pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+            self.declarations
+                .iter()
+                .map(|(name, declaration)| (name.as_str(), declaration.as_str()))
+        }
+
+        /// Writes the declarations to a file, e.g. a `.d.ts` file
+        pub fn write(&self, path: impl AsRef<Path>) -> io::Result<()> {
+            fs::write(path, self.to_string())
+        }
+    }
+
+    impl fmt::Display for Declarations {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            writeln!(f, "// Generated by reflector-typescript. Do not edit.")?;
+            for (_, declaration) in &self.declarations {
+                writeln!(f, "\n{declaration}")?;
+            }
+            Ok(())
+        }
+    }
+}
+
+mod reflect {
+    use reflector::*;
+
+    use crate::{Declarations, TypeScript, TypeScriptAs};
+
+    /// The TypeScript type of reflected types, implemented for any type which can be
+    /// introspected and whose fields implement [`TypeScript`], or [`TypeScriptAs`] for their
+    /// adapter.
+    pub trait ReflectTypeScript {
+        fn typescript(declarations: &mut Declarations) -> String;
+    }
+
+    impl<T> ReflectTypeScript for T
+    where
+        T: Introspect,
+        T: TypeScriptKind<T::Kind>,
+    {
+        fn typescript(declarations: &mut Declarations) -> String {
+            <T as TypeScriptKind<_>>::typescript(declarations)
+        }
+    }
+
+    pub trait TypeScriptKind<Kind> {
+        fn typescript(declarations: &mut Declarations) -> String;
+    }
+
+    impl<T> TypeScriptKind<StructKind> for T
+    where
+        T: Struct<Root = T>,
+        T: TypeScriptRepr<T::Repr>,
+    {
+        fn typescript(declarations: &mut Declarations) -> String {
+            declarations.declare::<T>(T::IDENT, <T as TypeScriptRepr<_>>::declaration)
+        }
+    }
+
+    // enum E { A, B(..), C { .. } }, as `"A" | { B: .. } | { C: .. }`
+    impl<T> TypeScriptKind<EnumKind> for T
+    where
+        T: Enum,
+        T::Variants: TypeScriptVariants,
+    {
+        fn typescript(declarations: &mut Declarations) -> String {
+            declarations.declare::<T>(T::IDENT, |declarations, name| {
+                let mut variants = Vec::new();
+                T::Variants::typescript(declarations, &mut variants);
+                match variants.as_slice() {
+                    // no value can be serialized
+                    [] => format!("export type {name} = never;"),
+                    [variant] => format!("export type {name} = {variant};"),
+                    variants => format!("export type {name} =\n  | {};", variants.join("\n  | ")),
+                }
+            })
+        }
+    }
+
+    pub trait TypeScriptRepr<Repr> {
+        fn declaration(declarations: &mut Declarations, name: &str) -> String;
+    }
+
+    impl<T> TypeScriptRepr<PlainRepr> for T
+    where
+        T: Struct,
+        T: TypeScriptShape<T::Shape>,
+    {
+        fn declaration(declarations: &mut Declarations, name: &str) -> String {
+            <T as TypeScriptShape<_>>::declaration(declarations, name)
+        }
+    }
+
+    // #[reflect(transparent)] struct T(A, PhantomData<..>);
+    impl<T> TypeScriptRepr<TransparentRepr> for T
+    where
+        T: TransparentStruct,
+        T::Inner: TypeScriptField<<T::Inner as Field>::Repr>,
+    {
+        fn declaration(declarations: &mut Declarations, name: &str) -> String {
+            format!(
+                "export type {name} = {};",
+                T::Inner::typescript(declarations)
+            )
+        }
+    }
+
+    // the payload of a struct or variant
+    pub trait TypeScriptShape<Shape> {
+        const IS_UNIT: bool = false;
+
+        fn typescript(declarations: &mut Declarations) -> String;
+
+        fn declaration(declarations: &mut Declarations, name: &str) -> String {
+            format!("export type {name} = {};", Self::typescript(declarations))
+        }
+    }
+
+    // struct S; as `null`, enum E { S, .. } as `"S"`
+    impl<S: Struct> TypeScriptShape<UnitShape> for S {
+        const IS_UNIT: bool = true;
+
+        fn typescript(_: &mut Declarations) -> String {
+            "null".into()
+        }
+    }
+
+    // struct S(A); as `A`, and struct S(A, B, ..); as `[A, B, ..]`
+    impl<S> TypeScriptShape<TupleShape> for S
+    where
+        S: Struct<Fields: TypeScriptFields>,
+    {
+        fn typescript(declarations: &mut Declarations) -> String {
+            let mut items = Vec::new();
+            S::Fields::items(declarations, &mut items);
+            match items.as_slice() {
+                [item] => item.clone(),
+                items => format!("[{}]", items.join(", ")),
+            }
+        }
+    }
+
+    // struct S { .. } as `interface S { .. }`, with the properties of flattened fields
+    impl<S> TypeScriptShape<NamedShape> for S
+    where
+        S: Struct<Fields: TypeScriptFields>,
+    {
+        fn typescript(declarations: &mut Declarations) -> String {
+            let mut properties = Vec::new();
+            S::Fields::properties(declarations, &mut properties);
+            let properties = properties
+                .iter()
+                .map(|(name, ty)| format!("{}: {ty}", property(name)))
+                .collect::<Vec<_>>();
+            match properties.is_empty() {
+                true => "{}".into(),
+                false => format!("{{ {} }}", properties.join("; ")),
+            }
+        }
+
+        fn declaration(declarations: &mut Declarations, name: &str) -> String {
+            let mut properties = Vec::new();
+            S::Fields::properties(declarations, &mut properties);
+            let mut declaration = format!("export interface {name} {{\n");
+            for (name, ty) in properties {
+                declaration += &format!("  {}: {ty};\n", property(name));
+            }
+            declaration + "}"
+        }
+    }
+
+    // a property name, quoted unless it is a valid identifier
+    fn property(name: &str) -> String {
+        let mut chars = name.chars();
+        let identifier = chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+        match identifier {
+            true => name.into(),
+            false => format!("{name:?}"),
+        }
+    }
+
+    // helper trait implemented recursively for a list of fields, e.g `(Field0, (Field1, ()))`
+    pub trait TypeScriptFields {
+        fn items(declarations: &mut Declarations, items: &mut Vec<String>);
+        fn properties(declarations: &mut Declarations, properties: &mut Vec<(&str, String)>);
+    }
+    // end of the recursion
+    impl TypeScriptFields for () {
+        fn items(_: &mut Declarations, _: &mut Vec<String>) {}
+        fn properties(_: &mut Declarations, _: &mut Vec<(&str, String)>) {}
+    }
+    impl<Head, Tail> TypeScriptFields for Cons<Head, Tail>
+    where
+        Head: Field + TypeScriptField<<Head as Field>::Repr>,
+        Tail: TypeScriptFields,
+    {
+        // fields marked `#[reflect(removed = ..)]` are no longer written
+        fn items(declarations: &mut Declarations, items: &mut Vec<String>) {
+            if Head::REMOVED.is_none() {
+                items.push(Head::typescript(declarations));
+            }
+            Tail::items(declarations, items)
+        }
+
+        fn properties(declarations: &mut Declarations, properties: &mut Vec<(&str, String)>) {
+            if Head::REMOVED.is_none() {
+                Head::properties(declarations, properties);
+            }
+            Tail::properties(declarations, properties)
+        }
+    }
+
+    // helper trait implemented for every field, depending on its representation
+    pub trait TypeScriptField<Repr>: Field {
+        fn typescript(declarations: &mut Declarations) -> String;
+
+        fn properties(declarations: &mut Declarations, properties: &mut Vec<(&str, String)>) {
+            properties.push((Self::IDENT.unwrap(), Self::typescript(declarations)));
+        }
+    }
+
+    impl<F> TypeScriptField<PlainRepr> for F
+    where
+        F: Field<Type: TypeScript>,
+    {
+        fn typescript(declarations: &mut Declarations) -> String {
+            declarations.typescript::<F::Type>()
+        }
+    }
+
+    impl<F, A> TypeScriptField<WithRepr<A>> for F
+    where
+        F: Field,
+        A: TypeScriptAs<F::Type>,
+    {
+        fn typescript(declarations: &mut Declarations) -> String {
+            A::typescript_as(declarations)
+        }
+    }
+
+    // the fields of the flattened struct become properties of the parent
+    impl<F, Inner> TypeScriptField<FlattenRepr> for F
+    where
+        F: Field<Type = Inner>,
+        Inner: Struct<Root = Inner, Shape = NamedShape, Fields: TypeScriptFields>,
+    {
+        fn typescript(declarations: &mut Declarations) -> String {
+            <Inner as TypeScriptShape<NamedShape>>::typescript(declarations)
+        }
+
+        fn properties(declarations: &mut Declarations, properties: &mut Vec<(&str, String)>) {
+            Inner::Fields::properties(declarations, properties)
+        }
+    }
+
+    // helper trait implemented recursively for a list of variants, e.g `(Variant0, (Variant1, ()))`
+    pub trait TypeScriptVariants {
+        fn typescript(declarations: &mut Declarations, variants: &mut Vec<String>);
+    }
+    // end of the recursion
+    impl TypeScriptVariants for () {
+        fn typescript(_: &mut Declarations, _: &mut Vec<String>) {}
+    }
+    impl<Head, Tail> TypeScriptVariants for Cons<Head, Tail>
+    where
+        Head: Variant + TypeScriptShape<Head::Shape>,
+        Tail: TypeScriptVariants,
+    {
+        fn typescript(declarations: &mut Declarations, variants: &mut Vec<String>) {
+            let name = property(Head::IDENT);
+            if <Head as TypeScriptShape<_>>::IS_UNIT {
+                variants.push(format!("{:?}", Head::IDENT));
+            } else {
+                let payload = <Head as TypeScriptShape<_>>::typescript(declarations);
+                variants.push(format!("{{ {name}: {payload} }}"));
+            }
+            Tail::typescript(declarations, variants)
+        }
+    }
+}
+
+// the adapters of `reflector_serde::with`
+mod adapters {
+    use reflector_serde::with::{Borrow, DisplayFromStr, Reflect, Same};
+
+    use crate::{Declarations, ReflectTypeScript, TypeScript, TypeScriptAs};
+
+    impl<T: TypeScript + ?Sized> TypeScriptAs<T> for Same {
+        fn typescript_as(declarations: &mut Declarations) -> String {
+            declarations.typescript::<T>()
+        }
+    }
+
+    impl<T: ReflectTypeScript> TypeScriptAs<T> for Reflect {
+        fn typescript_as(declarations: &mut Declarations) -> String {
+            T::typescript(declarations)
+        }
+    }
+
+    impl<T: ?Sized> TypeScriptAs<T> for DisplayFromStr {
+        fn typescript_as(_: &mut Declarations) -> String {
+            "string".into()
+        }
+    }
+
+    // borrowing doesn't change the representation
+    impl<T: TypeScript + ?Sized> TypeScriptAs<T> for Borrow {
+        fn typescript_as(declarations: &mut Declarations) -> String {
+            declarations.typescript::<T>()
+        }
+    }
+}
+
+// the representation of std types by serde and serde_json
+mod impls {
+    use std::{
+        borrow::Cow,
+        cell::{Cell, RefCell},
+        collections::{BTreeMap, BTreeSet, HashMap, HashSet, LinkedList, VecDeque},
+        marker::PhantomData,
+        net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6},
+        path::{Path, PathBuf},
+        rc::Rc,
+        sync::Arc,
+        time::Duration,
+    };
+
+    use crate::{Declarations, TypeScript};
+
+    macro_rules! simple {
+        ($ts:literal: $($t:ty),*) => {$(
+            impl TypeScript for $t {
+                fn typescript(_: &mut Declarations) -> String {
+                    $ts.into()
+                }
+            }
+        )*};
+    }
+
+    simple!("number": u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+    simple!("boolean": bool);
+    simple!("string": char, str, String, Path, PathBuf);
+    simple!("string": IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6);
+    simple!("null": ());
+
+    // serde represents `Duration` as a struct
+    impl TypeScript for Duration {
+        fn typescript(_: &mut Declarations) -> String {
+            "{ secs: number; nanos: number }".into()
+        }
+    }
+
+    impl<T: ?Sized> TypeScript for PhantomData<T> {
+        fn typescript(_: &mut Declarations) -> String {
+            "null".into()
+        }
+    }
+
+    // containers which are represented by their contents
+    macro_rules! forward {
+        ($($t:ty),*) => {$(
+            impl<T: TypeScript + ?Sized> TypeScript for $t {
+                fn typescript(declarations: &mut Declarations) -> String {
+                    declarations.typescript::<T>()
+                }
+            }
+        )*};
+    }
+
+    forward!(&T, &mut T, Box<T>, Rc<T>, Arc<T>, RefCell<T>);
+
+    impl<T: TypeScript + Copy> TypeScript for Cell<T> {
+        fn typescript(declarations: &mut Declarations) -> String {
+            declarations.typescript::<T>()
+        }
+    }
+
+    impl<T: TypeScript + ToOwned + ?Sized> TypeScript for Cow<'_, T> {
+        fn typescript(declarations: &mut Declarations) -> String {
+            declarations.typescript::<T>()
+        }
+    }
+
+    impl<T: TypeScript> TypeScript for Option<T> {
+        fn typescript(declarations: &mut Declarations) -> String {
+            format!("{} | null", declarations.typescript::<T>())
+        }
+    }
+
+    // `A[]`, or `(A | B)[]` for unions
+    fn array(item: String) -> String {
+        match item.contains(" | ") {
+            true => format!("({item})[]"),
+            false => format!("{item}[]"),
+        }
+    }
+
+    macro_rules! sequences {
+        ($($t:ty),*) => {$(
+            impl<T: TypeScript> TypeScript for $t {
+                fn typescript(declarations: &mut Declarations) -> String {
+                    array(declarations.typescript::<T>())
+                }
+            }
+        )*};
+    }
+
+    sequences!(
+        [T],
+        Vec<T>,
+        VecDeque<T>,
+        LinkedList<T>,
+        BTreeSet<T>,
+        HashSet<T>
+    );
+
+    impl<T: TypeScript, const N: usize> TypeScript for [T; N] {
+        fn typescript(declarations: &mut Declarations) -> String {
+            array(declarations.typescript::<T>())
+        }
+    }
+
+    // keys are strings in JSON, whatever their type
+    impl<K, V: TypeScript> TypeScript for BTreeMap<K, V> {
+        fn typescript(declarations: &mut Declarations) -> String {
+            format!("Record<string, {}>", declarations.typescript::<V>())
+        }
+    }
+
+    impl<K, V: TypeScript, S> TypeScript for HashMap<K, V, S> {
+        fn typescript(declarations: &mut Declarations) -> String {
+            format!("Record<string, {}>", declarations.typescript::<V>())
+        }
+    }
+
+    macro_rules! tuples {
+        ($(($($t:ident),+)),*) => {$(
+            impl<$($t: TypeScript),+> TypeScript for ($($t,)+) {
+                fn typescript(declarations: &mut Declarations) -> String {
+                    let items = [$(declarations.typescript::<$t>()),+];
+                    format!("[{}]", items.join(", "))
+                }
+            }
+        )*};
+    }
+
+    tuples!(
+        (A),
+        (A, B),
+        (A, B, C),
+        (A, B, C, D),
+        (A, B, C, D, E),
+        (A, B, C, D, E, F),
+        (A, B, C, D, E, F, G),
+        (A, B, C, D, E, F, G, H),
+        (A, B, C, D, E, F, G, H, I),
+        (A, B, C, D, E, F, G, H, I, J),
+        (A, B, C, D, E, F, G, H, I, J, K),
+        (A, B, C, D, E, F, G, H, I, J, K, L)
+    );
+}
@@ -0,0 +1,232 @@
+use std::collections::BTreeMap;
+
+use reflector::Introspect;
+use reflector_serde::with;
+use reflector_typescript::{Declarations, declarations};
+
+#[derive(Introspect)]
+#[reflect(impl(reflector_typescript::TypeScript))]
+pub struct Server {
+    host: String,
+    port: u16,
+}
+
+#[derive(Introspect)]
+#[reflect(impl(reflector_typescript::TypeScript))]
+pub enum Mode {
+    Fast,
+    Slow { delay: u32 },
+    Fixed(u8, u8),
+    Named(String),
+    Other,
+}
+
+#[derive(Introspect)]
+pub struct Settings {
+    server: Server,
+    mode: Mode,
+    workers: Option<usize>,
+    routes: Vec<Option<String>>,
+    limits: BTreeMap<String, f64>,
+    content_type: (String, bool),
+}
+
+#[test]
+fn structs_and_enums() {
+    assert_eq!(
+        declarations::<Settings>().to_string(),
+        r#"// Generated by reflector-typescript. Do not edit.
+
+export interface Settings {
+  server: Server;
+  mode: Mode;
+  workers: number | null;
+  routes: (string | null)[];
+  limits: Record<string, number>;
+  content_type: [string, boolean];
+}
+
+export interface Server {
+  host: string;
+  port: number;
+}
+
+export type Mode =
+  | "Fast"
+  | { Slow: { delay: number } }
+  | { Fixed: [number, number] }
+  | { Named: string }
+  | "Other";
+"#
+    );
+}
+
+#[derive(Introspect)]
+pub struct Unit;
+
+#[derive(Introspect)]
+pub struct Empty();
+
+#[derive(Introspect)]
+pub struct Newtype(bool);
+
+#[derive(Introspect)]
+pub enum Only {
+    A,
+}
+
+#[derive(Introspect)]
+pub enum Never {}
+
+#[test]
+fn shapes() {
+    let declarations = Declarations::default()
+        .add::<Unit>()
+        .add::<Empty>()
+        .add::<Newtype>()
+        .add::<Only>()
+        .add::<Never>()
+        .iter()
+        .map(|(_, declaration)| declaration.to_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        declarations,
+        [
+            "export type Unit = null;",
+            "export type Empty = [];",
+            "export type Newtype = boolean;",
+            "export type Only = \"A\";",
+            "export type Never = never;",
+        ]
+    );
+}
+
+#[derive(Introspect)]
+pub struct Page {
+    title: String,
+    #[reflect(flatten)]
+    meta: Meta,
+}
+
+#[derive(Introspect)]
+pub struct Meta {
+    author: String,
+}
+
+#[derive(Introspect)]
+#[reflect(transparent)]
+pub struct Id(u32);
+
+#[derive(Introspect)]
+pub struct Record {
+    #[reflect(adapter = with::Reflect)]
+    id: Id,
+    #[reflect(adapter = with::DisplayFromStr)]
+    count: u64,
+    #[reflect(adapter = with::Reflect)]
+    page: Page,
+}
+
+#[test]
+fn flatten_transparent_and_adapters() {
+    assert_eq!(
+        declarations::<Record>().iter().collect::<Vec<_>>(),
+        [
+            (
+                "Record",
+                "export interface Record {\n  id: Id;\n  count: string;\n  page: Page;\n}"
+            ),
+            ("Id", "export type Id = number;"),
+            (
+                "Page",
+                "export interface Page {\n  title: string;\n  author: string;\n}"
+            ),
+        ]
+    );
+}
+
+#[derive(Introspect)]
+#[reflect(impl(reflector_typescript::TypeScript))]
+pub struct Tree {
+    value: i8,
+    children: Vec<Tree>,
+}
+
+#[derive(Introspect)]
+#[reflect(impl(reflector_typescript::TypeScript))]
+pub struct Wrapper<T> {
+    value: T,
+}
+
+#[derive(Introspect)]
+pub struct Both {
+    a: Wrapper<bool>,
+    b: Wrapper<String>,
+    tree: Tree,
+}
+
+#[test]
+fn recursion_and_generics() {
+    assert_eq!(
+        declarations::<Both>().iter().collect::<Vec<_>>(),
+        [
+            (
+                "Both",
+                "export interface Both {\n  a: Wrapper;\n  b: Wrapper2;\n  tree: Tree;\n}"
+            ),
+            (
+                "Wrapper",
+                "export interface Wrapper {\n  value: boolean;\n}"
+            ),
+            (
+                "Wrapper2",
+                "export interface Wrapper2 {\n  value: string;\n}"
+            ),
+            (
+                "Tree",
+                "export interface Tree {\n  value: number;\n  children: Tree[];\n}"
+            ),
+        ]
+    );
+}
+
+#[test]
+fn write() {
+    let path = std::env::temp_dir().join("reflector-typescript-write.d.ts");
+    let declarations = declarations::<Server>();
+    declarations.write(&path).unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        declarations.to_string()
+    );
+    std::fs::remove_file(path).unwrap();
+}
+
+#[derive(Introspect)]
+#[reflect(version = 3)]
+pub struct Versioned {
+    a: u32,
+    #[reflect(since = 2, default)]
+    b: u32,
+    #[reflect(removed = 3)]
+    old: u32,
+}
+
+#[test]
+fn removed_fields() {
+    let value = Versioned { a: 1, b: 2, old: 3 };
+    assert_eq!(
+        serde_json::to_string(&reflector_serde::ser::Reflect(&value)).unwrap(),
+        r#"{"a":1,"b":2}"#
+    );
+    assert_eq!(
+        declarations::<Versioned>().to_string(),
+        r#"// Generated by reflector-typescript. Do not edit.
+
+export interface Versioned {
+  a: number;
+  b: number;
+}
+"#
+    );
+}
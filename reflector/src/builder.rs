@@ -0,0 +1,41 @@
+//! The runtime of the builders generated with `#[reflect(builder)]`, which `reflector_builder`
+//! documents.
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::Field;
+
+/// The error of `build()`, listing the required fields which weren't set.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Error {
+    /// The identifier of the struct or variant
+    pub ident: &'static str,
+    pub missing: Vec<&'static str>,
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "missing fields of {}: {}",
+            self.ident,
+            self.missing.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for Error {}
+
+// the value of a field when building, its default if unset, or else `None` and the field added to
+// the missing ones
+#[doc(hidden)]
+pub fn field<F>(value: Option<F::Type>, missing: &mut Vec<&'static str>) -> Option<F::Type>
+where
+    F: Field<Type: Sized>,
+{
+    let value = value.or_else(F::default_value);
+    if value.is_none() {
+        missing.extend(F::IDENT);
+    }
+    value
+}
@@ -1,13 +1,28 @@
 #![feature(freeze)]
 
+pub mod builder;
 mod list;
+mod type_hash;
+#[cfg(feature = "serde")]
+pub mod with;
+
+use std::{cmp::Ordering, hash::Hasher, marker::PhantomData};
 
 pub use list::*;
 pub use reflector_derive::Introspect;
+pub use type_hash::*;
 
 /// Anything which can be introspected - structs, enums, and enum variants, at the moment.
 pub trait Introspect {
     const IDENT: &'static str;
+    /// The current version of the layout, `#[reflect(version = 3)]`, which fields refer to with
+    /// [`Field::SINCE`] and [`Field::REMOVED`]. Variants share the version of their enum.
+    const VERSION: u32;
+    /// A hash of the layout: the identifier, and the names, written types and versions of fields
+    /// and variants. Nested types contribute only their written names, not their own layouts.
+    const FINGERPRINT: u64;
+    /// The doc comment of the type or variant, without the leading space of every line.
+    const DOC: &'static str = "";
 
     /// "Root" type. Refers to `Self` for every type, and to its enum for variants..
     type Root: Introspect;
@@ -17,6 +32,8 @@ pub trait Introspect {
 pub trait Struct: Introspect {
     type Shape: StructShape;
     type Fields: FieldList;
+    /// How the struct is represented, e.g. [`TransparentRepr`] for `#[reflect(transparent)]`.
+    type Repr: StructRepr;
 }
 
 pub trait SizedStruct: Struct<Fields: SizedFieldList> {
@@ -25,15 +42,55 @@ pub trait SizedStruct: Struct<Fields: SizedFieldList> {
     fn from_values(values: Self::FieldTypes) -> Self::Root;
 }
 
+/// A struct marked with `#[reflect(transparent)]`, which is represented by its only field. Apart
+/// from that field, it may only contain zero-sized fields like `PhantomData`.
+pub trait TransparentStruct: Struct<Repr = TransparentRepr> {
+    type Inner: Field<Root = Self::Root, Type: Sized>;
+
+    /// Constructs the struct from its inner field, filling the others with their default
+    fn from_inner(inner: <Self::Inner as Field>::Type) -> Self::Root;
+}
+
 pub trait Field {
     type Type: ?Sized;
     type Root: Introspect;
+    /// How the field is represented, e.g. [`FlattenRepr`] for fields marked `#[reflect(flatten)]`.
+    type Repr: FieldRepr;
+    /// How the field is compared and hashed, e.g. [`SkipRepr`] for fields marked
+    /// `#[reflect(skip_compare)]`.
+    type CompareRepr: FieldRepr;
 
     const IDENT: Option<&'static str>;
     const INDEX: u32;
+    /// Whether the field holds secrets which shouldn't be logged, `#[reflect(sensitive)]`.
+    const SENSITIVE: bool = false;
+    /// The doc comment of the field, without the leading space of every line.
+    const DOC: &'static str = "";
+    /// The version of the root which added the field, `#[reflect(since = 3)]`. Data of older
+    /// versions lacks the field, which then gets its [`default_value`](Self::default_value).
+    const SINCE: u32 = 0;
+    /// The version of the root which removed the field, `#[reflect(removed = 4)]`. The field is
+    /// no longer written from then on, but still read from older data.
+    const REMOVED: Option<u32> = None;
+    /// Former names of the field, `#[reflect(renamed_from = "old")]`, accepted when reading.
+    const ALIASES: &'static [&'static str] = &[];
 
     fn try_get_ref(p: &Self::Root) -> Option<&Self::Type>;
     fn try_get_mut(p: &mut Self::Root) -> Option<&mut Self::Type>;
+
+    /// The value of the field when it is missing from the data, for fields marked
+    /// `#[reflect(default)]`, `since` or `removed`
+    fn default_value() -> Option<Self::Type>
+    where
+        Self::Type: Sized,
+    {
+        None
+    }
+
+    /// Whether the field is part of the layout in the given version of its root
+    fn in_version(version: u32) -> bool {
+        Self::SINCE <= version && Self::REMOVED.is_none_or(|removed| version < removed)
+    }
 }
 
 pub trait Enum: Introspect {
@@ -64,6 +121,40 @@ impl StructShape for UnitShape {}
 impl StructShape for TupleShape {}
 impl StructShape for NamedShape {}
 
+// field representations
+pub trait FieldRepr {}
+/// The default representation: a field by its own value, a struct by its fields.
+pub struct PlainRepr;
+/// The fields of the field's type are inlined into the parent.
+pub struct FlattenRepr;
+/// The field is represented through the adapter `A`, chosen using `#[reflect(with = ..)]` and
+/// friends.
+pub struct WithRepr<A>(PhantomData<A>);
+/// The field is ignored.
+pub struct SkipRepr;
+impl FieldRepr for PlainRepr {}
+impl FieldRepr for FlattenRepr {}
+impl FieldRepr for SkipRepr {}
+impl<A> FieldRepr for WithRepr<A> {}
+
+/// Compares and hashes values of type `T` in a custom way, implemented for the fields marked
+/// `#[reflect(compare_with = module)]`, whose [`Field::CompareRepr`] is then `WithRepr<Self>`.
+///
+/// The functions must agree with each other like the std traits do: values which are equal
+/// according to `eq` have to compare as `Ordering::Equal` and hash the same.
+pub trait Comparator<T: ?Sized> {
+    fn eq(a: &T, b: &T) -> bool;
+    fn cmp(a: &T, b: &T) -> Ordering;
+    fn hash<H: Hasher>(value: &T, state: &mut H);
+}
+
+// struct representations
+pub trait StructRepr {}
+/// The struct is represented by its only field, see [`TransparentStruct`].
+pub struct TransparentRepr;
+impl StructRepr for PlainRepr {}
+impl StructRepr for TransparentRepr {}
+
 // kinds
 pub trait Kind {}
 pub struct StructKind;
@@ -0,0 +1,191 @@
+use std::borrow::{Cow, ToOwned};
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet, LinkedList, VecDeque};
+use std::ffi::{OsStr, OsString};
+use std::marker::PhantomData;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+
+/// A hash of the structure of a type, which is computed at compile time and stable across
+/// compilations, platforms and compiler versions, unlike [`TypeId`](std::any::TypeId).
+///
+/// Reflected types implement it through the derive with `#[reflect(type_hash)]`: a hash of their
+/// identifier, shape, field names, the type hashes of the fields and the variants. Unlike
+/// [`Introspect::FINGERPRINT`] it follows the field types into their own structure, and ignores
+/// representations and versions. Standard types hash their name and the type hashes of their
+/// parameters.
+///
+/// The derived impl requires type parameters to implement `TypeHash`, and fields whose type
+/// doesn't can be marked `#[reflect(opaque)]` to contribute their written type instead. A type
+/// referring to itself contributes a placeholder to its own hash, while mutually recursive types
+/// fail to compile.
+///
+/// [`Introspect::FINGERPRINT`]: crate::Introspect::FINGERPRINT
+pub trait TypeHash {
+    const HASH: u64;
+}
+
+// FNV-1a, continuing from `hash`
+const fn hash_bytes(mut hash: u64, bytes: &[u8]) -> u64 {
+    let mut i = 0;
+    while i < bytes.len() {
+        hash = (hash ^ bytes[i] as u64).wrapping_mul(0x0100_0000_01b3);
+        i += 1;
+    }
+    hash
+}
+
+/// Combines the hash of `name` with the given type hashes, in order, for implementing [`TypeHash`]
+/// by hand:
+///
+/// ```
+/// # use reflector::{TypeHash, hash_combine};
+/// struct Pair<A, B>(A, B);
+///
+/// impl<A: TypeHash, B: TypeHash> TypeHash for Pair<A, B> {
+///     const HASH: u64 = hash_combine("Pair", &[A::HASH, B::HASH]);
+/// }
+/// ```
+pub const fn hash_combine(name: &str, hashes: &[u64]) -> u64 {
+    let mut hash = hash_bytes(0xcbf2_9ce4_8422_2325, name.as_bytes());
+    let mut i = 0;
+    while i < hashes.len() {
+        hash = hash_bytes(hash, &hashes[i].to_le_bytes());
+        i += 1;
+    }
+    hash
+}
+
+/// Stands in for a type within its own fields, as the hash of a recursive type can't depend on
+/// itself
+#[doc(hidden)]
+pub struct Recursive;
+
+impl TypeHash for Recursive {
+    const HASH: u64 = hash_combine("Self", &[]);
+}
+
+macro_rules! named {
+    ($($t:ty),*) => {$(
+        impl TypeHash for $t {
+            const HASH: u64 = hash_combine(stringify!($t), &[]);
+        }
+    )*};
+}
+
+named!(
+    bool,
+    char,
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    f32,
+    f64,
+    str,
+    String,
+    Duration,
+    Path,
+    PathBuf,
+    OsStr,
+    OsString,
+    IpAddr,
+    Ipv4Addr,
+    Ipv6Addr,
+    SocketAddr,
+    SocketAddrV4,
+    SocketAddrV6
+);
+
+macro_rules! generic {
+    ($($name:literal $t:ident<$($p:ident),*>),* $(,)?) => {$(
+        impl<$($p: TypeHash),*> TypeHash for $t<$($p),*> {
+            const HASH: u64 = hash_combine($name, &[$($p::HASH),*]);
+        }
+    )*};
+}
+
+generic!(
+    "Option" Option<T>,
+    "Result" Result<T, E>,
+    "Vec" Vec<T>,
+    "VecDeque" VecDeque<T>,
+    "LinkedList" LinkedList<T>,
+    "BinaryHeap" BinaryHeap<T>,
+    "BTreeSet" BTreeSet<T>,
+    "BTreeMap" BTreeMap<K, V>,
+    "Cell" Cell<T>,
+);
+
+// pointers and locks, possibly of unsized types
+macro_rules! pointer {
+    ($($name:literal $t:ident),*) => {$(
+        impl<T: TypeHash + ?Sized> TypeHash for $t<T> {
+            const HASH: u64 = hash_combine($name, &[T::HASH]);
+        }
+    )*};
+}
+
+pointer!("Box" Box, "Rc" Rc, "Arc" Arc, "RefCell" RefCell, "Mutex" Mutex, "RwLock" RwLock, "PhantomData" PhantomData);
+
+impl<T: TypeHash + ?Sized> TypeHash for &T {
+    const HASH: u64 = hash_combine("&", &[T::HASH]);
+}
+
+impl<T: TypeHash + ?Sized> TypeHash for &mut T {
+    const HASH: u64 = hash_combine("&mut", &[T::HASH]);
+}
+
+impl<T: TypeHash + ToOwned + ?Sized> TypeHash for Cow<'_, T> {
+    const HASH: u64 = hash_combine("Cow", &[T::HASH]);
+}
+
+impl<T: TypeHash> TypeHash for [T] {
+    const HASH: u64 = hash_combine("[]", &[T::HASH]);
+}
+
+impl<T: TypeHash, const N: usize> TypeHash for [T; N] {
+    const HASH: u64 = hash_combine("[;]", &[T::HASH, N as u64]);
+}
+
+// the hasher isn't part of the structure
+impl<T: TypeHash, S> TypeHash for HashSet<T, S> {
+    const HASH: u64 = hash_combine("HashSet", &[T::HASH]);
+}
+
+impl<K: TypeHash, V: TypeHash, S> TypeHash for HashMap<K, V, S> {
+    const HASH: u64 = hash_combine("HashMap", &[K::HASH, V::HASH]);
+}
+
+macro_rules! tuple {
+    ($($p:ident)*) => {
+        impl<$($p: TypeHash),*> TypeHash for ($($p,)*) {
+            const HASH: u64 = hash_combine("()", &[$($p::HASH),*]);
+        }
+    };
+}
+
+tuple!();
+tuple!(A);
+tuple!(A B);
+tuple!(A B C);
+tuple!(A B C D);
+tuple!(A B C D E);
+tuple!(A B C D E F);
+tuple!(A B C D E F G);
+tuple!(A B C D E F G H);
+tuple!(A B C D E F G H I);
+tuple!(A B C D E F G H I J);
+tuple!(A B C D E F G H I J K);
+tuple!(A B C D E F G H I J K L);
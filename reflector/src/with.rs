@@ -0,0 +1,197 @@
+//! The traits of adapters for (de)serializing fields on behalf of their type, which
+//! `#[reflect(with = module)]`, `serialize_with` and `deserialize_with` implement for the field.
+//! The adapters themselves are in `reflector_serde::with`, which enables this module, except for
+//! [`Borrow`], which `#[reflect(borrow)]` refers to.
+
+use std::{borrow::Cow, fmt::Formatter, marker::PhantomData};
+
+use serde::de::{Error, SeqAccess, Unexpected, Visitor};
+pub use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Serializes `T` on behalf of its own `Serialize` impl.
+pub trait SerializeAs<T: ?Sized> {
+    fn serialize_as<S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error>;
+}
+
+/// Deserializes `T` on behalf of its own `Deserialize` impl.
+pub trait DeserializeAs<'de, T>: Sized {
+    fn deserialize_as<D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error>;
+}
+
+/// Deserializes borrowed data wherever possible, `#[reflect(borrow)]`.
+///
+/// `Cow<str>` and `Cow<[u8]>` borrow from the input if the format hands out borrowed data, and
+/// fall back to owned data otherwise. Their `Deserialize` impls, in contrast, always allocate.
+/// `&str` and `&[u8]` always borrow, failing if the format can't provide borrowed data.
+/// Byte slices are serialized as bytes instead of sequences. This also works within `Option`.
+pub struct Borrow;
+
+impl SerializeAs<&str> for Borrow {
+    fn serialize_as<S: Serializer>(value: &&str, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(value)
+    }
+}
+
+impl SerializeAs<Cow<'_, str>> for Borrow {
+    fn serialize_as<S: Serializer>(value: &Cow<str>, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(value)
+    }
+}
+
+impl SerializeAs<&[u8]> for Borrow {
+    fn serialize_as<S: Serializer>(value: &&[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(value)
+    }
+}
+
+impl SerializeAs<Cow<'_, [u8]>> for Borrow {
+    fn serialize_as<S: Serializer>(value: &Cow<[u8]>, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(value)
+    }
+}
+
+impl<T> SerializeAs<Option<T>> for Borrow
+where
+    Borrow: SerializeAs<T>,
+{
+    fn serialize_as<S: Serializer>(value: &Option<T>, serializer: S) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(value) => serializer.serialize_some(&Borrowed(value)),
+            None => serializer.serialize_none(),
+        }
+    }
+}
+
+impl<'de: 'a, 'a> DeserializeAs<'de, &'a str> for Borrow {
+    fn deserialize_as<D: Deserializer<'de>>(deserializer: D) -> Result<&'a str, D::Error> {
+        <&str>::deserialize(deserializer)
+    }
+}
+
+impl<'de: 'a, 'a> DeserializeAs<'de, &'a [u8]> for Borrow {
+    fn deserialize_as<D: Deserializer<'de>>(deserializer: D) -> Result<&'a [u8], D::Error> {
+        <&[u8]>::deserialize(deserializer)
+    }
+}
+
+impl<'de: 'a, 'a> DeserializeAs<'de, Cow<'a, str>> for Borrow {
+    fn deserialize_as<D: Deserializer<'de>>(deserializer: D) -> Result<Cow<'a, str>, D::Error> {
+        struct Visit;
+        impl<'de> Visitor<'de> for Visit {
+            type Value = Cow<'de, str>;
+
+            fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+                formatter.write_str("string")
+            }
+
+            fn visit_borrowed_str<E: Error>(self, v: &'de str) -> Result<Self::Value, E> {
+                Ok(Cow::Borrowed(v))
+            }
+            fn visit_str<E: Error>(self, v: &str) -> Result<Self::Value, E> {
+                Ok(Cow::Owned(v.to_owned()))
+            }
+            fn visit_string<E: Error>(self, v: String) -> Result<Self::Value, E> {
+                Ok(Cow::Owned(v))
+            }
+            fn visit_borrowed_bytes<E: Error>(self, v: &'de [u8]) -> Result<Self::Value, E> {
+                std::str::from_utf8(v)
+                    .map(Cow::Borrowed)
+                    .map_err(|_| E::invalid_value(Unexpected::Bytes(v), &self))
+            }
+            fn visit_bytes<E: Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+                self.visit_byte_buf(v.to_vec())
+            }
+            fn visit_byte_buf<E: Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+                String::from_utf8(v)
+                    .map(Cow::Owned)
+                    .map_err(|e| E::invalid_value(Unexpected::Bytes(e.as_bytes()), &self))
+            }
+        }
+
+        deserializer.deserialize_str(Visit)
+    }
+}
+
+impl<'de: 'a, 'a> DeserializeAs<'de, Cow<'a, [u8]>> for Borrow {
+    fn deserialize_as<D: Deserializer<'de>>(deserializer: D) -> Result<Cow<'a, [u8]>, D::Error> {
+        struct Visit;
+        impl<'de> Visitor<'de> for Visit {
+            type Value = Cow<'de, [u8]>;
+
+            fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+                formatter.write_str("bytes")
+            }
+
+            fn visit_borrowed_bytes<E: Error>(self, v: &'de [u8]) -> Result<Self::Value, E> {
+                Ok(Cow::Borrowed(v))
+            }
+            fn visit_bytes<E: Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+                Ok(Cow::Owned(v.to_vec()))
+            }
+            fn visit_byte_buf<E: Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+                Ok(Cow::Owned(v))
+            }
+            fn visit_borrowed_str<E: Error>(self, v: &'de str) -> Result<Self::Value, E> {
+                Ok(Cow::Borrowed(v.as_bytes()))
+            }
+            fn visit_str<E: Error>(self, v: &str) -> Result<Self::Value, E> {
+                Ok(Cow::Owned(v.as_bytes().to_vec()))
+            }
+            fn visit_string<E: Error>(self, v: String) -> Result<Self::Value, E> {
+                Ok(Cow::Owned(v.into_bytes()))
+            }
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+                while let Some(byte) = seq.next_element()? {
+                    bytes.push(byte);
+                }
+                Ok(Cow::Owned(bytes))
+            }
+        }
+
+        deserializer.deserialize_bytes(Visit)
+    }
+}
+
+impl<'de, T> DeserializeAs<'de, Option<T>> for Borrow
+where
+    Borrow: DeserializeAs<'de, T>,
+{
+    fn deserialize_as<D: Deserializer<'de>>(deserializer: D) -> Result<Option<T>, D::Error> {
+        struct Visit<T>(PhantomData<T>);
+        impl<'de, T> Visitor<'de> for Visit<T>
+        where
+            Borrow: DeserializeAs<'de, T>,
+        {
+            type Value = Option<T>;
+
+            fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+                formatter.write_str("option")
+            }
+
+            fn visit_none<E: Error>(self) -> Result<Self::Value, E> {
+                Ok(None)
+            }
+            fn visit_unit<E: Error>(self) -> Result<Self::Value, E> {
+                Ok(None)
+            }
+            fn visit_some<D: Deserializer<'de>>(self, de: D) -> Result<Self::Value, D::Error> {
+                Borrow::deserialize_as(de).map(Some)
+            }
+        }
+
+        deserializer.deserialize_option(Visit(PhantomData))
+    }
+}
+
+// serializes a value through `Borrow`
+struct Borrowed<'a, T>(&'a T);
+
+impl<T> Serialize for Borrowed<'_, T>
+where
+    Borrow: SerializeAs<T>,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Borrow::serialize_as(self.0, serializer)
+    }
+}
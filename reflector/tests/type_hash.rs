@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+
+use reflector::{Introspect, TypeHash};
+
+mod a {
+    use reflector::Introspect;
+
+    #[derive(Introspect)]
+    #[reflect(type_hash)]
+    pub struct Server {
+        pub host: String,
+        pub port: u16,
+        pub inner: Inner,
+    }
+
+    #[derive(Introspect)]
+    #[reflect(type_hash)]
+    pub struct Inner {
+        pub tags: Vec<String>,
+    }
+}
+
+mod b {
+    use reflector::Introspect;
+
+    // as in `a`, but with different versions and representations
+    #[derive(Introspect)]
+    #[reflect(version = 1, type_hash)]
+    pub struct Server {
+        #[reflect(renamed_from = "hostname")]
+        pub host: String,
+        #[reflect(since = 1)]
+        pub port: u16,
+        #[reflect(flatten)]
+        pub inner: Inner,
+    }
+
+    #[derive(Introspect)]
+    #[reflect(type_hash)]
+    pub struct Inner {
+        pub tags: Vec<String>,
+    }
+}
+
+mod c {
+    use reflector::Introspect;
+
+    #[derive(Introspect)]
+    #[reflect(type_hash)]
+    pub struct Server {
+        pub host: String,
+        pub port: u16,
+        pub inner: Inner,
+    }
+
+    // a change nested within `Server`
+    #[derive(Introspect)]
+    #[reflect(type_hash)]
+    pub struct Inner {
+        pub tags: Vec<&'static str>,
+    }
+}
+
+#[test]
+fn structure() {
+    assert_eq!(a::Server::HASH, b::Server::HASH);
+    assert_ne!(a::Server::HASH, c::Server::HASH);
+    // unlike the fingerprint, which only refers to `Inner` by name
+    assert_eq!(a::Server::FINGERPRINT, c::Server::FINGERPRINT);
+
+    #[derive(Introspect)]
+    #[reflect(type_hash)]
+    struct Named {
+        x: u8,
+    }
+    #[derive(Introspect)]
+    #[reflect(type_hash)]
+    struct Renamed {
+        y: u8,
+    }
+    #[derive(Introspect)]
+    #[reflect(type_hash)]
+    struct Tuple(u8);
+    let hashes = [Named::HASH, Renamed::HASH, Tuple::HASH];
+    assert!(
+        hashes
+            .iter()
+            .all(|h| hashes.iter().filter(|x| *x == h).count() == 1)
+    );
+
+    // stable across compilations and platforms
+    assert_eq!(u8::HASH, 0x08c4_8207_b567_53d8);
+    assert_eq!(usize::HASH, 0xd978_be71_270b_16c1);
+}
+
+#[test]
+fn enums_and_generics() {
+    mod first {
+        #[derive(reflector::Introspect)]
+        #[reflect(type_hash)]
+        pub enum Mode {
+            Fast,
+            Slow { delay: u32 },
+        }
+    }
+    mod second {
+        #[derive(reflector::Introspect)]
+        #[reflect(type_hash)]
+        pub enum Mode {
+            Slow { delay: u32 },
+            Fast,
+        }
+    }
+    assert_ne!(first::Mode::HASH, second::Mode::HASH);
+
+    #[derive(Introspect)]
+    #[reflect(type_hash)]
+    struct Wrapper<T> {
+        value: T,
+    }
+    assert_ne!(Wrapper::<u8>::HASH, Wrapper::<u16>::HASH);
+    assert_ne!(Wrapper::<Vec<u8>>::HASH, Wrapper::<HashMap<u8, u8>>::HASH);
+}
+
+#[test]
+fn recursion_and_opaque() {
+    #[derive(Introspect)]
+    #[reflect(type_hash)]
+    enum Tree<T> {
+        Leaf(T),
+        Node(Vec<Tree<T>>),
+    }
+    assert_ne!(Tree::<u8>::HASH, Tree::<i8>::HASH);
+
+    struct Foreign;
+    #[derive(Introspect)]
+    #[reflect(type_hash)]
+    struct Holder {
+        #[reflect(opaque)]
+        foreign: Foreign,
+    }
+    #[derive(Introspect)]
+    #[reflect(type_hash)]
+    struct Other {
+        #[reflect(opaque)]
+        foreign: Option<Foreign>,
+    }
+    assert_ne!(Holder::HASH, Other::HASH);
+}
+
+#[test]
+fn opt_in() {
+    use std::num::NonZeroU32;
+    use std::time::SystemTime;
+
+    // neither the fields nor the type parameters need to implement `TypeHash` without
+    // `#[reflect(type_hash)]`
+    struct Foreign;
+    #[derive(Introspect)]
+    struct Plain<T> {
+        modified: SystemTime,
+        count: NonZeroU32,
+        value: T,
+    }
+    assert_eq!(Plain::<Foreign>::IDENT, "Plain");
+}